@@ -0,0 +1,196 @@
+/* SPDX-FileCopyrightText: Rodolphe Bréard */
+/* SPDX-License-Identifier: MIT OR Apache-2.0 */
+
+#ifndef COFFIO_H
+#define COFFIO_H
+
+/* This file is generated by cbindgen, do not edit it manually. See ffi/README.md. */
+
+#include <stdarg.h>
+#include <stdbool.h>
+#include <stddef.h>
+#include <stdint.h>
+#include <stdlib.h>
+
+// The operation succeeded.
+#define COFFIO_OK 0
+
+// A required pointer was NULL.
+#define COFFIO_ERR_NULL_POINTER -1
+
+// A string was not valid UTF-8.
+#define COFFIO_ERR_INVALID_UTF8 -2
+
+// An argument had an invalid value.
+#define COFFIO_ERR_INVALID_ARGUMENT -3
+
+// An unexpected internal error occurred.
+#define COFFIO_ERR_PANIC -4
+
+// Data context.
+typedef struct CoffioDataContext CoffioDataContext;
+
+// List of input key materials (IKM).
+typedef struct CoffioIkmList CoffioIkmList;
+
+// Key context.
+typedef struct CoffioKeyContext CoffioKeyContext;
+
+#ifdef __cplusplus
+extern "C" {
+#endif // __cplusplus
+
+// Import an IKM list from its exported representation.
+//
+// On success, the IKM list is written in `out` and must be released using
+// [coffio_ikm_list_free].
+//
+// # Safety
+//
+// `ikml` must be NULL or a valid NUL-terminated string and `out` must be NULL or a valid pointer.
+int32_t coffio_ikm_list_import(const char *ikml, struct CoffioIkmList **out);
+
+// Import an IKM list which has been signed, checking its signature against the verifying key
+// exported using the `ikml-vk-v1` format. Unlike [coffio_ikm_list_import], unsigned IKM lists and
+// IKM lists signed using another key are rejected.
+//
+// On success, the IKM list is written in `out` and must be released using
+// [coffio_ikm_list_free].
+//
+// # Safety
+//
+// `ikml` and `verifying_key` must be NULL or valid NUL-terminated strings and `out` must be NULL
+// or a valid pointer.
+int32_t coffio_ikm_list_import_signed(const char *ikml,
+                                      const char *verifying_key,
+                                      struct CoffioIkmList **out);
+
+// Release an IKM list. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `ikml` must be NULL or a pointer returned by [coffio_ikm_list_import] or
+// [coffio_ikm_list_import_signed] which has not already been released.
+void coffio_ikm_list_free(struct CoffioIkmList *ikml);
+
+// Create a key context from an array of `len` strings. The key context is periodic and uses the
+// default periodicity.
+//
+// On success, the key context is written in `out` and must be released using
+// [coffio_key_context_free].
+//
+// # Safety
+//
+// `elems` must be NULL or point to `len` valid NUL-terminated strings and `out` must be NULL or
+// a valid pointer.
+int32_t coffio_key_context_new(const char *const *elems, size_t len, struct CoffioKeyContext **out);
+
+// Set the key context as static, meaning the same key will be used regardless of the time.
+//
+// # Safety
+//
+// `ctx` must be NULL or a valid key context.
+int32_t coffio_key_context_set_static(struct CoffioKeyContext *ctx);
+
+// Set the number of seconds after which a new key is used. The periodicity must not be zero.
+//
+// # Safety
+//
+// `ctx` must be NULL or a valid key context.
+int32_t coffio_key_context_set_periodicity(struct CoffioKeyContext *ctx, uint64_t periodicity);
+
+// Release a key context. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `ctx` must be NULL or a pointer returned by [coffio_key_context_new] which has not already
+// been released.
+void coffio_key_context_free(struct CoffioKeyContext *ctx);
+
+// Create a data context from an array of `len` strings.
+//
+// On success, the data context is written in `out` and must be released using
+// [coffio_data_context_free].
+//
+// # Safety
+//
+// `elems` must be NULL or point to `len` valid NUL-terminated strings and `out` must be NULL or
+// a valid pointer.
+int32_t coffio_data_context_new(const char *const *elems,
+                                size_t len,
+                                struct CoffioDataContext **out);
+
+// Release a data context. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `ctx` must be NULL or a pointer returned by [coffio_data_context_new] which has not already
+// been released.
+void coffio_data_context_free(struct CoffioDataContext *ctx);
+
+// Encrypt `data_len` bytes of data using a key context and a data context. If the key is
+// periodic, use the current timestamp.
+//
+// On success, the encrypted data is written in `out` as a NUL-terminated string which must be
+// released using [coffio_string_free].
+//
+// # Safety
+//
+// `ikml`, `key_ctx` and `data_ctx` must be NULL or valid objects, `data` must be NULL or point
+// to `data_len` bytes and `out` must be NULL or a valid pointer. `data` may be NULL if
+// `data_len` is zero.
+int32_t coffio_encrypt(const struct CoffioIkmList *ikml,
+                       const struct CoffioKeyContext *key_ctx,
+                       const struct CoffioDataContext *data_ctx,
+                       const uint8_t *data,
+                       size_t data_len,
+                       char **out);
+
+// Decrypt data using a key context and a data context.
+//
+// On success, a pointer to the decrypted data is written in `out` and its length in `out_len`.
+// The decrypted data must be released using [coffio_bytes_free].
+//
+// # Safety
+//
+// `ikml`, `key_ctx` and `data_ctx` must be NULL or valid objects, `encrypted` must be NULL or a
+// valid NUL-terminated string and `out` and `out_len` must be NULL or valid pointers.
+int32_t coffio_decrypt(const struct CoffioIkmList *ikml,
+                       const struct CoffioKeyContext *key_ctx,
+                       const struct CoffioDataContext *data_ctx,
+                       const char *encrypted,
+                       uint8_t **out,
+                       size_t *out_len);
+
+// Release a string returned by the library. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `s` must be NULL or a string returned by the library which has not already been released.
+void coffio_string_free(char *s);
+
+// Release data returned by the library. Passing NULL is a no-op.
+//
+// # Safety
+//
+// `data` must be NULL or data returned by the library which has not already been released and
+// `len` must be its length.
+void coffio_bytes_free(uint8_t *data, size_t len);
+
+// Returns the message of the last error that occurred on the current thread, or NULL if the last
+// call succeeded.
+//
+// The string is owned by the library and remains valid until the next call on the same thread.
+const char *coffio_last_error_message(void);
+
+// Returns the string code of the last error that occurred on the current thread (see
+// `coffio::Error::get_code_str`), or NULL if the last call succeeded.
+//
+// The string is owned by the library and remains valid until the next call on the same thread.
+const char *coffio_last_error_code_str(void);
+
+#ifdef __cplusplus
+}  // extern "C"
+#endif  // __cplusplus
+
+#endif  /* COFFIO_H */
@@ -0,0 +1,597 @@
+#![warn(missing_docs)]
+
+//! C interface for [Coffio](https://docs.rs/coffio/), allowing services written in other
+//! languages to read and write the same encrypted data.
+//!
+//! The C header is available in `include/coffio.h`.
+//!
+//! # Conventions
+//!
+//! - Every function that may fail returns a status code: [COFFIO_OK] on success, the code of the
+//!   Coffio error (see `coffio::Error::get_code`) or one of the negative `COFFIO_ERR_*` codes
+//!   otherwise. The details of the last error that occurred on the current thread can be
+//!   retrieved using [coffio_last_error_message] and [coffio_last_error_code_str].
+//! - Results are written in output parameters, which are left untouched on failure.
+//! - Objects created by the library must be released using the matching `*_free` function.
+//! - Strings are NUL-terminated and must be valid UTF-8.
+
+use coffio::{Coffio, DataContext, Error, IkmListVerifyingKey, InputKeyMaterialList, KeyContext};
+use std::cell::RefCell;
+use std::ffi::{CStr, CString, c_char};
+use std::num::NonZeroU64;
+use std::panic::{UnwindSafe, catch_unwind};
+use std::ptr;
+
+/// The operation succeeded.
+pub const COFFIO_OK: i32 = 0;
+/// A required pointer was NULL.
+pub const COFFIO_ERR_NULL_POINTER: i32 = -1;
+/// A string was not valid UTF-8.
+pub const COFFIO_ERR_INVALID_UTF8: i32 = -2;
+/// An argument had an invalid value.
+pub const COFFIO_ERR_INVALID_ARGUMENT: i32 = -3;
+/// An unexpected internal error occurred.
+pub const COFFIO_ERR_PANIC: i32 = -4;
+
+/// List of input key materials (IKM).
+pub struct CoffioIkmList(InputKeyMaterialList);
+
+/// Key context.
+pub struct CoffioKeyContext(KeyContext);
+
+/// Data context.
+pub struct CoffioDataContext(DataContext);
+
+enum FfiError {
+	NullPointer,
+	InvalidUtf8,
+	InvalidArgument(&'static str),
+	Coffio(Error),
+	Panic,
+}
+
+impl FfiError {
+	fn get_code(&self) -> i32 {
+		match self {
+			Self::NullPointer => COFFIO_ERR_NULL_POINTER,
+			Self::InvalidUtf8 => COFFIO_ERR_INVALID_UTF8,
+			Self::InvalidArgument(_) => COFFIO_ERR_INVALID_ARGUMENT,
+			Self::Coffio(e) => e.get_code().into(),
+			Self::Panic => COFFIO_ERR_PANIC,
+		}
+	}
+
+	fn get_code_str(&self) -> &'static str {
+		match self {
+			Self::NullPointer => "null_pointer",
+			Self::InvalidUtf8 => "invalid_utf8",
+			Self::InvalidArgument(_) => "invalid_argument",
+			Self::Coffio(e) => e.get_code_str(),
+			Self::Panic => "panic",
+		}
+	}
+
+	fn get_message(&self) -> String {
+		match self {
+			Self::NullPointer => "null pointer".to_string(),
+			Self::InvalidUtf8 => "invalid UTF-8 string".to_string(),
+			Self::InvalidArgument(name) => format!("{name}: invalid argument"),
+			Self::Coffio(e) => e.to_string(),
+			Self::Panic => "unexpected internal error".to_string(),
+		}
+	}
+}
+
+impl From<Error> for FfiError {
+	fn from(error: Error) -> Self {
+		Self::Coffio(error)
+	}
+}
+
+struct LastError {
+	code_str: CString,
+	message: CString,
+}
+
+thread_local! {
+	static LAST_ERROR: RefCell<Option<LastError>> = const { RefCell::new(None) };
+}
+
+fn set_last_error(error: Option<&FfiError>) {
+	let last_error = error.map(|e| LastError {
+		code_str: to_c_string(e.get_code_str()),
+		message: to_c_string(&e.get_message()),
+	});
+	LAST_ERROR.with(|le| *le.borrow_mut() = last_error);
+}
+
+fn to_c_string(s: &str) -> CString {
+	CString::new(s.replace('\0', "")).unwrap_or_default()
+}
+
+fn run(f: impl FnOnce() -> Result<(), FfiError> + UnwindSafe) -> i32 {
+	let res = catch_unwind(f).unwrap_or(Err(FfiError::Panic));
+	set_last_error(res.as_ref().err());
+	match res {
+		Ok(()) => COFFIO_OK,
+		Err(e) => e.get_code(),
+	}
+}
+
+unsafe fn get_ref<'a, T>(p: *const T) -> Result<&'a T, FfiError> {
+	unsafe { p.as_ref() }.ok_or(FfiError::NullPointer)
+}
+
+unsafe fn get_mut<'a, T>(p: *mut T) -> Result<&'a mut T, FfiError> {
+	unsafe { p.as_mut() }.ok_or(FfiError::NullPointer)
+}
+
+unsafe fn get_str<'a>(s: *const c_char) -> Result<&'a str, FfiError> {
+	if s.is_null() {
+		return Err(FfiError::NullPointer);
+	}
+	unsafe { CStr::from_ptr(s) }
+		.to_str()
+		.map_err(|_| FfiError::InvalidUtf8)
+}
+
+unsafe fn get_bytes<'a>(data: *const u8, len: usize) -> Result<&'a [u8], FfiError> {
+	if len == 0 {
+		return Ok(&[]);
+	}
+	if data.is_null() {
+		return Err(FfiError::NullPointer);
+	}
+	Ok(unsafe { std::slice::from_raw_parts(data, len) })
+}
+
+unsafe fn get_str_list<'a>(
+	elems: *const *const c_char,
+	len: usize,
+) -> Result<Vec<&'a str>, FfiError> {
+	if len == 0 {
+		return Ok(Vec::new());
+	}
+	if elems.is_null() {
+		return Err(FfiError::NullPointer);
+	}
+	unsafe { std::slice::from_raw_parts(elems, len) }
+		.iter()
+		.map(|e| unsafe { get_str(*e) })
+		.collect()
+}
+
+unsafe fn write_out<T>(out: *mut T, value: T) -> Result<(), FfiError> {
+	if out.is_null() {
+		return Err(FfiError::NullPointer);
+	}
+	unsafe { out.write(value) };
+	Ok(())
+}
+
+/// Import an IKM list from its exported representation.
+///
+/// On success, the IKM list is written in `out` and must be released using
+/// [coffio_ikm_list_free].
+///
+/// # Safety
+///
+/// `ikml` must be NULL or a valid NUL-terminated string and `out` must be NULL or a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_ikm_list_import(
+	ikml: *const c_char,
+	out: *mut *mut CoffioIkmList,
+) -> i32 {
+	run(|| {
+		let ikml = unsafe { get_str(ikml) }?;
+		let lst = InputKeyMaterialList::import(ikml)?;
+		let lst = Box::into_raw(Box::new(CoffioIkmList(lst)));
+		unsafe { write_out(out, lst) }.inspect_err(|_| {
+			drop(unsafe { Box::from_raw(lst) });
+		})
+	})
+}
+
+/// Import an IKM list which has been signed, checking its signature against the verifying key
+/// exported using the `ikml-vk-v1` format. Unlike [coffio_ikm_list_import], unsigned IKM lists and
+/// IKM lists signed using another key are rejected.
+///
+/// On success, the IKM list is written in `out` and must be released using
+/// [coffio_ikm_list_free].
+///
+/// # Safety
+///
+/// `ikml` and `verifying_key` must be NULL or valid NUL-terminated strings and `out` must be NULL
+/// or a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_ikm_list_import_signed(
+	ikml: *const c_char,
+	verifying_key: *const c_char,
+	out: *mut *mut CoffioIkmList,
+) -> i32 {
+	run(|| {
+		let ikml = unsafe { get_str(ikml) }?;
+		let verifying_key = unsafe { get_str(verifying_key) }?;
+		let verifying_key = IkmListVerifyingKey::import(verifying_key)?;
+		let lst = InputKeyMaterialList::import_signed(ikml, &verifying_key)?;
+		let lst = Box::into_raw(Box::new(CoffioIkmList(lst)));
+		unsafe { write_out(out, lst) }.inspect_err(|_| {
+			drop(unsafe { Box::from_raw(lst) });
+		})
+	})
+}
+
+/// Release an IKM list. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `ikml` must be NULL or a pointer returned by [coffio_ikm_list_import] or
+/// [coffio_ikm_list_import_signed] which has not already been released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_ikm_list_free(ikml: *mut CoffioIkmList) {
+	if !ikml.is_null() {
+		drop(unsafe { Box::from_raw(ikml) });
+	}
+}
+
+/// Create a key context from an array of `len` strings. The key context is periodic and uses the
+/// default periodicity.
+///
+/// On success, the key context is written in `out` and must be released using
+/// [coffio_key_context_free].
+///
+/// # Safety
+///
+/// `elems` must be NULL or point to `len` valid NUL-terminated strings and `out` must be NULL or
+/// a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_key_context_new(
+	elems: *const *const c_char,
+	len: usize,
+	out: *mut *mut CoffioKeyContext,
+) -> i32 {
+	run(|| {
+		let elems = unsafe { get_str_list(elems, len) }?;
+		let ctx = Box::into_raw(Box::new(CoffioKeyContext(elems.as_slice().into())));
+		unsafe { write_out(out, ctx) }.inspect_err(|_| {
+			drop(unsafe { Box::from_raw(ctx) });
+		})
+	})
+}
+
+/// Set the key context as static, meaning the same key will be used regardless of the time.
+///
+/// # Safety
+///
+/// `ctx` must be NULL or a valid key context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_key_context_set_static(ctx: *mut CoffioKeyContext) -> i32 {
+	run(|| {
+		unsafe { get_mut(ctx) }?.0.set_static();
+		Ok(())
+	})
+}
+
+/// Set the number of seconds after which a new key is used. The periodicity must not be zero.
+///
+/// # Safety
+///
+/// `ctx` must be NULL or a valid key context.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_key_context_set_periodicity(
+	ctx: *mut CoffioKeyContext,
+	periodicity: u64,
+) -> i32 {
+	run(|| {
+		let ctx = unsafe { get_mut(ctx) }?;
+		let periodicity =
+			NonZeroU64::new(periodicity).ok_or(FfiError::InvalidArgument("periodicity"))?;
+		ctx.0.set_periodicity(periodicity);
+		Ok(())
+	})
+}
+
+/// Release a key context. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `ctx` must be NULL or a pointer returned by [coffio_key_context_new] which has not already
+/// been released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_key_context_free(ctx: *mut CoffioKeyContext) {
+	if !ctx.is_null() {
+		drop(unsafe { Box::from_raw(ctx) });
+	}
+}
+
+/// Create a data context from an array of `len` strings.
+///
+/// On success, the data context is written in `out` and must be released using
+/// [coffio_data_context_free].
+///
+/// # Safety
+///
+/// `elems` must be NULL or point to `len` valid NUL-terminated strings and `out` must be NULL or
+/// a valid pointer.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_data_context_new(
+	elems: *const *const c_char,
+	len: usize,
+	out: *mut *mut CoffioDataContext,
+) -> i32 {
+	run(|| {
+		let elems = unsafe { get_str_list(elems, len) }?;
+		let ctx = Box::into_raw(Box::new(CoffioDataContext(elems.as_slice().into())));
+		unsafe { write_out(out, ctx) }.inspect_err(|_| {
+			drop(unsafe { Box::from_raw(ctx) });
+		})
+	})
+}
+
+/// Release a data context. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `ctx` must be NULL or a pointer returned by [coffio_data_context_new] which has not already
+/// been released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_data_context_free(ctx: *mut CoffioDataContext) {
+	if !ctx.is_null() {
+		drop(unsafe { Box::from_raw(ctx) });
+	}
+}
+
+/// Encrypt `data_len` bytes of data using a key context and a data context. If the key is
+/// periodic, use the current timestamp.
+///
+/// On success, the encrypted data is written in `out` as a NUL-terminated string which must be
+/// released using [coffio_string_free].
+///
+/// # Safety
+///
+/// `ikml`, `key_ctx` and `data_ctx` must be NULL or valid objects, `data` must be NULL or point
+/// to `data_len` bytes and `out` must be NULL or a valid pointer. `data` may be NULL if
+/// `data_len` is zero.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_encrypt(
+	ikml: *const CoffioIkmList,
+	key_ctx: *const CoffioKeyContext,
+	data_ctx: *const CoffioDataContext,
+	data: *const u8,
+	data_len: usize,
+	out: *mut *mut c_char,
+) -> i32 {
+	run(|| {
+		let ikml = unsafe { get_ref(ikml) }?;
+		let key_ctx = unsafe { get_ref(key_ctx) }?;
+		let data_ctx = unsafe { get_ref(data_ctx) }?;
+		let data = unsafe { get_bytes(data, data_len) }?;
+		if out.is_null() {
+			return Err(FfiError::NullPointer);
+		}
+		let encrypted = Coffio::new(&ikml.0).encrypt(&key_ctx.0, &data_ctx.0, data)?;
+		let encrypted = to_c_string(&encrypted).into_raw();
+		unsafe { write_out(out, encrypted) }
+	})
+}
+
+/// Decrypt data using a key context and a data context.
+///
+/// On success, a pointer to the decrypted data is written in `out` and its length in `out_len`.
+/// The decrypted data must be released using [coffio_bytes_free].
+///
+/// # Safety
+///
+/// `ikml`, `key_ctx` and `data_ctx` must be NULL or valid objects, `encrypted` must be NULL or a
+/// valid NUL-terminated string and `out` and `out_len` must be NULL or valid pointers.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_decrypt(
+	ikml: *const CoffioIkmList,
+	key_ctx: *const CoffioKeyContext,
+	data_ctx: *const CoffioDataContext,
+	encrypted: *const c_char,
+	out: *mut *mut u8,
+	out_len: *mut usize,
+) -> i32 {
+	run(|| {
+		let ikml = unsafe { get_ref(ikml) }?;
+		let key_ctx = unsafe { get_ref(key_ctx) }?;
+		let data_ctx = unsafe { get_ref(data_ctx) }?;
+		let encrypted = unsafe { get_str(encrypted) }?;
+		if out.is_null() || out_len.is_null() {
+			return Err(FfiError::NullPointer);
+		}
+		let data = Coffio::new(&ikml.0).decrypt(&key_ctx.0, &data_ctx.0, encrypted)?;
+		let len = data.len();
+		let data = Box::into_raw(data.into_boxed_slice()) as *mut u8;
+		unsafe {
+			write_out(out, data)?;
+			write_out(out_len, len)
+		}
+	})
+}
+
+/// Release a string returned by the library. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `s` must be NULL or a string returned by the library which has not already been released.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_string_free(s: *mut c_char) {
+	if !s.is_null() {
+		drop(unsafe { CString::from_raw(s) });
+	}
+}
+
+/// Release data returned by the library. Passing NULL is a no-op.
+///
+/// # Safety
+///
+/// `data` must be NULL or data returned by the library which has not already been released and
+/// `len` must be its length.
+#[unsafe(no_mangle)]
+pub unsafe extern "C" fn coffio_bytes_free(data: *mut u8, len: usize) {
+	if !data.is_null() {
+		drop(unsafe { Box::from_raw(ptr::slice_from_raw_parts_mut(data, len)) });
+	}
+}
+
+/// Returns the message of the last error that occurred on the current thread, or NULL if the last
+/// call succeeded.
+///
+/// The string is owned by the library and remains valid until the next call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn coffio_last_error_message() -> *const c_char {
+	LAST_ERROR.with(|le| {
+		le.borrow()
+			.as_ref()
+			.map_or(ptr::null(), |e| e.message.as_ptr())
+	})
+}
+
+/// Returns the string code of the last error that occurred on the current thread (see
+/// `coffio::Error::get_code_str`), or NULL if the last call succeeded.
+///
+/// The string is owned by the library and remains valid until the next call on the same thread.
+#[unsafe(no_mangle)]
+pub extern "C" fn coffio_last_error_code_str() -> *const c_char {
+	LAST_ERROR.with(|le| {
+		le.borrow()
+			.as_ref()
+			.map_or(ptr::null(), |e| e.code_str.as_ptr())
+	})
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	const HEADER_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/include/coffio.h");
+	const TEST_RAW_IKML: &str = "ikml-v1:AQAAAA:AQAAAAEAAAC_vYEw1ujVG5i-CtoPYSzik_6xaAq59odjPm5ij01-e6zz4mUAAAAALJGBiwAAAAAA";
+	const TEST_SIGNED_RAW_IKML: &str = "ikml-sig-v1:QG0h7hQHigCTQGFeeImmdwqkJgeqNsj1ROW0C2AcXZ4Eyr-0WZdUF0btC6AlWJe-fx3UvHaNW6Z0IVapeSvzCg:ikml-v2:AQAAAAAAAAAAAAAA:AQEAAAABAAAAv72BMNbo1RuYvgraD2Es4pP-sWgKufaHYz5uYo9Nfnus8-JlAAAAACyRgYsAAAAAAA";
+	const TEST_VERIFYING_KEY: &str = "ikml-vk-v1:ikqbo0__36X7seCBYHHkdd96kJSrx-R65v8NnYjyDMk";
+	const TEST_OTHER_VERIFYING_KEY: &str = "ikml-vk-v1:uy1kjiGiQoAxJpFYzfSEB_8M7OCsk0HV7rl9kQaDyRs";
+
+	fn get_header() -> String {
+		let root = env!("CARGO_MANIFEST_DIR");
+		let config = cbindgen::Config::from_file(format!("{root}/cbindgen.toml")).unwrap();
+		let mut header = Vec::new();
+		cbindgen::Builder::new()
+			.with_config(config)
+			.with_src(format!("{root}/src/lib.rs"))
+			.generate()
+			.unwrap()
+			.write(&mut header);
+		String::from_utf8(header).unwrap()
+	}
+
+	fn get_last_error() -> Option<(String, String)> {
+		let code_str = coffio_last_error_code_str();
+		let message = coffio_last_error_message();
+		if code_str.is_null() || message.is_null() {
+			return None;
+		}
+		let to_string = |s| unsafe { CStr::from_ptr(s) }.to_str().unwrap().to_string();
+		Some((to_string(code_str), to_string(message)))
+	}
+
+	#[test]
+	#[ignore]
+	fn generate_header() {
+		std::fs::write(HEADER_PATH, get_header()).unwrap();
+	}
+
+	#[test]
+	fn header_up_to_date() {
+		let header = std::fs::read_to_string(HEADER_PATH).unwrap();
+		assert_eq!(header, get_header());
+	}
+
+	#[test]
+	fn null_pointers() {
+		let mut ikml = ptr::null_mut();
+		let code = unsafe { coffio_ikm_list_import(ptr::null(), &mut ikml) };
+		assert_eq!(code, COFFIO_ERR_NULL_POINTER);
+		assert!(ikml.is_null());
+		let raw_ikml = to_c_string(TEST_RAW_IKML);
+		let code = unsafe { coffio_ikm_list_import(raw_ikml.as_ptr(), ptr::null_mut()) };
+		assert_eq!(code, COFFIO_ERR_NULL_POINTER);
+		let code = unsafe { coffio_key_context_set_static(ptr::null_mut()) };
+		assert_eq!(code, COFFIO_ERR_NULL_POINTER);
+		let mut ctx = ptr::null_mut();
+		let code = unsafe { coffio_data_context_new(ptr::null(), 1, &mut ctx) };
+		assert_eq!(code, COFFIO_ERR_NULL_POINTER);
+		assert_eq!(
+			get_last_error(),
+			Some(("null_pointer".to_string(), "null pointer".to_string()))
+		);
+		let code = unsafe { coffio_data_context_new(ptr::null(), 0, &mut ctx) };
+		assert_eq!(code, COFFIO_OK);
+		assert_eq!(get_last_error(), None);
+		unsafe { coffio_data_context_free(ctx) };
+	}
+
+	#[test]
+	fn error_codes() {
+		let mut ikml = ptr::null_mut();
+		let raw_ikml = to_c_string("ikml-v1:AQAAAA:invalid");
+		let code = unsafe { coffio_ikm_list_import(raw_ikml.as_ptr(), &mut ikml) };
+		assert!(code > 0);
+		assert!(ikml.is_null());
+		let (code_str, _) = get_last_error().unwrap();
+		assert!(code_str.starts_with("parsing_"));
+
+		let mut ctx = ptr::null_mut();
+		let code = unsafe { coffio_key_context_new(ptr::null(), 0, &mut ctx) };
+		assert_eq!(code, COFFIO_OK);
+		let code = unsafe { coffio_key_context_set_periodicity(ctx, 0) };
+		assert_eq!(code, COFFIO_ERR_INVALID_ARGUMENT);
+		assert_eq!(
+			get_last_error(),
+			Some((
+				"invalid_argument".to_string(),
+				"periodicity: invalid argument".to_string()
+			))
+		);
+		unsafe { coffio_key_context_free(ctx) };
+	}
+
+	#[test]
+	fn import_signed() {
+		let import = |ikml: &str, verifying_key: &str| {
+			let ikml = to_c_string(ikml);
+			let verifying_key = to_c_string(verifying_key);
+			let mut lst = ptr::null_mut();
+			let code = unsafe {
+				coffio_ikm_list_import_signed(ikml.as_ptr(), verifying_key.as_ptr(), &mut lst)
+			};
+			unsafe { coffio_ikm_list_free(lst) };
+			code
+		};
+		let code = import(TEST_SIGNED_RAW_IKML, TEST_VERIFYING_KEY);
+		assert_eq!(code, COFFIO_OK);
+		let code = import(TEST_SIGNED_RAW_IKML, TEST_OTHER_VERIFYING_KEY);
+		assert_eq!(code, i32::from(Error::IkmListInvalidSignature.get_code()));
+		let code = import(TEST_RAW_IKML, TEST_VERIFYING_KEY);
+		assert!(code > 0);
+		let code = import(TEST_SIGNED_RAW_IKML, "ikml-vk-v1:invalid");
+		assert!(code > 0);
+		let (code_str, _) = get_last_error().unwrap();
+		assert!(code_str.starts_with("parsing_"));
+
+		let raw_ikml = to_c_string(TEST_SIGNED_RAW_IKML);
+		let mut lst = ptr::null_mut();
+		let code =
+			unsafe { coffio_ikm_list_import_signed(raw_ikml.as_ptr(), ptr::null(), &mut lst) };
+		assert_eq!(code, COFFIO_ERR_NULL_POINTER);
+		assert!(lst.is_null());
+	}
+
+	#[test]
+	fn invalid_utf8() {
+		let mut ikml = ptr::null_mut();
+		let raw_ikml = CString::new(vec![0xff, 0xfe]).unwrap();
+		let code = unsafe { coffio_ikm_list_import(raw_ikml.as_ptr(), &mut ikml) };
+		assert_eq!(code, COFFIO_ERR_INVALID_UTF8);
+	}
+}
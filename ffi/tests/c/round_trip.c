@@ -0,0 +1,88 @@
+/*
+ * Usage: round_trip <ikm list> <encrypted data>
+ *
+ * Decrypts the encrypted data and prints it on the first line, then encrypts a message and prints
+ * the encrypted data on the second line. Both use the key and data contexts below. Exits with a
+ * non-zero status if any check fails.
+ */
+
+#include <stdio.h>
+#include <stdlib.h>
+#include <string.h>
+
+#include "coffio.h"
+
+#define CHECK(cond)                                                           \
+	do {                                                                      \
+		if (!(cond)) {                                                        \
+			const char *msg = coffio_last_error_message();                    \
+			fprintf(stderr, "%s:%d: check failed: %s (last error: %s)\n",     \
+				__FILE__, __LINE__, #cond, msg ? msg : "none");               \
+			exit(1);                                                          \
+		}                                                                     \
+	} while (0)
+
+#define DECRYPTION_FAILED 300
+
+static const char *KEY_CTX[] = {"db_name", "table_name", "column_name"};
+static const char *DATA_CTX[] = {"018db876-3d9d-79af-9460-55d17da991d8"};
+static const char *OTHER_DATA_CTX[] = {"b3b21eb1-70d7-4dc6-9a2a-439e17d8491d"};
+static const char *MESSAGE = "Hello from C!";
+
+int main(int argc, char **argv)
+{
+	CoffioIkmList *ikml = NULL;
+	CoffioKeyContext *key_ctx = NULL;
+	CoffioDataContext *data_ctx = NULL;
+	CoffioDataContext *other_data_ctx = NULL;
+	char *encrypted = NULL;
+	uint8_t *decrypted = NULL;
+	size_t decrypted_len = 0;
+
+	if (argc != 3) {
+		fprintf(stderr, "usage: %s <ikm list> <encrypted data>\n", argv[0]);
+		return 2;
+	}
+
+	CHECK(coffio_ikm_list_import(argv[1], &ikml) == COFFIO_OK);
+	CHECK(coffio_last_error_message() == NULL);
+	CHECK(coffio_key_context_new(KEY_CTX, 3, &key_ctx) == COFFIO_OK);
+	CHECK(coffio_data_context_new(DATA_CTX, 1, &data_ctx) == COFFIO_OK);
+	CHECK(coffio_data_context_new(OTHER_DATA_CTX, 1, &other_data_ctx) == COFFIO_OK);
+
+	/* Data encrypted by the Rust API */
+	CHECK(coffio_decrypt(ikml, key_ctx, data_ctx, argv[2], &decrypted, &decrypted_len) == COFFIO_OK);
+	printf("%.*s\n", (int)decrypted_len, (const char *)decrypted);
+	coffio_bytes_free(decrypted, decrypted_len);
+
+	/* Round trip */
+	CHECK(coffio_encrypt(ikml, key_ctx, data_ctx, (const uint8_t *)MESSAGE, strlen(MESSAGE), &encrypted) == COFFIO_OK);
+	CHECK(coffio_decrypt(ikml, key_ctx, data_ctx, encrypted, &decrypted, &decrypted_len) == COFFIO_OK);
+	CHECK(decrypted_len == strlen(MESSAGE));
+	CHECK(memcmp(decrypted, MESSAGE, decrypted_len) == 0);
+	coffio_bytes_free(decrypted, decrypted_len);
+
+	/* Wrong data context */
+	decrypted = NULL;
+	CHECK(coffio_decrypt(ikml, key_ctx, other_data_ctx, encrypted, &decrypted, &decrypted_len) == DECRYPTION_FAILED);
+	CHECK(decrypted == NULL);
+	CHECK(strcmp(coffio_last_error_code_str(), "decryption_failed") == 0);
+	printf("%s\n", encrypted);
+	coffio_string_free(encrypted);
+
+	/* Empty data and invalid arguments */
+	CHECK(coffio_encrypt(ikml, key_ctx, data_ctx, NULL, 0, &encrypted) == COFFIO_OK);
+	CHECK(coffio_decrypt(ikml, key_ctx, data_ctx, encrypted, &decrypted, &decrypted_len) == COFFIO_OK);
+	CHECK(decrypted_len == 0);
+	coffio_bytes_free(decrypted, decrypted_len);
+	coffio_string_free(encrypted);
+	CHECK(coffio_encrypt(ikml, key_ctx, data_ctx, NULL, 1, &encrypted) == COFFIO_ERR_NULL_POINTER);
+	CHECK(coffio_key_context_set_periodicity(key_ctx, 0) == COFFIO_ERR_INVALID_ARGUMENT);
+	CHECK(strcmp(coffio_last_error_code_str(), "invalid_argument") == 0);
+
+	coffio_data_context_free(other_data_ctx);
+	coffio_data_context_free(data_ctx);
+	coffio_key_context_free(key_ctx);
+	coffio_ikm_list_free(ikml);
+	return 0;
+}
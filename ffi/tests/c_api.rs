@@ -0,0 +1,68 @@
+//! Build the C test program against the shared library and check that data encrypted by one side
+//! can be decrypted by the other one.
+
+#![cfg(unix)]
+
+use coffio::{Coffio, DataContext, InputKeyMaterialList, KeyContext};
+use std::path::{Path, PathBuf};
+use std::process::Command;
+
+const KEY_CTX: &[&str] = &["db_name", "table_name", "column_name"];
+const DATA_CTX: &[&str] = &["018db876-3d9d-79af-9460-55d17da991d8"];
+const RUST_MESSAGE: &str = "Hello from Rust!";
+const C_MESSAGE: &str = "Hello from C!";
+
+fn get_lib_dir() -> PathBuf {
+	// The test executable is located next to the library, in `<target dir>/<profile>/deps/`.
+	let exe = std::env::current_exe().unwrap();
+	exe.parent().unwrap().to_path_buf()
+}
+
+fn build_c_program(lib_dir: &Path) -> PathBuf {
+	let root = Path::new(env!("CARGO_MANIFEST_DIR"));
+	let output = lib_dir.join("coffio_c_round_trip");
+	let cc = std::env::var("CC").unwrap_or_else(|_| "cc".to_string());
+	let status = Command::new(cc)
+		.args(["-std=c99", "-Wall", "-Wextra", "-Werror", "-I"])
+		.arg(root.join("include"))
+		.arg(root.join("tests/c/round_trip.c"))
+		.arg("-L")
+		.arg(lib_dir)
+		.arg(format!("-Wl,-rpath,{}", lib_dir.display()))
+		.arg("-lcoffio_ffi")
+		.arg("-o")
+		.arg(&output)
+		.status()
+		.expect("unable to run the C compiler");
+	assert!(status.success(), "unable to build the C test program");
+	output
+}
+
+#[test]
+fn c_round_trip() {
+	let mut ikml = InputKeyMaterialList::new();
+	ikml.add_ikm().unwrap();
+	let raw_ikml = ikml.export().unwrap();
+	let key_ctx: KeyContext = KEY_CTX.into();
+	let data_ctx: DataContext = DATA_CTX.into();
+	let coffio = Coffio::new(&ikml);
+	let encrypted = coffio.encrypt(&key_ctx, &data_ctx, RUST_MESSAGE).unwrap();
+
+	let program = build_c_program(&get_lib_dir());
+	let output = Command::new(program)
+		.arg(&raw_ikml)
+		.arg(&encrypted)
+		.output()
+		.unwrap();
+	assert!(
+		output.status.success(),
+		"{}",
+		String::from_utf8_lossy(&output.stderr)
+	);
+	let stdout = String::from_utf8(output.stdout).unwrap();
+	let lines: Vec<&str> = stdout.lines().collect();
+	assert_eq!(lines.len(), 2);
+	assert_eq!(lines[0], RUST_MESSAGE);
+	let decrypted = coffio.decrypt(&key_ctx, &data_ctx, lines[1]).unwrap();
+	assert_eq!(decrypted, C_MESSAGE.as_bytes());
+}
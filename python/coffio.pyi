@@ -0,0 +1,70 @@
+from datetime import timedelta
+from enum import Enum
+from typing import List, Optional
+
+__version__: str
+DEFAULT_KEY_CTX_PERIODICITY: int
+
+class CoffioError(Exception):
+    code: int
+    code_str: str
+    kind: str
+
+class ParseError(CoffioError): ...
+class PolicyError(CoffioError): ...
+class IntegrityError(CoffioError): ...
+class KeyUnavailableError(CoffioError): ...
+class InvalidOperationError(CoffioError): ...
+class SystemFailureError(CoffioError): ...
+
+class InputKeyMaterialList:
+    generation: int
+    fingerprint: str
+    ikm_ids: List[int]
+    def __init__(self) -> None: ...
+    @staticmethod
+    def import_(s: str, verifying_key: Optional[str] = None) -> InputKeyMaterialList: ...
+    def export(self) -> str: ...
+    def add_ikm(self) -> int: ...
+    def delete_ikm(self, id: int) -> int: ...
+    def revoke_ikm(self, id: int) -> int: ...
+    def reinstate_ikm(self, id: int, reason: str) -> int: ...
+    def schedule_ikm_rotation(self, interval: timedelta, horizon: timedelta) -> List[int]: ...
+    def __len__(self) -> int: ...
+
+class DecryptionPolicyAction(Enum):
+    Allow = ...
+    Deny = ...
+    Warn = ...
+
+class DecryptionPolicy:
+    def __init__(self) -> None: ...
+    def set_early_enc(self, action: DecryptionPolicyAction) -> None: ...
+    def set_expired_enc(self, action: DecryptionPolicyAction) -> None: ...
+    def set_expired_now(self, action: DecryptionPolicyAction) -> None: ...
+    def set_future_enc(self, action: DecryptionPolicyAction) -> None: ...
+    def set_pending(self, action: DecryptionPolicyAction) -> None: ...
+    def set_revoked(self, action: DecryptionPolicyAction) -> None: ...
+    def set_revoked_enc_before(self, action: DecryptionPolicyAction) -> None: ...
+    def set_revoked_enc_after(self, action: DecryptionPolicyAction) -> None: ...
+
+class Coffio:
+    def __init__(
+        self,
+        ikm_list: InputKeyMaterialList,
+        decryption_policy: Optional[DecryptionPolicy] = None,
+    ) -> None: ...
+    def encrypt(
+        self,
+        key_context: List[str],
+        data_context: List[str],
+        data: bytes,
+        key_periodicity: Optional[int] = DEFAULT_KEY_CTX_PERIODICITY,
+    ) -> str: ...
+    def decrypt(
+        self,
+        key_context: List[str],
+        data_context: List[str],
+        encrypted_data: str,
+        key_periodicity: Optional[int] = DEFAULT_KEY_CTX_PERIODICITY,
+    ) -> bytes: ...
@@ -0,0 +1,366 @@
+//! Python bindings for [Coffio](https://docs.rs/coffio/).
+//!
+//! The Python interface is described in `coffio.pyi`.
+
+use coffio::{
+	DEFAULT_KEY_CTX_PERIODICITY, DEFAULT_SCHEME, DataContext, DecryptionPolicy,
+	DecryptionPolicyAction, ErrorKind, Generation, IkmId, IkmListVerifyingKey,
+	InputKeyMaterialList, KeyContext,
+};
+use pyo3::create_exception;
+use pyo3::exceptions::{PyException, PyValueError};
+use pyo3::prelude::*;
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+create_exception!(
+	coffio,
+	CoffioError,
+	PyException,
+	"Base class of every error raised by Coffio."
+);
+create_exception!(
+	coffio,
+	ParseError,
+	CoffioError,
+	"Invalid encoded data, such as an IKM list or encrypted data."
+);
+create_exception!(
+	coffio,
+	PolicyError,
+	CoffioError,
+	"Operation refused by the decryption policy or the encryption budget."
+);
+create_exception!(
+	coffio,
+	IntegrityError,
+	CoffioError,
+	"Data that has been altered or that does not match the key or data context."
+);
+create_exception!(
+	coffio,
+	KeyUnavailableError,
+	CoffioError,
+	"No suitable IKM is available."
+);
+create_exception!(
+	coffio,
+	InvalidOperationError,
+	CoffioError,
+	"Operation that cannot be applied to the IKM list."
+);
+create_exception!(
+	coffio,
+	SystemFailureError,
+	CoffioError,
+	"Failure of the underlying system, such as the random source or the clock."
+);
+
+fn to_py_err(error: coffio::Error) -> PyErr {
+	let message = error.to_string();
+	let err = match error.get_kind() {
+		ErrorKind::Parse => ParseError::new_err(message),
+		ErrorKind::Policy => PolicyError::new_err(message),
+		ErrorKind::Integrity => IntegrityError::new_err(message),
+		ErrorKind::KeyUnavailable => KeyUnavailableError::new_err(message),
+		ErrorKind::InvalidOperation => InvalidOperationError::new_err(message),
+		ErrorKind::System => SystemFailureError::new_err(message),
+		_ => CoffioError::new_err(message),
+	};
+	Python::attach(|py| {
+		let value = err.value(py);
+		let _ = value.setattr("code", error.get_code());
+		let _ = value.setattr("code_str", error.get_code_str());
+		let _ = value.setattr("kind", error.get_kind().as_str());
+	});
+	err
+}
+
+/// List of input key materials (IKM).
+#[pyclass(name = "InputKeyMaterialList", module = "coffio")]
+#[derive(Default)]
+struct PyInputKeyMaterialList {
+	inner: InputKeyMaterialList,
+}
+
+#[pymethods]
+impl PyInputKeyMaterialList {
+	/// Create a new empty IKM list.
+	#[new]
+	fn new() -> Self {
+		Self::default()
+	}
+
+	/// Import an IKM list from its exported representation. If a verifying key is specified, the
+	/// IKM list must have been signed using the associated signing key, otherwise signatures are
+	/// never verified.
+	#[staticmethod]
+	#[pyo3(name = "import_", signature = (s, verifying_key = None))]
+	fn import(s: &str, verifying_key: Option<&str>) -> PyResult<Self> {
+		let inner = match verifying_key {
+			Some(key) => {
+				let key = IkmListVerifyingKey::import(key).map_err(to_py_err)?;
+				InputKeyMaterialList::import_signed(s, &key)
+			}
+			None => InputKeyMaterialList::import(s),
+		}
+		.map_err(to_py_err)?;
+		Ok(Self { inner })
+	}
+
+	/// Export the IKM list to a string.
+	fn export(&self) -> PyResult<String> {
+		self.inner.export().map_err(to_py_err)
+	}
+
+	/// Add a new random IKM using the default scheme and return its identifier.
+	fn add_ikm(&mut self) -> PyResult<IkmId> {
+		self.inner.add_ikm().map_err(to_py_err)
+	}
+
+	/// Delete an IKM from the list.
+	fn delete_ikm(&mut self, id: IkmId) -> PyResult<IkmId> {
+		self.inner.delete_ikm(id).map_err(to_py_err)
+	}
+
+	/// Revoke an IKM.
+	fn revoke_ikm(&mut self, id: IkmId) -> PyResult<IkmId> {
+		self.inner.revoke_ikm(id).map_err(to_py_err)
+	}
+
+	/// Reinstate a revoked IKM. The reason is recorded in the IKM list's journal.
+	fn reinstate_ikm(&mut self, id: IkmId, reason: &str) -> PyResult<IkmId> {
+		self.inner.reinstate_ikm(id, reason).map_err(to_py_err)
+	}
+
+	/// Add IKMs using the default scheme so that a new one becomes valid every `interval` until
+	/// `horizon` is reached. Returns the identifiers of the added IKMs.
+	fn schedule_ikm_rotation(
+		&mut self,
+		interval: Duration,
+		horizon: Duration,
+	) -> PyResult<Vec<IkmId>> {
+		self.inner
+			.schedule_ikm_rotation(DEFAULT_SCHEME, interval, horizon)
+			.map_err(to_py_err)
+	}
+
+	/// Generation of the IKM list. Once modified, a list has the generation it had when it was
+	/// imported, incremented by one.
+	#[getter]
+	fn generation(&self) -> Generation {
+		self.inner.get_generation()
+	}
+
+	/// Fingerprint of the IKM list, which does not contain any secret.
+	#[getter]
+	fn fingerprint(&self) -> String {
+		self.inner.get_fingerprint()
+	}
+
+	/// Identifiers of the IKMs in the list.
+	#[getter]
+	fn ikm_ids(&self) -> Vec<IkmId> {
+		self.inner.iter().map(|ikm| ikm.get_id()).collect()
+	}
+
+	fn __len__(&self) -> usize {
+		self.inner.len()
+	}
+}
+
+/// Action taken when a decryption policy rule is triggered.
+#[pyclass(name = "DecryptionPolicyAction", module = "coffio", eq, eq_int)]
+#[derive(Clone, Copy, PartialEq)]
+enum PyDecryptionPolicyAction {
+	/// Allow decryption.
+	Allow,
+	/// Deny decryption and raise an error.
+	Deny,
+	/// Allow decryption and emit a warning.
+	Warn,
+}
+
+impl From<PyDecryptionPolicyAction> for DecryptionPolicyAction {
+	fn from(action: PyDecryptionPolicyAction) -> Self {
+		match action {
+			PyDecryptionPolicyAction::Allow => Self::Allow,
+			PyDecryptionPolicyAction::Deny => Self::Deny,
+			PyDecryptionPolicyAction::Warn => Self::Warn,
+		}
+	}
+}
+
+/// Policy defining whether or not data may be decrypted.
+#[pyclass(name = "DecryptionPolicy", module = "coffio")]
+#[derive(Default)]
+struct PyDecryptionPolicy {
+	inner: DecryptionPolicy,
+}
+
+#[pymethods]
+impl PyDecryptionPolicy {
+	/// Create a new policy using the default actions.
+	#[new]
+	fn new() -> Self {
+		Self::default()
+	}
+
+	/// Set the action for an IKM which has been used before its validity period.
+	/// Default value is deny.
+	fn set_early_enc(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_early_enc(action.into());
+	}
+
+	/// Set the action for an IKM which was expired when the encryption took place.
+	/// Default value is deny.
+	fn set_expired_enc(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_expired_enc(action.into());
+	}
+
+	/// Set the action for a now expired IKM.
+	/// Default value is warn.
+	fn set_expired_now(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_expired_now(action.into());
+	}
+
+	/// Set the action for data previously encrypted using a time period located in the future.
+	/// Default value is deny.
+	fn set_future_enc(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_future_enc(action.into());
+	}
+
+	/// Set the action for an IKM which is still pending activation.
+	/// Default value is warn.
+	fn set_pending(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_pending(action.into());
+	}
+
+	/// Set the action for a revoked IKM when it is not possible to know whether the data has been
+	/// encrypted before or after the revocation.
+	/// Default value is warn.
+	fn set_revoked(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_revoked(action.into());
+	}
+
+	/// Set the action for a revoked IKM which has been used before its revocation date.
+	/// Default value is the action set for a revoked IKM.
+	fn set_revoked_enc_before(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_revoked_enc_before(action.into());
+	}
+
+	/// Set the action for a revoked IKM which has been used after its revocation date.
+	/// Default value is the action set for a revoked IKM.
+	fn set_revoked_enc_after(&mut self, action: PyDecryptionPolicyAction) {
+		self.inner.set_revoked_enc_after(action.into());
+	}
+}
+
+fn get_key_context(elems: &[String], periodicity: Option<u64>) -> PyResult<KeyContext> {
+	let elems: Vec<&str> = elems.iter().map(String::as_str).collect();
+	let mut ctx: KeyContext = elems.as_slice().into();
+	match periodicity {
+		Some(periodicity) => {
+			let periodicity = NonZeroU64::new(periodicity)
+				.ok_or_else(|| PyValueError::new_err("the key periodicity must not be zero"))?;
+			ctx.set_periodicity(periodicity);
+		}
+		None => ctx.set_static(),
+	}
+	Ok(ctx)
+}
+
+fn get_data_context(elems: &[String]) -> DataContext {
+	let elems: Vec<&str> = elems.iter().map(String::as_str).collect();
+	elems.as_slice().into()
+}
+
+/// Encrypt and decrypt data using the IKMs of an IKM list.
+///
+/// Modifications of the IKM list are taken into account by subsequent operations.
+#[pyclass(name = "Coffio", module = "coffio")]
+struct PyCoffio {
+	ikm_list: Py<PyInputKeyMaterialList>,
+	decryption_policy: DecryptionPolicy,
+}
+
+#[pymethods]
+impl PyCoffio {
+	/// Create a new instance using an IKM list and, optionally, a custom decryption policy.
+	#[new]
+	#[pyo3(signature = (ikm_list, decryption_policy = None))]
+	fn new(
+		ikm_list: Py<PyInputKeyMaterialList>,
+		decryption_policy: Option<PyRef<'_, PyDecryptionPolicy>>,
+	) -> Self {
+		Self {
+			ikm_list,
+			decryption_policy: decryption_policy.map(|p| p.inner).unwrap_or_default(),
+		}
+	}
+
+	/// Encrypt data using a key context and a data context, both being lists of strings. If the
+	/// key periodicity is `None`, the key context is static. Otherwise, the key changes every
+	/// `key_periodicity` seconds.
+	#[pyo3(signature = (key_context, data_context, data, key_periodicity = Some(DEFAULT_KEY_CTX_PERIODICITY)))]
+	fn encrypt(
+		&self,
+		py: Python<'_>,
+		key_context: Vec<String>,
+		data_context: Vec<String>,
+		data: &[u8],
+		key_periodicity: Option<u64>,
+	) -> PyResult<String> {
+		let key_ctx = get_key_context(&key_context, key_periodicity)?;
+		let data_ctx = get_data_context(&data_context);
+		let ikm_list = self.ikm_list.borrow(py);
+		coffio::Coffio::with_decryption_policy(&ikm_list.inner, &self.decryption_policy)
+			.encrypt(&key_ctx, &data_ctx, data)
+			.map_err(to_py_err)
+	}
+
+	/// Decrypt data using a key context and a data context, both being lists of strings. The key
+	/// periodicity must be the one used for the encryption, otherwise the decryption policy
+	/// checks depending on the time period are not reliable.
+	#[pyo3(signature = (key_context, data_context, encrypted_data, key_periodicity = Some(DEFAULT_KEY_CTX_PERIODICITY)))]
+	fn decrypt(
+		&self,
+		py: Python<'_>,
+		key_context: Vec<String>,
+		data_context: Vec<String>,
+		encrypted_data: &str,
+		key_periodicity: Option<u64>,
+	) -> PyResult<Vec<u8>> {
+		let key_ctx = get_key_context(&key_context, key_periodicity)?;
+		let data_ctx = get_data_context(&data_context);
+		let ikm_list = self.ikm_list.borrow(py);
+		coffio::Coffio::with_decryption_policy(&ikm_list.inner, &self.decryption_policy)
+			.decrypt(&key_ctx, &data_ctx, encrypted_data)
+			.map_err(to_py_err)
+	}
+}
+
+/// Abstraction layer for symmetric data encryption, primarily designed for database column
+/// encryption.
+#[pymodule]
+#[pyo3(name = "coffio")]
+fn coffio_module(m: &Bound<'_, PyModule>) -> PyResult<()> {
+	let py = m.py();
+	m.add("__version__", env!("CARGO_PKG_VERSION"))?;
+	m.add("DEFAULT_KEY_CTX_PERIODICITY", DEFAULT_KEY_CTX_PERIODICITY)?;
+	m.add_class::<PyInputKeyMaterialList>()?;
+	m.add_class::<PyDecryptionPolicyAction>()?;
+	m.add_class::<PyDecryptionPolicy>()?;
+	m.add_class::<PyCoffio>()?;
+	m.add("CoffioError", py.get_type::<CoffioError>())?;
+	m.add("ParseError", py.get_type::<ParseError>())?;
+	m.add("PolicyError", py.get_type::<PolicyError>())?;
+	m.add("IntegrityError", py.get_type::<IntegrityError>())?;
+	m.add("KeyUnavailableError", py.get_type::<KeyUnavailableError>())?;
+	m.add(
+		"InvalidOperationError",
+		py.get_type::<InvalidOperationError>(),
+	)?;
+	m.add("SystemFailureError", py.get_type::<SystemFailureError>())?;
+	Ok(())
+}
@@ -0,0 +1,146 @@
+import unittest
+from datetime import timedelta
+
+import coffio
+
+TEST_RAW_IKML = "ikml-v1:AQAAAA:AQAAAAEAAAC_vYEw1ujVG5i-CtoPYSzik_6xaAq59odjPm5ij01-e6zz4mUAAAAALJGBiwAAAAAA"
+TEST_SIGNED_RAW_IKML = "ikml-sig-v1:QG0h7hQHigCTQGFeeImmdwqkJgeqNsj1ROW0C2AcXZ4Eyr-0WZdUF0btC6AlWJe-fx3UvHaNW6Z0IVapeSvzCg:ikml-v2:AQAAAAAAAAAAAAAA:AQEAAAABAAAAv72BMNbo1RuYvgraD2Es4pP-sWgKufaHYz5uYo9Nfnus8-JlAAAAACyRgYsAAAAAAA"
+TEST_VERIFYING_KEY = "ikml-vk-v1:ikqbo0__36X7seCBYHHkdd96kJSrx-R65v8NnYjyDMk"
+TEST_OTHER_VERIFYING_KEY = "ikml-vk-v1:uy1kjiGiQoAxJpFYzfSEB_8M7OCsk0HV7rl9kQaDyRs"
+TEST_CIPHERTEXT = "enc-v1:AQAAAA:qpVDbGvu0wl2tQgfF5jngCWCoCq5d9gj:eTkOSKz9YyvJE8PyT1lAFn4hyeK_0l6tWU4yyHA-7WRCJ9G-HWNpqoKBxg:NgAAAAAAAAA"
+TEST_DATA = b"Lorem ipsum dolor sit amet."
+TEST_KEY_CTX = ["db_name", "table_name", "column_name"]
+TEST_DATA_CTX = ["018db876-3d9d-79af-9460-55d17da991d8"]
+
+
+def get_ikm_list():
+    ikml = coffio.InputKeyMaterialList()
+    ikml.add_ikm()
+    return ikml
+
+
+class TestInputKeyMaterialList(unittest.TestCase):
+    def test_import_export(self):
+        ikml = coffio.InputKeyMaterialList.import_(TEST_RAW_IKML)
+        self.assertEqual(len(ikml), 1)
+        self.assertEqual(ikml.ikm_ids, [1])
+        ikml2 = coffio.InputKeyMaterialList.import_(ikml.export())
+        self.assertEqual(ikml.fingerprint, ikml2.fingerprint)
+
+    def test_import_signed(self):
+        ikml = coffio.InputKeyMaterialList.import_(
+            TEST_SIGNED_RAW_IKML, verifying_key=TEST_VERIFYING_KEY
+        )
+        self.assertEqual(ikml.ikm_ids, [1])
+        with self.assertRaises(coffio.IntegrityError):
+            coffio.InputKeyMaterialList.import_(
+                TEST_SIGNED_RAW_IKML, verifying_key=TEST_OTHER_VERIFYING_KEY
+            )
+        with self.assertRaises(coffio.CoffioError):
+            coffio.InputKeyMaterialList.import_(
+                TEST_RAW_IKML, verifying_key=TEST_VERIFYING_KEY
+            )
+        with self.assertRaises(coffio.ParseError):
+            coffio.InputKeyMaterialList.import_(TEST_SIGNED_RAW_IKML)
+
+    def test_invalid_import(self):
+        with self.assertRaises(coffio.ParseError) as cm:
+            coffio.InputKeyMaterialList.import_("ikml-v1:invalid")
+        self.assertIsInstance(cm.exception, coffio.CoffioError)
+        self.assertEqual(cm.exception.kind, "parse")
+        self.assertTrue(cm.exception.code_str.startswith("parsing_"))
+
+    def test_management(self):
+        ikml = coffio.InputKeyMaterialList()
+        self.assertEqual(len(ikml), 0)
+        self.assertEqual(ikml.add_ikm(), 1)
+        self.assertEqual(ikml.add_ikm(), 2)
+        ikml = coffio.InputKeyMaterialList.import_(ikml.export())
+        generation = ikml.generation
+        fingerprint = ikml.fingerprint
+        self.assertEqual(ikml.revoke_ikm(1), 1)
+        self.assertEqual(ikml.generation, generation + 1)
+        self.assertNotEqual(ikml.fingerprint, fingerprint)
+        self.assertEqual(ikml.reinstate_ikm(1, "revoked by mistake"), 1)
+        self.assertEqual(ikml.delete_ikm(2), 2)
+        self.assertEqual(ikml.ikm_ids, [1])
+        with self.assertRaises(coffio.KeyUnavailableError):
+            ikml.delete_ikm(42)
+
+    def test_schedule_ikm_rotation(self):
+        ikml = coffio.InputKeyMaterialList()
+        day = timedelta(days=1)
+        self.assertEqual(len(ikml.schedule_ikm_rotation(30 * day, 60 * day)), 3)
+        self.assertEqual(ikml.schedule_ikm_rotation(30 * day, 60 * day), [])
+
+
+class TestCoffio(unittest.TestCase):
+    def test_reference_ciphertext(self):
+        ikml = coffio.InputKeyMaterialList.import_(TEST_RAW_IKML)
+        cb = coffio.Coffio(ikml)
+        self.assertEqual(cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_CIPHERTEXT), TEST_DATA)
+
+    def test_round_trip(self):
+        cb = coffio.Coffio(get_ikm_list())
+        for periodicity in [coffio.DEFAULT_KEY_CTX_PERIODICITY, 3600, None]:
+            encrypted = cb.encrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_DATA, periodicity)
+            decrypted = cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, encrypted, periodicity)
+            self.assertEqual(decrypted, TEST_DATA)
+        encrypted = cb.encrypt([], [], b"")
+        self.assertEqual(cb.decrypt([], [], encrypted), b"")
+
+    def test_invalid_context(self):
+        cb = coffio.Coffio(get_ikm_list())
+        encrypted = cb.encrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_DATA)
+        with self.assertRaises(coffio.IntegrityError) as cm:
+            cb.decrypt(TEST_KEY_CTX, ["invalid", "data", "context"], encrypted)
+        self.assertEqual(cm.exception.code, 300)
+        self.assertEqual(cm.exception.code_str, "decryption_failed")
+        with self.assertRaises(coffio.IntegrityError):
+            cb.decrypt(["invalid", "key", "context"], TEST_DATA_CTX, encrypted)
+
+    def test_invalid_periodicity(self):
+        cb = coffio.Coffio(get_ikm_list())
+        with self.assertRaises(ValueError):
+            cb.encrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_DATA, 0)
+
+    def test_no_ikm(self):
+        cb = coffio.Coffio(coffio.InputKeyMaterialList())
+        with self.assertRaises(coffio.KeyUnavailableError):
+            cb.encrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_DATA)
+
+    def test_shared_ikm_list(self):
+        ikml = coffio.InputKeyMaterialList()
+        cb = coffio.Coffio(ikml)
+        ikml.add_ikm()
+        encrypted = cb.encrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_DATA)
+        self.assertEqual(cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, encrypted), TEST_DATA)
+
+    def test_decryption_policy(self):
+        ikml = get_ikm_list()
+        encrypted = coffio.Coffio(ikml).encrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_DATA)
+        ikml.revoke_ikm(1)
+        cb = coffio.Coffio(ikml)
+        self.assertEqual(cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, encrypted), TEST_DATA)
+        policy = coffio.DecryptionPolicy()
+        policy.set_revoked(coffio.DecryptionPolicyAction.Deny)
+        cb = coffio.Coffio(ikml, policy)
+        with self.assertRaises(coffio.PolicyError) as cm:
+            cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, encrypted)
+        self.assertEqual(cm.exception.kind, "policy")
+
+    def test_decryption_policy_time_period(self):
+        ikml = coffio.InputKeyMaterialList.import_(TEST_RAW_IKML)
+        ikml.revoke_ikm(1)
+        policy = coffio.DecryptionPolicy()
+        policy.set_revoked_enc_before(coffio.DecryptionPolicyAction.Deny)
+        cb = coffio.Coffio(ikml, policy)
+        with self.assertRaises(coffio.PolicyError) as cm:
+            cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_CIPHERTEXT)
+        self.assertEqual(cm.exception.code_str, "policy_encrypted_before_revocation")
+        decrypted = cb.decrypt(TEST_KEY_CTX, TEST_DATA_CTX, TEST_CIPHERTEXT, None)
+        self.assertEqual(decrypted, TEST_DATA)
+
+
+if __name__ == "__main__":
+    unittest.main()
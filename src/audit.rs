@@ -0,0 +1,114 @@
+use crate::canonicalization::canonicalize;
+use crate::context::KeyContext;
+use crate::error::Error;
+use crate::ikm::IkmId;
+use crate::policy::PolicyDecision;
+use alloc::string::String;
+use alloc::vec::Vec;
+
+/// Operation performed by [Coffio][crate::Coffio].
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum AuditOperation {
+	/// Data has been encrypted.
+	Encrypt,
+	/// Data has been decrypted.
+	Decrypt,
+}
+
+/// Non-secret metadata about an operation performed by [Coffio][crate::Coffio].
+pub struct AuditEvent<'a> {
+	pub(crate) operation: AuditOperation,
+	pub(crate) key_context: &'a KeyContext,
+	pub(crate) ikm_id: Option<IkmId>,
+	pub(crate) time_period: Option<u64>,
+	pub(crate) policy_decisions: Vec<PolicyDecision>,
+	pub(crate) error: Option<&'a Error>,
+}
+
+impl<'a> AuditEvent<'a> {
+	pub(crate) fn new(operation: AuditOperation, key_context: &'a KeyContext) -> Self {
+		Self {
+			operation,
+			key_context,
+			ikm_id: None,
+			time_period: None,
+			policy_decisions: Vec::new(),
+			error: None,
+		}
+	}
+
+	/// Returns the operation that has been performed.
+	pub fn get_operation(&self) -> AuditOperation {
+		self.operation
+	}
+
+	/// Returns the canonicalized representation of the key context.
+	pub fn get_key_context(&self) -> String {
+		canonicalize(&self.key_context.get_ctx_elems(None))
+	}
+
+	/// Returns the identifier of the IKM used by the operation. This identifier is not available
+	/// if the operation failed before the IKM could be determined.
+	pub fn get_ikm_id(&self) -> Option<IkmId> {
+		self.ikm_id
+	}
+
+	/// Returns the time period used by the operation, if the key context is periodic.
+	pub fn get_time_period(&self) -> Option<u64> {
+		self.time_period
+	}
+
+	/// Returns the decryption policy rules that have been triggered, along with the action taken
+	/// for each of them. This list is always empty for encryptions.
+	pub fn get_policy_decisions(&self) -> &[PolicyDecision] {
+		&self.policy_decisions
+	}
+
+	/// Returns whether or not the operation succeeded.
+	pub fn is_success(&self) -> bool {
+		self.error.is_none()
+	}
+
+	/// Returns the error which made the operation fail, if any.
+	pub fn get_error(&self) -> Option<&Error> {
+		self.error
+	}
+}
+
+/// Observer notified by [Coffio][crate::Coffio] after each encryption and decryption.
+///
+/// The events do not contain any secret nor any data context. Since the identity of the caller is
+/// only known by the application, the observer should retrieve it by its own means.
+///
+/// # Examples
+///
+/// ```
+/// use coffio::{AuditEvent, AuditObserver, Coffio, InputKeyMaterialList, KeyContext};
+///
+/// struct AuditLog;
+///
+/// impl AuditObserver for AuditLog {
+///     fn on_event(&self, event: &AuditEvent) {
+///         println!(
+///             "{:?}: key context: {}, ikm: {:?}, success: {}",
+///             event.get_operation(),
+///             event.get_key_context(),
+///             event.get_ikm_id(),
+///             event.is_success(),
+///         );
+///     }
+/// }
+///
+/// let mut ikm_list = InputKeyMaterialList::new();
+/// let _ = ikm_list.add_ikm()?;
+/// let key_ctx: KeyContext = ["db name", "table name", "column name"].into();
+/// let mut coffio = Coffio::new(&ikm_list);
+/// coffio.set_audit_observer(AuditLog);
+/// let encrypted_data = coffio.encrypt(&key_ctx, &[].into(), b"Hello, World!")?;
+/// # Ok::<(), coffio::Error>(())
+/// ```
+pub trait AuditObserver: Send + Sync {
+	/// Handle an event. This function is called once the operation is complete, whether it
+	/// succeeded or not.
+	fn on_event(&self, event: &AuditEvent<'_>);
+}
@@ -0,0 +1,500 @@
+//! Commands encrypting, decrypting and inspecting data, which are useful to debug operations.
+
+use crate::ikm::get_scheme_name;
+use crate::io::{InputArgs, is_stdio, read_stdin};
+use crate::time::{format_time, parse_time};
+use crate::{Error, Result};
+use clap::{Args, ValueEnum};
+use coffio::{
+	AuditEvent, AuditObserver, AuditOperation, Coffio, DEFAULT_KEY_CTX_PERIODICITY, DataContext,
+	DecryptionPolicy, DecryptionPolicyAction, IkmId, InputKeyMaterialList, KeyContext,
+	PolicyDecision, PolicyViolation, Timestamp,
+};
+use std::io::Write;
+use std::num::NonZeroU64;
+use std::sync::{Arc, Mutex};
+
+/// Periodicity of the key contexts.
+#[derive(Args)]
+pub(crate) struct PeriodicityArgs {
+	/// Periodicity of the key context, in seconds [default: 31556925]
+	#[arg(long, value_name = "SECONDS")]
+	periodicity: Option<NonZeroU64>,
+	/// Use a key context without periodicity
+	#[arg(long = "static", conflicts_with = "periodicity")]
+	is_static: bool,
+}
+
+impl PeriodicityArgs {
+	fn get_periodicity(&self) -> Option<NonZeroU64> {
+		let default = NonZeroU64::new(DEFAULT_KEY_CTX_PERIODICITY).unwrap();
+		(!self.is_static).then_some(self.periodicity.unwrap_or(default))
+	}
+
+	pub(crate) fn get_key_context(&self, elems: &[String]) -> KeyContext {
+		let elems: Vec<&str> = elems.iter().map(String::as_str).collect();
+		let mut key_ctx = KeyContext::from(elems.as_slice());
+		match self.get_periodicity() {
+			Some(periodicity) => key_ctx.set_periodicity(periodicity),
+			None => key_ctx.set_static(),
+		}
+		key_ctx
+	}
+}
+
+/// Contexts used to derive the key and to authenticate the data.
+#[derive(Args)]
+pub(crate) struct ContextArgs {
+	/// Element of the key context, may be repeated
+	#[arg(short, long = "key-context", value_name = "ELEMENT")]
+	key_context: Vec<String>,
+	/// Element of the data context, may be repeated
+	#[arg(short, long = "data-context", value_name = "ELEMENT")]
+	data_context: Vec<String>,
+	#[command(flatten)]
+	periodicity: PeriodicityArgs,
+}
+
+impl ContextArgs {
+	/// Returns the periodicity only if it has been set explicitly: the default one may not be the
+	/// one the data has been encrypted with, in which case the bounds of the time period would be
+	/// wrong.
+	fn get_explicit_periodicity(&self) -> Option<u64> {
+		self.periodicity.periodicity.map(NonZeroU64::get)
+	}
+
+	fn get_key_context(&self) -> KeyContext {
+		self.periodicity.get_key_context(&self.key_context)
+	}
+
+	fn get_data_context(&self) -> DataContext {
+		let elems: Vec<&str> = self.data_context.iter().map(String::as_str).collect();
+		DataContext::from(elems.as_slice())
+	}
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum ActionArg {
+	Allow,
+	Deny,
+	Warn,
+}
+
+impl From<ActionArg> for DecryptionPolicyAction {
+	fn from(action: ActionArg) -> Self {
+		match action {
+			ActionArg::Allow => DecryptionPolicyAction::Allow,
+			ActionArg::Deny => DecryptionPolicyAction::Deny,
+			ActionArg::Warn => DecryptionPolicyAction::Warn,
+		}
+	}
+}
+
+/// Actions of the decryption policy, the default policy being used for the unspecified ones.
+#[derive(Args)]
+#[command(next_help_heading = "Decryption policy")]
+pub(crate) struct PolicyArgs {
+	/// Action for an IKM used before its validity period [default: deny]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	early_enc: Option<ActionArg>,
+	/// Action for an IKM which was expired when the encryption took place [default: deny]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	expired_enc: Option<ActionArg>,
+	/// Action for a now expired IKM [default: warn]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	expired_now: Option<ActionArg>,
+	/// Action for data encrypted using a time period located in the future [default: deny]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	future_enc: Option<ActionArg>,
+	/// Action for an IKM still pending activation [default: warn]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	pending: Option<ActionArg>,
+	/// Action for a revoked IKM [default: warn]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	revoked: Option<ActionArg>,
+	/// Action for a revoked IKM used before its revocation date [default: same as --revoked]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	revoked_enc_before: Option<ActionArg>,
+	/// Action for a revoked IKM used after its revocation date [default: same as --revoked]
+	#[arg(long, value_enum, value_name = "ACTION")]
+	revoked_enc_after: Option<ActionArg>,
+}
+
+impl PolicyArgs {
+	pub(crate) fn get_policy(&self) -> DecryptionPolicy {
+		let mut policy = DecryptionPolicy::default();
+		if let Some(action) = self.early_enc {
+			policy.set_early_enc(action.into());
+		}
+		if let Some(action) = self.expired_enc {
+			policy.set_expired_enc(action.into());
+		}
+		if let Some(action) = self.expired_now {
+			policy.set_expired_now(action.into());
+		}
+		if let Some(action) = self.future_enc {
+			policy.set_future_enc(action.into());
+		}
+		if let Some(action) = self.pending {
+			policy.set_pending(action.into());
+		}
+		if let Some(action) = self.revoked {
+			policy.set_revoked(action.into());
+		}
+		if let Some(action) = self.revoked_enc_before {
+			policy.set_revoked_enc_before(action.into());
+		}
+		if let Some(action) = self.revoked_enc_after {
+			policy.set_revoked_enc_after(action.into());
+		}
+		policy
+	}
+}
+
+/// Non-secret metadata about an encryption or a decryption, as reported by Coffio's audit
+/// observer.
+#[derive(Clone, Debug, PartialEq)]
+struct Report {
+	operation: AuditOperation,
+	ikm_id: Option<IkmId>,
+	time_period: Option<u64>,
+	policy_decisions: Vec<PolicyDecision>,
+}
+
+#[derive(Clone, Default)]
+struct ReportRecorder(Arc<Mutex<Option<Report>>>);
+
+impl ReportRecorder {
+	fn take(&self) -> Option<Report> {
+		self.0.lock().ok().and_then(|mut report| report.take())
+	}
+
+	fn render(&self, ikml: &InputKeyMaterialList, context: &ContextArgs) -> String {
+		self.take()
+			.map(|report| render_report(&report, ikml, context.get_explicit_periodicity()))
+			.unwrap_or_default()
+	}
+}
+
+impl AuditObserver for ReportRecorder {
+	fn on_event(&self, event: &AuditEvent<'_>) {
+		if let Ok(mut report) = self.0.lock() {
+			*report = Some(Report {
+				operation: event.get_operation(),
+				ikm_id: event.get_ikm_id(),
+				time_period: event.get_time_period(),
+				policy_decisions: event.get_policy_decisions().to_vec(),
+			});
+		}
+	}
+}
+
+/// Arguments shared by the commands using an IKM list to process some data.
+#[derive(Args)]
+pub(crate) struct CryptoArgs {
+	#[command(flatten)]
+	input: InputArgs,
+	#[command(flatten)]
+	context: ContextArgs,
+}
+
+impl CryptoArgs {
+	fn read_data(&self, data: &Option<String>) -> Result<Vec<u8>> {
+		match data {
+			Some(data) => Ok(data.as_bytes().to_vec()),
+			None if is_stdio(&self.input.ikm_list) => Err(Error::StdinUsedTwice),
+			None => read_stdin(),
+		}
+	}
+
+	fn read_ciphertext(&self, ciphertext: &Option<String>) -> Result<String> {
+		let ciphertext = self.read_data(ciphertext)?;
+		String::from_utf8(ciphertext)
+			.map(|s| s.trim().to_string())
+			.map_err(|_| Error::InvalidCiphertext)
+	}
+}
+
+#[derive(Args)]
+pub(crate) struct EncryptArgs {
+	/// Data to encrypt, read from the standard input if not specified
+	data: Option<String>,
+	#[command(flatten)]
+	crypto: CryptoArgs,
+	/// Encrypt the data as if the current time was the specified date [default: now]
+	#[arg(long, value_name = "DATE", value_parser = parse_time)]
+	at: Option<Timestamp>,
+	/// Do not display the IKM used
+	#[arg(short, long)]
+	quiet: bool,
+}
+
+pub(crate) fn encrypt(args: EncryptArgs) -> Result<()> {
+	let data = args.crypto.read_data(&args.data)?;
+	let ikml = args.crypto.input.read()?;
+	let context = &args.crypto.context;
+	let recorder = ReportRecorder::default();
+	let mut coffio = Coffio::new(&ikml);
+	coffio.set_audit_observer(recorder.clone());
+	let encryption_time = args.at.map_or_else(Timestamp::now, Ok)?;
+	let res = coffio.encrypt_at(
+		&context.get_key_context(),
+		&context.get_data_context(),
+		data,
+		encryption_time,
+	);
+	if !args.quiet {
+		eprint!("{}", recorder.render(&ikml, context));
+	}
+	println!("{}", res?);
+	Ok(())
+}
+
+/// Arguments shared by the commands decrypting data.
+#[derive(Args)]
+pub(crate) struct DecryptionArgs {
+	/// Encrypted data, read from the standard input if not specified
+	ciphertext: Option<String>,
+	#[command(flatten)]
+	crypto: CryptoArgs,
+	#[command(flatten)]
+	policy: PolicyArgs,
+}
+
+impl DecryptionArgs {
+	/// Decrypt the data and returns the result along with the rendered report.
+	fn decrypt(&self) -> Result<(Result<Vec<u8>>, String)> {
+		let ciphertext = self.crypto.read_ciphertext(&self.ciphertext)?;
+		let ikml = self.crypto.input.read()?;
+		let context = &self.crypto.context;
+		let recorder = ReportRecorder::default();
+		let mut coffio = Coffio::with_decryption_policy(&ikml, &self.policy.get_policy());
+		coffio.set_audit_observer(recorder.clone());
+		let res = coffio.decrypt(
+			&context.get_key_context(),
+			&context.get_data_context(),
+			&ciphertext,
+		);
+		Ok((res.map_err(Error::from), recorder.render(&ikml, context)))
+	}
+}
+
+#[derive(Args)]
+pub(crate) struct DecryptArgs {
+	#[command(flatten)]
+	decryption: DecryptionArgs,
+	/// Do not display the IKM used and the policy decisions
+	#[arg(short, long)]
+	quiet: bool,
+}
+
+pub(crate) fn decrypt(args: DecryptArgs) -> Result<()> {
+	let (res, report) = args.decryption.decrypt()?;
+	if !args.quiet {
+		eprint!("{report}");
+	}
+	let mut stdout = std::io::stdout();
+	stdout
+		.write_all(&res?)
+		.and_then(|_| stdout.flush())
+		.map_err(|e| Error::Io("-".into(), e))
+}
+
+#[derive(Args)]
+pub(crate) struct InspectArgs {
+	#[command(flatten)]
+	decryption: DecryptionArgs,
+}
+
+pub(crate) fn inspect(args: InspectArgs) -> Result<()> {
+	let (res, report) = args.decryption.decrypt()?;
+	print!("{report}");
+	res?;
+	println!("decryption: ok");
+	Ok(())
+}
+
+fn render_report(report: &Report, ikml: &InputKeyMaterialList, periodicity: Option<u64>) -> String {
+	let mut ret = String::new();
+	if let Some(id) = report.ikm_id {
+		match ikml.iter().find(|ikm| ikm.get_id() == id) {
+			Some(ikm) => {
+				ret += &format!("ikm: {id}\n");
+				ret += &format!("scheme: {}\n", get_scheme_name(ikm.get_scheme()));
+				ret += &format!("state: {:?}\n", ikm.get_state());
+				ret += &format!(
+					"validity: {} to {}\n",
+					format_time(ikm.get_not_before()),
+					format_time(ikm.get_not_after())
+				);
+				if ikm.is_revoked() {
+					let revoked_at = ikm.get_revoked_at().map_or("yes".to_string(), format_time);
+					ret += &format!("revoked: {revoked_at}\n");
+				}
+			}
+			None => ret += &format!("ikm: {id} (not found in the IKM list)\n"),
+		}
+	}
+	match (report.time_period, periodicity) {
+		(Some(tp), Some(periodicity)) => {
+			let period = get_period_bounds(tp, periodicity)
+				.map(|(start, end)| format!(" ({} to {})", format_time(start), format_time(end)))
+				.unwrap_or_default();
+			ret += &format!("time period: {tp}{period}\n");
+		}
+		(Some(tp), None) => ret += &format!("time period: {tp}\n"),
+		(None, _) if report.ikm_id.is_some() => ret += "time period: none (static key)\n",
+		(None, _) => {}
+	}
+	if report.operation == AuditOperation::Decrypt && report.ikm_id.is_some() {
+		for decision in &report.policy_decisions {
+			ret += &format!(
+				"policy: {}: {}\n",
+				get_violation_name(decision.get_violation()),
+				get_action_name(decision.get_action())
+			);
+		}
+		ret += &format!(
+			"policy outcome: {}\n",
+			get_policy_outcome(&report.policy_decisions)
+		);
+	}
+	ret
+}
+
+fn get_period_bounds(time_period: u64, periodicity: u64) -> Option<(Timestamp, Timestamp)> {
+	let start = time_period.checked_mul(periodicity)?;
+	let end = start.checked_add(periodicity)?;
+	Some((Timestamp::from_secs(start), Timestamp::from_secs(end)))
+}
+
+fn get_violation_name(violation: PolicyViolation) -> &'static str {
+	match violation {
+		PolicyViolation::EarlyEnc => "early-enc",
+		PolicyViolation::ExpiredEnc => "expired-enc",
+		PolicyViolation::ExpiredNow => "expired-now",
+		PolicyViolation::FutureEnc => "future-enc",
+		PolicyViolation::Pending => "pending",
+		PolicyViolation::Destroyed => "destroyed",
+		PolicyViolation::Revoked => "revoked",
+		PolicyViolation::RevokedEncBefore => "revoked-enc-before",
+		PolicyViolation::RevokedEncAfter => "revoked-enc-after",
+	}
+}
+
+fn get_action_name(action: DecryptionPolicyAction) -> &'static str {
+	match action {
+		DecryptionPolicyAction::Allow => "allow",
+		DecryptionPolicyAction::Deny => "deny",
+		DecryptionPolicyAction::Warn => "warn",
+	}
+}
+
+fn get_policy_outcome(decisions: &[PolicyDecision]) -> &'static str {
+	let actions = decisions.iter().map(|d| d.get_action());
+	if actions.clone().any(|a| a == DecryptionPolicyAction::Deny) {
+		"deny"
+	} else if actions.clone().any(|a| a == DecryptionPolicyAction::Warn) {
+		"warn"
+	} else if decisions.is_empty() {
+		"pass"
+	} else {
+		"allow"
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use coffio::RevocationReason;
+
+	const TEST_RAW_IKML: &str = "ikml-v1:AQAAAA:AQAAAAEAAAC_vYEw1ujVG5i-CtoPYSzik_6xaAq59odjPm5ij01-e6zz4mUAAAAALJGBiwAAAAAA";
+	const TEST_CIPHERTEXT: &str = "enc-v1:AQAAAA:qpVDbGvu0wl2tQgfF5jngCWCoCq5d9gj:eTkOSKz9YyvJE8PyT1lAFn4hyeK_0l6tWU4yyHA-7WRCJ9G-HWNpqoKBxg:NgAAAAAAAAA";
+
+	fn get_context_args() -> ContextArgs {
+		ContextArgs {
+			key_context: vec![
+				"db_name".to_string(),
+				"table_name".to_string(),
+				"column_name".to_string(),
+			],
+			data_context: vec!["018db876-3d9d-79af-9460-55d17da991d8".to_string()],
+			periodicity: PeriodicityArgs {
+				periodicity: None,
+				is_static: false,
+			},
+		}
+	}
+
+	fn decrypt_with_report(
+		ikml: &InputKeyMaterialList,
+		policy: &DecryptionPolicy,
+	) -> (Result<Vec<u8>, coffio::Error>, Report) {
+		let context = get_context_args();
+		let recorder = ReportRecorder::default();
+		let mut coffio = Coffio::with_decryption_policy(ikml, policy);
+		coffio.set_audit_observer(recorder.clone());
+		let res = coffio.decrypt(
+			&context.get_key_context(),
+			&context.get_data_context(),
+			TEST_CIPHERTEXT,
+		);
+		(res, recorder.take().unwrap())
+	}
+
+	#[test]
+	fn reference_ciphertext() {
+		let ikml = InputKeyMaterialList::import(TEST_RAW_IKML).unwrap();
+		let (res, report) = decrypt_with_report(&ikml, &DecryptionPolicy::default());
+		assert_eq!(res.unwrap(), b"Lorem ipsum dolor sit amet.");
+		assert_eq!(report.ikm_id, Some(1));
+		assert_eq!(report.time_period, Some(54));
+		let rendered = render_report(&report, &ikml, None);
+		let expected = "ikm: 1
+scheme: xchacha20poly1305-blake3
+state: Active
+validity: 2024-03-02T09:38:52Z to 2044-03-02T09:38:52Z
+time period: 54
+policy outcome: pass
+";
+		assert_eq!(rendered, expected);
+		let rendered = render_report(&report, &ikml, Some(DEFAULT_KEY_CTX_PERIODICITY));
+		let expected = expected.replace(
+			"time period: 54\n",
+			"time period: 54 (2024-01-01T01:52:30Z to 2024-12-31T07:41:15Z)\n",
+		);
+		assert_eq!(rendered, expected);
+	}
+
+	#[test]
+	fn policy() {
+		let mut ikml = InputKeyMaterialList::import(TEST_RAW_IKML).unwrap();
+		ikml.revoke_ikm_at(1, Timestamp::UNIX_EPOCH, RevocationReason::KeyCompromise)
+			.unwrap();
+		let args = PolicyArgs {
+			early_enc: None,
+			expired_enc: None,
+			expired_now: Some(ActionArg::Allow),
+			future_enc: None,
+			pending: None,
+			revoked: Some(ActionArg::Deny),
+			revoked_enc_before: None,
+			revoked_enc_after: None,
+		};
+		let (res, report) = decrypt_with_report(&ikml, &args.get_policy());
+		assert!(res.is_err());
+		let rendered = render_report(&report, &ikml, None);
+		assert!(rendered.contains("revoked: 1970-01-01T00:00:00Z\n"));
+		assert!(rendered.ends_with("policy: revoked-enc-after: deny\npolicy outcome: deny\n"));
+		assert!(rendered.contains("time period: 54\n"));
+	}
+
+	#[test]
+	fn unknown_ikm() {
+		let ikml = InputKeyMaterialList::new();
+		let (res, report) = decrypt_with_report(&ikml, &DecryptionPolicy::default());
+		assert!(res.is_err());
+		let rendered = render_report(&report, &ikml, None);
+		assert!(rendered.starts_with("ikm: 1 (not found in the IKM list)\n"));
+	}
+}
@@ -0,0 +1,299 @@
+//! Commands managing the IKMs of an IKM list.
+
+use crate::io::{InputArgs, OutputArgs, UpdateArgs};
+use crate::table::render_table;
+use crate::time::{format_time, parse_time};
+use crate::{Error, Result};
+use clap::{Args, ValueEnum};
+use coffio::{
+	DEFAULT_IKM_DURATION, DEFAULT_SCHEME, IkmId, InputKeyMaterial, InputKeyMaterialList,
+	RevocationReason, Scheme, Timestamp,
+};
+use std::time::Duration;
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum SchemeArg {
+	#[cfg(feature = "chacha")]
+	#[value(name = "xchacha20poly1305-blake3")]
+	XChaCha20Poly1305WithBlake3,
+	#[cfg(feature = "aes")]
+	#[value(name = "aes128gcm-sha256")]
+	Aes128GcmWithSha256,
+}
+
+impl From<SchemeArg> for Scheme {
+	fn from(scheme: SchemeArg) -> Self {
+		match scheme {
+			#[cfg(feature = "chacha")]
+			SchemeArg::XChaCha20Poly1305WithBlake3 => Scheme::XChaCha20Poly1305WithBlake3,
+			#[cfg(feature = "aes")]
+			SchemeArg::Aes128GcmWithSha256 => Scheme::Aes128GcmWithSha256,
+		}
+	}
+}
+
+impl From<Scheme> for SchemeArg {
+	fn from(scheme: Scheme) -> Self {
+		match scheme {
+			#[cfg(feature = "chacha")]
+			Scheme::XChaCha20Poly1305WithBlake3 => SchemeArg::XChaCha20Poly1305WithBlake3,
+			#[cfg(feature = "aes")]
+			Scheme::Aes128GcmWithSha256 => SchemeArg::Aes128GcmWithSha256,
+		}
+	}
+}
+
+/// Returns the name of the scheme, as accepted by the `--scheme` option.
+pub(crate) fn get_scheme_name(scheme: Scheme) -> String {
+	SchemeArg::from(scheme)
+		.to_possible_value()
+		.map(|v| v.get_name().to_string())
+		.unwrap_or_default()
+}
+
+#[derive(Clone, Copy, Debug, Default, PartialEq, ValueEnum)]
+pub(crate) enum RevocationReasonArg {
+	#[default]
+	Unspecified,
+	KeyCompromise,
+	Superseded,
+	CessationOfOperation,
+}
+
+impl From<RevocationReasonArg> for RevocationReason {
+	fn from(reason: RevocationReasonArg) -> Self {
+		match reason {
+			RevocationReasonArg::Unspecified => RevocationReason::Unspecified,
+			RevocationReasonArg::KeyCompromise => RevocationReason::KeyCompromise,
+			RevocationReasonArg::Superseded => RevocationReason::Superseded,
+			RevocationReasonArg::CessationOfOperation => RevocationReason::CessationOfOperation,
+		}
+	}
+}
+
+/// Settings of a new IKM.
+#[derive(Args)]
+pub(crate) struct IkmArgs {
+	/// Scheme the IKM is bound to [default: xchacha20poly1305-blake3 if available]
+	#[arg(short, long, value_enum)]
+	scheme: Option<SchemeArg>,
+	/// Date from which the IKM may be used, either as a UTC date (YYYY-MM-DD or
+	/// YYYY-MM-DDTHH:MM:SSZ) or as a Unix timestamp [default: now]
+	#[arg(long, value_name = "DATE", value_parser = parse_time)]
+	not_before: Option<Timestamp>,
+	/// Date after which the IKM must not be used [default: 10 years after --not-before]
+	#[arg(long, value_name = "DATE", value_parser = parse_time)]
+	not_after: Option<Timestamp>,
+	/// Validity period of the IKM, in seconds, as an alternative to --not-after
+	#[arg(long, value_name = "SECONDS", conflicts_with = "not_after")]
+	validity: Option<u64>,
+}
+
+impl IkmArgs {
+	fn add_to(&self, ikml: &mut InputKeyMaterialList) -> Result<IkmId> {
+		let scheme = self.scheme.map_or(DEFAULT_SCHEME, Scheme::from);
+		let not_before = self.not_before.map_or_else(Timestamp::now, Ok)?;
+		let not_after = match self.not_after {
+			Some(not_after) => not_after,
+			None => {
+				let validity = self.validity.unwrap_or(DEFAULT_IKM_DURATION);
+				not_before
+					.checked_add(Duration::from_secs(validity))
+					.ok_or(Error::InvalidValidityPeriod)?
+			}
+		};
+		if not_after <= not_before {
+			return Err(Error::InvalidValidityPeriod);
+		}
+		Ok(ikml.add_custom_ikm(scheme, not_before, not_after)?)
+	}
+}
+
+#[derive(Args)]
+pub(crate) struct NewArgs {
+	#[command(flatten)]
+	ikm: IkmArgs,
+	/// Generate an empty IKM list
+	#[arg(long, conflicts_with_all = ["scheme", "not_before", "not_after", "validity"])]
+	empty: bool,
+	#[command(flatten)]
+	output: OutputArgs,
+}
+
+pub(crate) fn new(args: NewArgs) -> Result<()> {
+	let mut ikml = InputKeyMaterialList::new();
+	if !args.empty {
+		args.ikm.add_to(&mut ikml)?;
+	}
+	args.output.write(&ikml)
+}
+
+#[derive(Args)]
+pub(crate) struct AddArgs {
+	#[command(flatten)]
+	ikm: IkmArgs,
+	#[command(flatten)]
+	update: UpdateArgs,
+}
+
+pub(crate) fn add(args: AddArgs) -> Result<()> {
+	let id = args.update.update(|ikml| args.ikm.add_to(ikml))?;
+	eprintln!("IKM {id} added");
+	Ok(())
+}
+
+#[derive(Args)]
+pub(crate) struct RevokeArgs {
+	/// Identifier of the IKM
+	id: IkmId,
+	/// Reason why the IKM is revoked
+	#[arg(long, value_enum, default_value_t)]
+	reason: RevocationReasonArg,
+	/// Date from which the IKM is considered revoked [default: now]
+	#[arg(long, value_name = "DATE", value_parser = parse_time)]
+	at: Option<Timestamp>,
+	#[command(flatten)]
+	update: UpdateArgs,
+}
+
+pub(crate) fn revoke(args: RevokeArgs) -> Result<()> {
+	let revoked_at = args.at.map_or_else(Timestamp::now, Ok)?;
+	let id = args
+		.update
+		.update(|ikml| Ok(ikml.revoke_ikm_at(args.id, revoked_at, args.reason.into())?))?;
+	eprintln!("IKM {id} revoked");
+	Ok(())
+}
+
+#[derive(Args)]
+pub(crate) struct DeleteArgs {
+	/// Identifier of the IKM
+	id: IkmId,
+	#[command(flatten)]
+	update: UpdateArgs,
+}
+
+pub(crate) fn delete(args: DeleteArgs) -> Result<()> {
+	let id = args.update.update(|ikml| Ok(ikml.delete_ikm(args.id)?))?;
+	eprintln!("IKM {id} deleted");
+	Ok(())
+}
+
+#[derive(Args)]
+pub(crate) struct ListArgs {
+	#[command(flatten)]
+	input: InputArgs,
+}
+
+pub(crate) fn list(args: ListArgs) -> Result<()> {
+	let ikml = args.input.read()?;
+	print!("{}", render_ikm_table(&ikml));
+	Ok(())
+}
+
+/// Render the IKMs as a table. The secret content of the IKMs is never displayed, only their key
+/// check value.
+fn render_ikm_table(ikml: &InputKeyMaterialList) -> String {
+	let header = [
+		"ID",
+		"SCHEME",
+		"STATE",
+		"NOT BEFORE",
+		"NOT AFTER",
+		"REVOKED",
+		"KEY CHECK VALUE",
+	]
+	.map(String::from);
+	let rows: Vec<_> = std::iter::once(header)
+		.chain(ikml.iter().map(render_row))
+		.collect();
+	render_table(&rows)
+}
+
+fn render_row(ikm: &InputKeyMaterial) -> [String; 7] {
+	let revoked = match (ikm.get_revoked_at(), ikm.get_revocation_reason()) {
+		(_, None) => "-".to_string(),
+		(revoked_at, Some(reason)) => {
+			let mut revoked = revoked_at.map_or("yes".to_string(), format_time);
+			if reason != RevocationReason::Unspecified {
+				revoked += &format!(" ({reason:?})");
+			}
+			revoked
+		}
+	};
+	[
+		ikm.get_id().to_string(),
+		get_scheme_name(ikm.get_scheme()),
+		format!("{:?}", ikm.get_state()),
+		format_time(ikm.get_not_before()),
+		format_time(ikm.get_not_after()),
+		revoked,
+		ikm.get_key_check_value(),
+	]
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use base64ct::{Base64Unpadded, Base64UrlUnpadded, Encoding};
+
+	fn get_ikm_args(not_before: u64, not_after: u64) -> IkmArgs {
+		IkmArgs {
+			scheme: None,
+			not_before: parse_time(&not_before.to_string()).ok(),
+			not_after: parse_time(&not_after.to_string()).ok(),
+			validity: None,
+		}
+	}
+
+	#[test]
+	fn add_ikm() {
+		let mut ikml = InputKeyMaterialList::new();
+		let args = get_ikm_args(1_700_000_000, 1_800_000_000);
+		assert_eq!(args.add_to(&mut ikml).unwrap(), 1);
+		assert_eq!(ikml[0].get_scheme(), DEFAULT_SCHEME);
+		assert_eq!(format_time(ikml[0].get_not_after()), "2027-01-15T08:00:00Z");
+
+		let args = get_ikm_args(1_800_000_000, 1_700_000_000);
+		assert!(matches!(
+			args.add_to(&mut ikml),
+			Err(Error::InvalidValidityPeriod)
+		));
+		assert_eq!(ikml.len(), 1);
+	}
+
+	#[test]
+	fn table() {
+		let mut ikml = InputKeyMaterialList::new();
+		let secret: Vec<u8> = (1..=32).collect();
+		ikml.add_ikm_from_bytes(
+			DEFAULT_SCHEME,
+			&secret,
+			Timestamp::from_secs(1_700_000_000),
+			Timestamp::from_secs(1_800_000_000),
+			None,
+		)
+		.unwrap();
+		get_ikm_args(1_750_000_000, 1_850_000_000)
+			.add_to(&mut ikml)
+			.unwrap();
+		let revoked_at = parse_time("2025-01-01").unwrap();
+		ikml.revoke_ikm_at(1, revoked_at, RevocationReason::KeyCompromise)
+			.unwrap();
+		let table = render_ikm_table(&ikml);
+		let lines: Vec<_> = table.lines().collect();
+		assert_eq!(lines.len(), 3);
+		assert!(lines[0].starts_with("ID  SCHEME"));
+		assert!(lines[1].contains("2023-11-14T22:13:20Z"));
+		assert!(lines[1].contains("2025-01-01T00:00:00Z (KeyCompromise)"));
+		assert!(lines[1].contains(&ikml[0].get_key_check_value()));
+		assert!(lines[2].contains("Active"));
+		assert!(lines[2].contains("  -  "));
+
+		// The secret content must never be displayed.
+		let hex_secret: String = secret.iter().map(|b| format!("{b:02x}")).collect();
+		assert!(!table.to_lowercase().contains(&hex_secret));
+		assert!(!table.contains(&Base64Unpadded::encode_string(&secret)));
+		assert!(!table.contains(&Base64UrlUnpadded::encode_string(&secret)));
+	}
+}
@@ -0,0 +1,304 @@
+//! Reading and writing IKM lists from and to files or the standard streams.
+
+use crate::{Error, Result};
+use clap::Args;
+use coffio::{IkmListVerifyingKey, InputKeyMaterialList};
+use std::ffi::OsString;
+use std::fs::{File, OpenOptions};
+use std::io::{ErrorKind, IsTerminal, Read, Write};
+use std::path::{Path, PathBuf};
+
+pub(crate) const STDIO: &str = "-";
+
+#[derive(Args)]
+pub(crate) struct InputArgs {
+	/// File containing the IKM list, `-` for the standard input.
+	#[arg(short = 'l', long, value_name = "FILE", default_value = STDIO)]
+	pub(crate) ikm_list: PathBuf,
+	/// Verifying key (`ikml-vk-v1:…`) of the key used to sign the IKM list. If specified, the IKM
+	/// list must have been signed using this key, otherwise it is rejected.
+	#[arg(long, value_name = "KEY", value_parser = parse_verifying_key)]
+	pub(crate) verifying_key: Option<IkmListVerifyingKey>,
+}
+
+impl InputArgs {
+	pub(crate) fn read(&self) -> Result<InputKeyMaterialList> {
+		let path = &self.ikm_list;
+		let mut s = String::new();
+		let res = if is_stdio(path) {
+			std::io::stdin().read_to_string(&mut s)
+		} else {
+			File::open(path).and_then(|mut f| f.read_to_string(&mut s))
+		};
+		res.map_err(|e| Error::Io(path.clone(), e))?;
+		let ikml = match &self.verifying_key {
+			Some(key) => InputKeyMaterialList::import_signed(s.trim(), key)?,
+			None => InputKeyMaterialList::import(s.trim())?,
+		};
+		Ok(ikml)
+	}
+}
+
+fn parse_verifying_key(s: &str) -> Result<IkmListVerifyingKey, String> {
+	IkmListVerifyingKey::import(s).map_err(|e| format!("{s}: {e}"))
+}
+
+#[derive(Args)]
+pub(crate) struct OutputArgs {
+	/// File to write the IKM list to, `-` for the standard output. An existing file is replaced
+	/// and, like new files, is only readable by its owner.
+	#[arg(short, long, value_name = "FILE", default_value = STDIO)]
+	pub(crate) output: PathBuf,
+	/// Allow to write the IKM list to a terminal, which displays its secret content.
+	#[arg(long)]
+	pub(crate) show_secrets: bool,
+}
+
+impl OutputArgs {
+	pub(crate) fn write(&self, ikml: &InputKeyMaterialList) -> Result<()> {
+		write_ikm_list(&self.output, self.show_secrets, ikml)
+	}
+}
+
+/// Arguments of the commands modifying an existing IKM list.
+#[derive(Args)]
+pub(crate) struct UpdateArgs {
+	#[command(flatten)]
+	pub(crate) input: InputArgs,
+	#[command(flatten)]
+	pub(crate) output: OutputArgs,
+	/// Write the modified IKM list back to the file it has been read from.
+	#[arg(short, long, conflicts_with = "output")]
+	pub(crate) in_place: bool,
+}
+
+impl UpdateArgs {
+	pub(crate) fn update<T>(
+		&self,
+		f: impl FnOnce(&mut InputKeyMaterialList) -> Result<T>,
+	) -> Result<T> {
+		let mut ikml = self.input.read()?;
+		let res = f(&mut ikml)?;
+		let output = if self.in_place {
+			&self.input.ikm_list
+		} else {
+			&self.output.output
+		};
+		write_ikm_list(output, self.output.show_secrets, &ikml)?;
+		Ok(res)
+	}
+}
+
+pub(crate) fn is_stdio(path: &Path) -> bool {
+	path.as_os_str() == STDIO
+}
+
+/// Check whether or not both paths refer to the same existing file, including through symbolic or
+/// hard links.
+pub(crate) fn is_same_file(a: &Path, b: &Path) -> bool {
+	#[cfg(unix)]
+	{
+		use std::os::unix::fs::MetadataExt;
+		match (std::fs::metadata(a), std::fs::metadata(b)) {
+			(Ok(a), Ok(b)) => a.dev() == b.dev() && a.ino() == b.ino(),
+			_ => false,
+		}
+	}
+	#[cfg(not(unix))]
+	matches!(
+		(std::fs::canonicalize(a), std::fs::canonicalize(b)),
+		(Ok(a), Ok(b)) if a == b
+	)
+}
+
+pub(crate) fn read_stdin() -> Result<Vec<u8>> {
+	let mut data = Vec::new();
+	std::io::stdin()
+		.read_to_end(&mut data)
+		.map_err(|e| Error::Io(STDIO.into(), e))?;
+	Ok(data)
+}
+
+fn write_ikm_list(path: &Path, show_secrets: bool, ikml: &InputKeyMaterialList) -> Result<()> {
+	let exported = ikml.export()?;
+	let res = if is_stdio(path) {
+		let mut stdout = std::io::stdout();
+		if stdout.is_terminal() && !show_secrets {
+			return Err(Error::SecretOnTerminal);
+		}
+		writeln!(stdout, "{exported}")
+	} else {
+		AtomicFile::create(path, true).and_then(|mut f| {
+			writeln!(f, "{exported}")?;
+			f.commit()
+		})
+	};
+	res.map_err(|e| Error::Io(path.to_path_buf(), e))
+}
+
+/// File which is written to a temporary file in the same directory and replaces the target file
+/// only once it has been committed, so that a failure never leaves a truncated or partially
+/// written file behind. The temporary file is removed if it has not been committed.
+pub(crate) struct AtomicFile {
+	path: PathBuf,
+	tmp_path: PathBuf,
+	file: File,
+	is_committed: bool,
+}
+
+impl AtomicFile {
+	/// Create the temporary file. If the target file is a symbolic link, the file it points to is
+	/// replaced. Secret files are only readable by their owner.
+	#[cfg_attr(not(unix), allow(unused_variables))]
+	pub(crate) fn create(path: &Path, is_secret: bool) -> std::io::Result<Self> {
+		let path = match std::fs::canonicalize(path) {
+			Ok(path) => path,
+			Err(e) if e.kind() == ErrorKind::NotFound => path.to_path_buf(),
+			Err(e) => return Err(e),
+		};
+		let file_name = path
+			.file_name()
+			.ok_or_else(|| std::io::Error::from(ErrorKind::InvalidInput))?;
+		let mut tmp_name = OsString::from(".");
+		tmp_name.push(file_name);
+		tmp_name.push(format!(".{}.tmp", std::process::id()));
+		let tmp_path = path.with_file_name(tmp_name);
+		let mut options = OpenOptions::new();
+		options.write(true).create_new(true);
+		#[cfg(unix)]
+		if is_secret {
+			std::os::unix::fs::OpenOptionsExt::mode(&mut options, 0o600);
+		}
+		let file = options.open(&tmp_path)?;
+		Ok(Self {
+			path,
+			tmp_path,
+			file,
+			is_committed: false,
+		})
+	}
+
+	/// Flush the temporary file to the disk and rename it to the target file.
+	pub(crate) fn commit(mut self) -> std::io::Result<()> {
+		self.file.sync_all()?;
+		std::fs::rename(&self.tmp_path, &self.path)?;
+		self.is_committed = true;
+		// Make sure the rename itself is persisted.
+		#[cfg(unix)]
+		{
+			let dir = match self.path.parent() {
+				Some(dir) if !dir.as_os_str().is_empty() => dir,
+				_ => Path::new("."),
+			};
+			File::open(dir)?.sync_all()?;
+		}
+		Ok(())
+	}
+}
+
+impl Write for AtomicFile {
+	fn write(&mut self, buf: &[u8]) -> std::io::Result<usize> {
+		self.file.write(buf)
+	}
+
+	fn flush(&mut self) -> std::io::Result<()> {
+		self.file.flush()
+	}
+}
+
+impl Drop for AtomicFile {
+	fn drop(&mut self) {
+		if !self.is_committed {
+			let _ = std::fs::remove_file(&self.tmp_path);
+		}
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn get_test_dir(name: &str) -> PathBuf {
+		let dir = std::env::temp_dir().join(format!("coffio-{name}-{}", std::process::id()));
+		let _ = std::fs::remove_dir_all(&dir);
+		std::fs::create_dir(&dir).unwrap();
+		dir
+	}
+
+	#[test]
+	fn atomic_file() {
+		let dir = get_test_dir("atomic-file");
+		let path = dir.join("list.ikml");
+		std::fs::write(&path, "old").unwrap();
+
+		let mut f = AtomicFile::create(&path, true).unwrap();
+		f.write_all(b"new").unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "old");
+		f.commit().unwrap();
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+		#[cfg(unix)]
+		{
+			use std::os::unix::fs::PermissionsExt;
+			let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+			assert_eq!(mode & 0o777, 0o600);
+		}
+
+		let mut f = AtomicFile::create(&path, true).unwrap();
+		f.write_all(b"aborted").unwrap();
+		drop(f);
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "new");
+		assert_eq!(std::fs::read_dir(&dir).unwrap().count(), 1);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn read_signed() {
+		use coffio::IkmListSigningKey;
+
+		let dir = get_test_dir("read-signed");
+		let signed_path = dir.join("signed.ikml");
+		let unsigned_path = dir.join("unsigned.ikml");
+		let signing_key = IkmListSigningKey::generate().unwrap();
+		let verifying_key = signing_key.get_verifying_key();
+		let mut ikml = InputKeyMaterialList::new();
+		let _ = ikml.add_ikm().unwrap();
+		std::fs::write(&signed_path, ikml.export_signed(&signing_key).unwrap()).unwrap();
+		std::fs::write(&unsigned_path, ikml.export().unwrap()).unwrap();
+		let input = |ikm_list: &Path, verifying_key| InputArgs {
+			ikm_list: ikm_list.to_path_buf(),
+			verifying_key,
+		};
+
+		let res = input(&signed_path, Some(verifying_key)).read();
+		assert_eq!(res.unwrap().len(), 1);
+		let other_key = IkmListSigningKey::generate().unwrap().get_verifying_key();
+		let res = input(&signed_path, Some(other_key)).read();
+		assert!(matches!(
+			res,
+			Err(Error::Coffio(coffio::Error::IkmListInvalidSignature))
+		));
+		let res = input(&unsigned_path, Some(verifying_key)).read();
+		assert!(res.is_err());
+		let res = input(&unsigned_path, None).read();
+		assert_eq!(res.unwrap().len(), 1);
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+
+	#[test]
+	fn same_file() {
+		let dir = get_test_dir("same-file");
+		let path = dir.join("data.csv");
+		std::fs::write(&path, "").unwrap();
+		let link = dir.join("link.csv");
+		std::fs::hard_link(&path, &link).unwrap();
+		let other = dir.join("other.csv");
+		std::fs::write(&other, "").unwrap();
+
+		assert!(is_same_file(&path, &path));
+		assert!(is_same_file(&path, &link));
+		assert!(is_same_file(&path, &dir.join(".").join("data.csv")));
+		assert!(!is_same_file(&path, &other));
+		assert!(!is_same_file(&path, &dir.join("missing.csv")));
+		std::fs::remove_dir_all(&dir).unwrap();
+	}
+}
@@ -0,0 +1,103 @@
+//! Command-line tool to manage Coffio's IKM lists and to debug the encryption and decryption of
+//! data.
+
+mod crypto;
+mod ikm;
+mod io;
+mod reencrypt;
+mod table;
+mod time;
+
+use clap::{Parser, Subcommand};
+use std::path::PathBuf;
+use std::process::ExitCode;
+
+pub(crate) type Result<T, E = Error> = std::result::Result<T, E>;
+
+/// An error type representing all the things that can go wrong within the command-line tool.
+#[derive(thiserror::Error, Debug)]
+pub(crate) enum Error {
+	#[error("{0}")]
+	Coffio(#[from] coffio::Error),
+	#[error("{0}: {1}")]
+	Io(PathBuf, std::io::Error),
+	#[error("the IKM list and the data cannot both be read from the standard input")]
+	StdinUsedTwice,
+	#[error("the encrypted data is not valid UTF-8")]
+	InvalidCiphertext,
+	#[error("{0}")]
+	Csv(#[from] csv::Error),
+	#[error("{0}")]
+	Json(#[from] serde_json::Error),
+	#[error("line {0}: {1}")]
+	Line(u64, Box<Error>),
+	#[error("{0}: no such column")]
+	MissingColumn(String),
+	#[error("not a JSON object")]
+	NotAnObject,
+	#[error("unknown file format, use --format")]
+	UnknownFormat,
+	#[error("{0}: the output file cannot be one of the input files")]
+	OutputIsInput(PathBuf),
+	#[error("invalid validity period: the IKM must expire after it becomes valid")]
+	InvalidValidityPeriod,
+	#[error(
+		"refusing to write the IKM list to a terminal since it contains secret material, use --output or --show-secrets"
+	)]
+	SecretOnTerminal,
+}
+
+/// Manage Coffio's IKM lists and debug the encryption and decryption of data.
+///
+/// IKM lists are read from the standard input and written to the standard output unless files
+/// are specified.
+#[derive(Parser)]
+#[command(name = "coffio", version)]
+struct Cli {
+	#[command(subcommand)]
+	command: Command,
+}
+
+#[derive(Subcommand)]
+enum Command {
+	/// Generate a new IKM list containing a single IKM.
+	New(ikm::NewArgs),
+	/// Add a new IKM to an IKM list.
+	Add(ikm::AddArgs),
+	/// Revoke an IKM.
+	Revoke(ikm::RevokeArgs),
+	/// Delete an IKM from an IKM list.
+	Delete(ikm::DeleteArgs),
+	/// Display the IKMs of an IKM list, without their secret content.
+	List(ikm::ListArgs),
+	/// Encrypt some data and display the IKM used.
+	Encrypt(crypto::EncryptArgs),
+	/// Decrypt some data and display the IKM used and the decryption policy decisions.
+	Decrypt(crypto::DecryptArgs),
+	/// Check whether some encrypted data can be decrypted, without displaying it.
+	Inspect(crypto::InspectArgs),
+	/// Re-encrypt the outdated values of a CSV or JSON Lines file using the latest IKM.
+	Reencrypt(reencrypt::ReencryptArgs),
+}
+
+fn main() -> ExitCode {
+	let cli = Cli::parse();
+	let res = match cli.command {
+		Command::New(args) => ikm::new(args),
+		Command::Add(args) => ikm::add(args),
+		Command::Revoke(args) => ikm::revoke(args),
+		Command::Delete(args) => ikm::delete(args),
+		Command::List(args) => ikm::list(args),
+		Command::Encrypt(args) => crypto::encrypt(args),
+		Command::Decrypt(args) => crypto::decrypt(args),
+		Command::Inspect(args) => crypto::inspect(args),
+		Command::Reencrypt(args) => reencrypt::reencrypt(args),
+	};
+	match res {
+		Ok(()) => ExitCode::SUCCESS,
+		Err(e) => {
+			eprintln!("coffio: {e}");
+			ExitCode::FAILURE
+		}
+	}
+}
@@ -0,0 +1,591 @@
+//! Bulk re-encryption of CSV and JSON Lines files.
+
+use crate::crypto::{PeriodicityArgs, PolicyArgs};
+use crate::io::{AtomicFile, InputArgs, STDIO, is_same_file, is_stdio};
+use crate::table::render_table;
+use crate::{Error, Result};
+use clap::{Args, ValueEnum};
+use coffio::{
+	CiphertextInfo, Coffio, DataContext, IkmId, InputKeyMaterialList, KeyContext, Timestamp,
+};
+use serde_json::{Map, Value};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::File;
+use std::io::{BufRead, BufReader, BufWriter, Read, Write};
+use std::path::{Path, PathBuf};
+
+#[derive(Clone, Copy, Debug, PartialEq, ValueEnum)]
+pub(crate) enum Format {
+	/// Comma-separated values, the first record being the header
+	Csv,
+	/// JSON Lines, each line being a JSON object
+	Jsonl,
+}
+
+impl Format {
+	fn from_path(path: &Path) -> Option<Self> {
+		match path.extension()?.to_str()? {
+			"csv" => Some(Self::Csv),
+			"jsonl" | "ndjson" => Some(Self::Jsonl),
+			_ => None,
+		}
+	}
+}
+
+/// Column containing encrypted data, along with the last element of its key context.
+#[derive(Clone, Debug, PartialEq)]
+pub(crate) struct ColumnArg {
+	name: String,
+	key_context_elem: String,
+}
+
+fn parse_column(s: &str) -> Result<ColumnArg, String> {
+	let (name, elem) = s.split_once('=').unwrap_or((s, s));
+	if name.is_empty() {
+		return Err("empty column name".to_string());
+	}
+	Ok(ColumnArg {
+		name: name.to_string(),
+		key_context_elem: elem.to_string(),
+	})
+}
+
+#[derive(Args)]
+pub(crate) struct ReencryptArgs {
+	/// CSV or JSON Lines file, `-` for the standard input
+	#[arg(default_value = STDIO)]
+	file: PathBuf,
+	/// Format of the file [default: guessed from the file extension]
+	#[arg(short, long, value_enum)]
+	format: Option<Format>,
+	#[command(flatten)]
+	input: InputArgs,
+	/// File to write the re-encrypted data to, `-` for the standard output. It is only replaced
+	/// once every row has been re-encrypted and cannot be one of the input files.
+	#[arg(short, long, value_name = "FILE", default_value = STDIO)]
+	output: PathBuf,
+	/// Leading element of the key contexts, may be repeated
+	#[arg(short, long = "key-context", value_name = "ELEMENT")]
+	key_context: Vec<String>,
+	/// Column or JSON field containing encrypted data, may be repeated. Its key context is made of
+	/// the leading elements followed by ELEMENT, which defaults to the column's name.
+	#[arg(short, long = "column", value_name = "COLUMN[=ELEMENT]", value_parser = parse_column, required = true)]
+	columns: Vec<ColumnArg>,
+	/// Column or JSON field whose value is an element of the data context, may be repeated
+	#[arg(short, long = "data-context", value_name = "COLUMN")]
+	data_context: Vec<String>,
+	#[command(flatten)]
+	periodicity: PeriodicityArgs,
+	#[command(flatten)]
+	policy: PolicyArgs,
+	/// Only report the values which would be re-encrypted, without decrypting nor writing anything
+	#[arg(long, conflicts_with = "output")]
+	dry_run: bool,
+}
+
+pub(crate) fn reencrypt(args: ReencryptArgs) -> Result<()> {
+	if is_stdio(&args.file) && is_stdio(&args.input.ikm_list) {
+		return Err(Error::StdinUsedTwice);
+	}
+	if !args.dry_run
+		&& (is_same_file(&args.output, &args.file)
+			|| is_same_file(&args.output, &args.input.ikm_list))
+	{
+		return Err(Error::OutputIsInput(args.output));
+	}
+	let format = args
+		.format
+		.or_else(|| Format::from_path(&args.file))
+		.ok_or(Error::UnknownFormat)?;
+	let ikml = args.input.read()?;
+	let policy = args.policy.get_policy();
+	let coffio = Coffio::with_decryption_policy(&ikml, &policy);
+	let mut reencryptor = Reencryptor::new(&coffio, &ikml, &args, Timestamp::now()?)?;
+	let reader = open_input(&args.file)?;
+	let output_err = |e| Error::Io(args.output.clone(), e);
+	// The output file is written to a temporary file, so that a failure never leaves a partially
+	// re-encrypted file behind.
+	let mut output_file = if args.dry_run || is_stdio(&args.output) {
+		None
+	} else {
+		Some(AtomicFile::create(&args.output, false).map_err(output_err)?)
+	};
+	let writer: Box<dyn Write> = match &mut output_file {
+		Some(file) => Box::new(BufWriter::new(file)),
+		None if args.dry_run => Box::new(std::io::sink()),
+		None => Box::new(BufWriter::new(std::io::stdout().lock())),
+	};
+	match format {
+		Format::Csv => reencrypt_csv(&mut reencryptor, reader, writer, &args.output)?,
+		Format::Jsonl => {
+			reencrypt_jsonl(&mut reencryptor, reader, writer, &args.file, &args.output)?
+		}
+	}
+	if let Some(file) = output_file {
+		file.commit().map_err(output_err)?;
+	}
+	eprint!(
+		"{}",
+		reencryptor.summary.render(reencryptor.target, args.dry_run)
+	);
+	Ok(())
+}
+
+fn open_input(path: &Path) -> Result<Box<dyn Read>> {
+	if is_stdio(path) {
+		return Ok(Box::new(std::io::stdin().lock()));
+	}
+	let file = File::open(path).map_err(|e| Error::Io(path.to_path_buf(), e))?;
+	Ok(Box::new(file))
+}
+
+fn reencrypt_csv(
+	reencryptor: &mut Reencryptor<'_>,
+	reader: impl Read,
+	writer: impl Write,
+	output: &Path,
+) -> Result<()> {
+	let mut reader = csv::Reader::from_reader(reader);
+	let mut writer = csv::Writer::from_writer(writer);
+	let headers = reader.headers()?.clone();
+	let headers: Vec<String> = headers.iter().map(String::from).collect();
+	for column in reencryptor.get_required_columns() {
+		if !headers.iter().any(|h| h == column) {
+			return Err(Error::MissingColumn(column.to_string()));
+		}
+	}
+	writer.write_record(&headers)?;
+	for record in reader.records() {
+		let record = record?;
+		let line = record.position().map_or(0, |p| p.line());
+		let mut row = CsvRow {
+			headers: &headers,
+			values: record.iter().map(String::from).collect(),
+		};
+		reencryptor
+			.process_row(&mut row)
+			.map_err(|e| Error::Line(line, Box::new(e)))?;
+		writer.write_record(&row.values)?;
+	}
+	writer
+		.flush()
+		.map_err(|e| Error::Io(output.to_path_buf(), e))
+}
+
+fn reencrypt_jsonl(
+	reencryptor: &mut Reencryptor<'_>,
+	reader: impl Read,
+	mut writer: impl Write,
+	file: &Path,
+	output: &Path,
+) -> Result<()> {
+	let output_err = |e| Error::Io(output.to_path_buf(), e);
+	for (i, line) in BufReader::new(reader).lines().enumerate() {
+		let line_nb = i as u64 + 1;
+		let line = line.map_err(|e| Error::Io(file.to_path_buf(), e))?;
+		if line.trim().is_empty() {
+			continue;
+		}
+		let process = |reencryptor: &mut Reencryptor<'_>| -> Result<Map<String, Value>> {
+			let mut row = match serde_json::from_str(&line)? {
+				Value::Object(row) => row,
+				_ => return Err(Error::NotAnObject),
+			};
+			reencryptor.process_row(&mut row)?;
+			Ok(row)
+		};
+		let row = process(reencryptor).map_err(|e| Error::Line(line_nb, Box::new(e)))?;
+		serde_json::to_writer(&mut writer, &row)?;
+		writeln!(writer).map_err(output_err)?;
+	}
+	writer.flush().map_err(output_err)
+}
+
+/// Row of a file, whose values are accessed using the column's name.
+trait Row {
+	/// Returns the value of a column, `None` meaning the value is null.
+	fn get(&self, column: &str) -> Result<Option<String>>;
+
+	fn set(&mut self, column: &str, value: String);
+}
+
+struct CsvRow<'a> {
+	headers: &'a [String],
+	values: Vec<String>,
+}
+
+impl CsvRow<'_> {
+	fn get_index(&self, column: &str) -> Result<usize> {
+		self.headers
+			.iter()
+			.position(|h| h == column)
+			.filter(|&i| i < self.values.len())
+			.ok_or_else(|| Error::MissingColumn(column.to_string()))
+	}
+}
+
+impl Row for CsvRow<'_> {
+	fn get(&self, column: &str) -> Result<Option<String>> {
+		let i = self.get_index(column)?;
+		Ok(Some(self.values[i].clone()))
+	}
+
+	fn set(&mut self, column: &str, value: String) {
+		if let Ok(i) = self.get_index(column) {
+			self.values[i] = value;
+		}
+	}
+}
+
+impl Row for Map<String, Value> {
+	fn get(&self, column: &str) -> Result<Option<String>> {
+		match Map::get(self, column) {
+			Some(Value::Null) => Ok(None),
+			Some(Value::String(s)) => Ok(Some(s.clone())),
+			Some(v) => Ok(Some(v.to_string())),
+			None => Err(Error::MissingColumn(column.to_string())),
+		}
+	}
+
+	fn set(&mut self, column: &str, value: String) {
+		self.insert(column.to_string(), Value::String(value));
+	}
+}
+
+/// Number of rows and values re-encrypted for a given IKM.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+struct IkmSummary {
+	rows: u64,
+	values: u64,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+struct Summary {
+	rows: u64,
+	rows_touched: u64,
+	values_up_to_date: u64,
+	ikms: BTreeMap<IkmId, IkmSummary>,
+}
+
+impl Summary {
+	fn render(&self, target: IkmId, dry_run: bool) -> String {
+		let touched = if dry_run {
+			"rows to re-encrypt"
+		} else {
+			"re-encrypted rows"
+		};
+		let mut ret = format!("target IKM: {target}\n");
+		ret += &format!("rows: {}\n", self.rows);
+		ret += &format!("{touched}: {}\n", self.rows_touched);
+		ret += &format!("up-to-date values: {}\n", self.values_up_to_date);
+		if !self.ikms.is_empty() {
+			let header = ["IKM", "ROWS", "VALUES"].map(String::from);
+			let rows: Vec<_> = std::iter::once(header)
+				.chain(
+					self.ikms
+						.iter()
+						.map(|(id, s)| [id.to_string(), s.rows.to_string(), s.values.to_string()]),
+				)
+				.collect();
+			ret.push('\n');
+			ret += &render_table(&rows);
+		}
+		ret
+	}
+}
+
+/// Re-encrypt the values of the rows which have not been encrypted using the target IKM.
+struct Reencryptor<'a> {
+	coffio: &'a Coffio<'a>,
+	target: IkmId,
+	encryption_time: Timestamp,
+	columns: Vec<(String, KeyContext)>,
+	data_context: Vec<String>,
+	dry_run: bool,
+	summary: Summary,
+}
+
+impl<'a> Reencryptor<'a> {
+	fn new(
+		coffio: &'a Coffio<'a>,
+		ikml: &InputKeyMaterialList,
+		args: &ReencryptArgs,
+		encryption_time: Timestamp,
+	) -> Result<Self> {
+		// Coffio selects the most recently added usable IKM by default.
+		let target = ikml
+			.iter()
+			.rev()
+			.find(|ikm| ikm.is_usable_at(encryption_time))
+			.ok_or(coffio::Error::IkmNoneAvailable)?
+			.get_id();
+		let columns = args
+			.columns
+			.iter()
+			.map(|column| {
+				let mut elems = args.key_context.clone();
+				elems.push(column.key_context_elem.clone());
+				let key_ctx = args.periodicity.get_key_context(&elems);
+				(column.name.clone(), key_ctx)
+			})
+			.collect();
+		Ok(Self {
+			coffio,
+			target,
+			encryption_time,
+			columns,
+			data_context: args.data_context.clone(),
+			dry_run: args.dry_run,
+			summary: Summary::default(),
+		})
+	}
+
+	fn get_required_columns(&self) -> impl Iterator<Item = &str> {
+		self.columns
+			.iter()
+			.map(|(name, _)| name.as_str())
+			.chain(self.data_context.iter().map(String::as_str))
+	}
+
+	fn process_row(&mut self, row: &mut impl Row) -> Result<()> {
+		let data_ctx = self
+			.data_context
+			.iter()
+			.map(|column| Ok(row.get(column)?.unwrap_or_default()))
+			.collect::<Result<Vec<_>>>()?;
+		let data_ctx: Vec<&str> = data_ctx.iter().map(String::as_str).collect();
+		let data_ctx = DataContext::from(data_ctx.as_slice());
+		let mut touched = BTreeSet::new();
+		for (column, key_ctx) in &self.columns {
+			let value = match row.get(column)? {
+				Some(value) if !value.is_empty() => value,
+				_ => continue,
+			};
+			let ikm_id = CiphertextInfo::parse(&value)?.get_ikm_id();
+			if ikm_id == self.target {
+				self.summary.values_up_to_date += 1;
+				continue;
+			}
+			if !self.dry_run {
+				let data = self.coffio.decrypt(key_ctx, &data_ctx, &value)?;
+				let value =
+					self.coffio
+						.encrypt_at(key_ctx, &data_ctx, data, self.encryption_time)?;
+				row.set(column, value);
+			}
+			self.summary.ikms.entry(ikm_id).or_default().values += 1;
+			touched.insert(ikm_id);
+		}
+		self.summary.rows += 1;
+		if !touched.is_empty() {
+			self.summary.rows_touched += 1;
+		}
+		for ikm_id in touched {
+			self.summary.ikms.entry(ikm_id).or_default().rows += 1;
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use clap::Parser;
+	use std::time::Duration;
+
+	#[derive(Parser)]
+	struct TestCli {
+		#[command(flatten)]
+		args: ReencryptArgs,
+	}
+
+	fn get_args(args: &[&str]) -> ReencryptArgs {
+		let args = ["coffio", "-l", "list.ikml"].iter().chain(args);
+		TestCli::parse_from(args).args
+	}
+
+	fn get_ikm_list() -> InputKeyMaterialList {
+		let mut ikml = InputKeyMaterialList::new();
+		let not_before = Timestamp::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+		let not_after = Timestamp::UNIX_EPOCH + Duration::from_secs(4_000_000_000);
+		ikml.add_custom_ikm(coffio::DEFAULT_SCHEME, not_before, not_after)
+			.unwrap();
+		ikml
+	}
+
+	fn encrypt(coffio: &Coffio, column: &str, data_ctx: &str, data: &str) -> String {
+		let key_ctx: KeyContext = ["db", column].into();
+		coffio.encrypt(&key_ctx, &[data_ctx].into(), data).unwrap()
+	}
+
+	fn decrypt(coffio: &Coffio, column: &str, data_ctx: &str, data: &str) -> String {
+		let key_ctx: KeyContext = ["db", column].into();
+		let data = coffio.decrypt(&key_ctx, &[data_ctx].into(), data).unwrap();
+		String::from_utf8(data).unwrap()
+	}
+
+	#[test]
+	fn column() {
+		let column = parse_column("email").unwrap();
+		assert_eq!(column.name, "email");
+		assert_eq!(column.key_context_elem, "email");
+		let column = parse_column("mail=email").unwrap();
+		assert_eq!(column.name, "mail");
+		assert_eq!(column.key_context_elem, "email");
+		assert!(parse_column("=email").is_err());
+	}
+
+	#[test]
+	fn csv() {
+		let mut ikml = get_ikm_list();
+		let (old_email, old_phone) = {
+			let coffio = Coffio::new(&ikml);
+			(
+				encrypt(&coffio, "email", "1", "a@example.org"),
+				encrypt(&coffio, "phone", "1", "+33 1 23 45 67 89"),
+			)
+		};
+		ikml.add_ikm().unwrap();
+		let new_email = encrypt(&Coffio::new(&ikml), "email", "2", "b@example.org");
+		let input = format!("id,email,phone\n1,{old_email},{old_phone}\n2,{new_email},\n3,,\n");
+
+		let args = get_args(&["-k", "db", "-c", "email", "-c", "phone", "-d", "id"]);
+		let coffio = Coffio::new(&ikml);
+		let mut reencryptor =
+			Reencryptor::new(&coffio, &ikml, &args, Timestamp::now().unwrap()).unwrap();
+		let mut output = Vec::new();
+		reencrypt_csv(
+			&mut reencryptor,
+			input.as_bytes(),
+			&mut output,
+			Path::new("-"),
+		)
+		.unwrap();
+		let output = String::from_utf8(output).unwrap();
+		let lines: Vec<Vec<&str>> = output.lines().map(|l| l.split(',').collect()).collect();
+		assert_eq!(lines.len(), 4);
+		assert_eq!(lines[0], ["id", "email", "phone"]);
+		assert_ne!(lines[1][1], old_email);
+		assert_eq!(CiphertextInfo::parse(lines[1][1]).unwrap().get_ikm_id(), 2);
+		assert_eq!(decrypt(&coffio, "email", "1", lines[1][1]), "a@example.org");
+		assert_eq!(
+			decrypt(&coffio, "phone", "1", lines[1][2]),
+			"+33 1 23 45 67 89"
+		);
+		assert_eq!(lines[2], ["2", new_email.as_str(), ""]);
+		assert_eq!(lines[3], ["3", "", ""]);
+
+		let summary = reencryptor.summary;
+		assert_eq!(summary.rows, 3);
+		assert_eq!(summary.rows_touched, 1);
+		assert_eq!(summary.values_up_to_date, 1);
+		assert_eq!(summary.ikms[&1], IkmSummary { rows: 1, values: 2 });
+		let rendered = summary.render(2, false);
+		assert!(rendered.contains("re-encrypted rows: 1\n"));
+		assert!(rendered.ends_with("IKM  ROWS  VALUES\n1    1     2\n"));
+	}
+
+	#[test]
+	fn jsonl() {
+		let mut ikml = get_ikm_list();
+		let old = encrypt(&Coffio::new(&ikml), "mail", "42", "a@example.org");
+		ikml.add_ikm().unwrap();
+		let input = format!(
+			"{{\"id\":42,\"email\":\"{old}\",\"name\":\"Alice\"}}\n\n{{\"id\":43,\"email\":null}}\n"
+		);
+
+		let args = get_args(&["-k", "db", "-c", "email=mail", "-d", "id", "--dry-run"]);
+		let coffio = Coffio::new(&ikml);
+		let mut reencryptor =
+			Reencryptor::new(&coffio, &ikml, &args, Timestamp::now().unwrap()).unwrap();
+		let mut output = Vec::new();
+		reencrypt_jsonl(
+			&mut reencryptor,
+			input.as_bytes(),
+			&mut output,
+			Path::new("-"),
+			Path::new("-"),
+		)
+		.unwrap();
+		assert_eq!(
+			reencryptor.summary.ikms[&1],
+			IkmSummary { rows: 1, values: 1 }
+		);
+		let output = String::from_utf8(output).unwrap();
+		assert!(output.starts_with(&format!(
+			"{{\"id\":42,\"email\":\"{old}\",\"name\":\"Alice\"}}\n"
+		)));
+
+		let args = get_args(&["-k", "db", "-c", "email=mail", "-d", "id"]);
+		let mut reencryptor =
+			Reencryptor::new(&coffio, &ikml, &args, Timestamp::now().unwrap()).unwrap();
+		let mut output = Vec::new();
+		reencrypt_jsonl(
+			&mut reencryptor,
+			input.as_bytes(),
+			&mut output,
+			Path::new("-"),
+			Path::new("-"),
+		)
+		.unwrap();
+		let output = String::from_utf8(output).unwrap();
+		let lines: Vec<Value> = output
+			.lines()
+			.map(|l| serde_json::from_str(l).unwrap())
+			.collect();
+		assert_eq!(lines.len(), 2);
+		let email = lines[0]["email"].as_str().unwrap();
+		assert_eq!(CiphertextInfo::parse(email).unwrap().get_ikm_id(), 2);
+		assert_eq!(decrypt(&coffio, "mail", "42", email), "a@example.org");
+		assert_eq!(lines[0]["name"], "Alice");
+		assert_eq!(lines[1]["email"], Value::Null);
+	}
+
+	#[test]
+	fn errors() {
+		let ikml = get_ikm_list();
+		let coffio = Coffio::new(&ikml);
+		let args = get_args(&["-c", "email", "-d", "id"]);
+		let mut reencryptor =
+			Reencryptor::new(&coffio, &ikml, &args, Timestamp::now().unwrap()).unwrap();
+		let res = reencrypt_csv(
+			&mut reencryptor,
+			"email\nfoo\n".as_bytes(),
+			Vec::new(),
+			Path::new("-"),
+		);
+		assert!(matches!(res, Err(Error::MissingColumn(c)) if c == "id"));
+		let res = reencrypt_jsonl(
+			&mut reencryptor,
+			"{\"id\":1,\"email\":\"foo\"}".as_bytes(),
+			Vec::new(),
+			Path::new("-"),
+			Path::new("-"),
+		);
+		assert!(matches!(res, Err(Error::Line(1, _))));
+		let res = reencrypt_jsonl(
+			&mut reencryptor,
+			"[]".as_bytes(),
+			Vec::new(),
+			Path::new("-"),
+			Path::new("-"),
+		);
+		assert!(matches!(res, Err(Error::Line(1, e)) if matches!(*e, Error::NotAnObject)));
+
+		let ikml = InputKeyMaterialList::new();
+		let res = Reencryptor::new(&coffio, &ikml, &args, Timestamp::now().unwrap());
+		assert!(res.is_err());
+	}
+
+	#[test]
+	fn output_is_input() {
+		let path =
+			std::env::temp_dir().join(format!("coffio-reencrypt-{}.csv", std::process::id()));
+		std::fs::write(&path, "email\n").unwrap();
+		let path_str = path.to_str().unwrap();
+		let res = reencrypt(get_args(&[path_str, "-c", "email", "-o", path_str]));
+		assert!(matches!(res, Err(Error::OutputIsInput(p)) if p == path));
+		assert_eq!(std::fs::read_to_string(&path).unwrap(), "email\n");
+		std::fs::remove_file(&path).unwrap();
+	}
+}
@@ -0,0 +1,22 @@
+//! Rendering of tables displayed to the user.
+
+/// Render rows as a table whose columns are aligned, the first row being the header.
+pub(crate) fn render_table<const N: usize>(rows: &[[String; N]]) -> String {
+	let mut widths = [0; N];
+	for row in rows {
+		for (width, cell) in widths.iter_mut().zip(row) {
+			*width = (*width).max(cell.len());
+		}
+	}
+	let mut table = String::new();
+	for row in rows {
+		let line: Vec<_> = row
+			.iter()
+			.zip(widths)
+			.map(|(cell, width)| format!("{cell:width$}"))
+			.collect();
+		table += line.join("  ").trim_end();
+		table.push('\n');
+	}
+	table
+}
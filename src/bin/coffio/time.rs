@@ -0,0 +1,137 @@
+//! Conversion between timestamps and their textual representation, which is either a number of
+//! seconds since the Unix epoch or a UTC date.
+
+use coffio::Timestamp;
+
+const SECS_PER_DAY: u64 = 86_400;
+
+/// Parse a date, which is either a number of seconds since the Unix epoch or a UTC date using
+/// the `YYYY-MM-DD` or `YYYY-MM-DDTHH:MM:SSZ` format.
+pub(crate) fn parse_time(s: &str) -> Result<Timestamp, String> {
+	let secs = if !s.is_empty() && s.bytes().all(|b| b.is_ascii_digit()) {
+		s.parse().map_err(|_| format!("{s}: invalid timestamp"))?
+	} else {
+		parse_utc_date(s).ok_or_else(|| {
+			format!("{s}: invalid date, expected YYYY-MM-DD, YYYY-MM-DDTHH:MM:SSZ or a timestamp")
+		})?
+	};
+	Ok(Timestamp::from_secs(secs))
+}
+
+/// Format a date using the `YYYY-MM-DDTHH:MM:SSZ` format.
+pub(crate) fn format_time(time: Timestamp) -> String {
+	let secs = time.as_secs();
+	let (year, month, day) = civil_from_days(secs / SECS_PER_DAY);
+	let secs = secs % SECS_PER_DAY;
+	format!(
+		"{year:04}-{month:02}-{day:02}T{:02}:{:02}:{:02}Z",
+		secs / 3600,
+		secs % 3600 / 60,
+		secs % 60
+	)
+}
+
+fn parse_utc_date(s: &str) -> Option<u64> {
+	let (date, time) = match s.split_once('T') {
+		Some((date, time)) => (date, time.strip_suffix('Z')?),
+		None => (s, "00:00:00"),
+	};
+	let date = parse_fields(date, '-', [4, 2, 2])?;
+	let time = parse_fields(time, ':', [2, 2, 2])?;
+	let [year, month, day] = date;
+	if year < 1970 || !(1..=12).contains(&month) || day == 0 {
+		return None;
+	}
+	let days = days_from_civil(year, month, day);
+	if civil_from_days(days) != (year, month, day) {
+		return None;
+	}
+	let [hours, minutes, seconds] = time;
+	if hours > 23 || minutes > 59 || seconds > 59 {
+		return None;
+	}
+	Some(days * SECS_PER_DAY + hours * 3600 + minutes * 60 + seconds)
+}
+
+fn parse_fields(s: &str, separator: char, widths: [usize; 3]) -> Option<[u64; 3]> {
+	let mut fields = s.split(separator);
+	let mut res = [0; 3];
+	for (value, width) in res.iter_mut().zip(widths) {
+		let field = fields.next()?;
+		if field.len() != width || !field.bytes().all(|b| b.is_ascii_digit()) {
+			return None;
+		}
+		*value = field.parse().ok()?;
+	}
+	fields.next().is_none().then_some(res)
+}
+
+// Days since the Unix epoch of a date of the proleptic Gregorian calendar, adapted from Howard
+// Hinnant's algorithm: http://howardhinnant.github.io/date_algorithms.html
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+	let year = if month <= 2 { year - 1 } else { year };
+	let era = year / 400;
+	let yoe = year - era * 400;
+	let doy = (153 * ((month + 9) % 12) + 2) / 5 + day - 1;
+	let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+	era * 146_097 + doe - 719_468
+}
+
+fn civil_from_days(days: u64) -> (u64, u64, u64) {
+	let days = days + 719_468;
+	let era = days / 146_097;
+	let doe = days - era * 146_097;
+	let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+	let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+	let mp = (5 * doy + 2) / 153;
+	let day = doy - (153 * mp + 2) / 5 + 1;
+	let month = if mp < 10 { mp + 3 } else { mp - 9 };
+	let year = yoe + era * 400 + u64::from(month <= 2);
+	(year, month, day)
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn ts(secs: u64) -> Timestamp {
+		Timestamp::from_secs(secs)
+	}
+
+	#[test]
+	fn parse() {
+		assert_eq!(parse_time("0"), Ok(ts(0)));
+		assert_eq!(parse_time("1700000000"), Ok(ts(1_700_000_000)));
+		assert_eq!(parse_time("1970-01-01"), Ok(ts(0)));
+		assert_eq!(parse_time("2023-11-14T22:13:20Z"), Ok(ts(1_700_000_000)));
+		assert_eq!(parse_time("2024-02-29"), Ok(ts(1_709_164_800)));
+	}
+
+	#[test]
+	fn parse_invalid() {
+		for s in [
+			"",
+			"-1",
+			"1969-12-31",
+			"2023-02-29",
+			"2023-13-01",
+			"2023-11-14T22:13:20",
+			"2023-11-14T24:00:00Z",
+			"2023-11-14 22:13:20Z",
+			"2023-1-14",
+			"tomorrow",
+		] {
+			assert!(parse_time(s).is_err(), "{s}");
+		}
+	}
+
+	#[test]
+	fn format() {
+		assert_eq!(format_time(ts(0)), "1970-01-01T00:00:00Z");
+		assert_eq!(format_time(ts(1_700_000_000)), "2023-11-14T22:13:20Z");
+		assert_eq!(format_time(ts(1_709_251_199)), "2024-02-29T23:59:59Z");
+		let s = format_time(ts(4_102_444_800));
+		assert_eq!(s, "2100-01-01T00:00:00Z");
+		assert_eq!(parse_time(&s), Ok(ts(4_102_444_800)));
+	}
+}
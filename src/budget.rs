@@ -0,0 +1,226 @@
+use crate::canonicalization::canonicalize;
+use crate::context::KeyContext;
+use crate::error::{Error, Result};
+use crate::ikm::IkmId;
+use alloc::boxed::Box;
+use alloc::string::String;
+#[cfg(feature = "std")]
+use std::collections::HashMap;
+#[cfg(feature = "std")]
+use std::sync::Mutex;
+
+/// Identifier of a derived key, which is defined by the IKM, the key context and the time period.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct UsageKey {
+	ikm_id: IkmId,
+	key_context: String,
+	time_period: Option<u64>,
+}
+
+impl UsageKey {
+	pub(crate) fn new(ikm_id: IkmId, key_context: &KeyContext, time_period: Option<u64>) -> Self {
+		Self {
+			ikm_id,
+			key_context: canonicalize(&key_context.get_ctx_elems(None)),
+			time_period,
+		}
+	}
+
+	/// Returns the identifier of the IKM the key is derived from.
+	pub fn get_ikm_id(&self) -> IkmId {
+		self.ikm_id
+	}
+
+	/// Returns the canonicalized representation of the key context. This representation does not
+	/// contain any secret and can be used as a storage key.
+	pub fn get_key_context(&self) -> &str {
+		&self.key_context
+	}
+
+	/// Returns the time period of the key, if the key context is periodic.
+	pub fn get_time_period(&self) -> Option<u64> {
+		self.time_period
+	}
+}
+
+/// Storage for the number of encryptions performed using each derived key.
+///
+/// In order for the budget to be enforced across restarts and across several nodes, the counters
+/// should be kept in a persistent storage shared by all the nodes, such as the database in which
+/// the encrypted data is stored.
+pub trait UsageCounterStore: Send + Sync {
+	/// Atomically increment the counter associated with the key and return its new value. A counter
+	/// which does not exist yet starts at zero.
+	fn increment(&self, key: &UsageKey) -> Result<u64>;
+}
+
+/// Non-persistent [UsageCounterStore] which keeps the counters in memory.
+#[cfg(feature = "std")]
+#[derive(Debug, Default)]
+pub struct MemoryUsageCounterStore {
+	counters: Mutex<HashMap<UsageKey, u64>>,
+}
+
+#[cfg(feature = "std")]
+impl MemoryUsageCounterStore {
+	/// Create a new empty store.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+#[cfg(feature = "std")]
+impl UsageCounterStore for MemoryUsageCounterStore {
+	fn increment(&self, key: &UsageKey) -> Result<u64> {
+		let mut counters = self
+			.counters
+			.lock()
+			.map_err(|e| Error::UsageCounterStoreError(e.to_string()))?;
+		let counter = counters.entry(key.clone()).or_default();
+		*counter = counter.saturating_add(1);
+		Ok(*counter)
+	}
+}
+
+/// Limit the number of encryptions performed using each derived key.
+///
+/// Some encryption schemes can only be used a limited number of times with the same key. For
+/// example, because AES-GCM uses random 96-bit nonces, the same key should not be used more than
+/// 2<sup>32</sup> times. Since a new key is derived for each IKM, key context and time period,
+/// this limit is usually far away, but a frequently written column using long time periods may
+/// approach it.
+///
+/// Once the soft limit is exceeded, a warning is emitted for each encryption. Once the hard limit
+/// is exceeded, the encryption is refused. There is no limit by default.
+///
+/// # Examples
+///
+/// ```
+/// use coffio::{Coffio, EncryptionBudget, InputKeyMaterialList, KeyContext, MemoryUsageCounterStore};
+///
+/// let mut ikm_list = InputKeyMaterialList::new();
+/// let _ = ikm_list.add_ikm()?;
+/// let key_ctx: KeyContext = ["db name", "table name", "column name"].into();
+///
+/// let mut budget = EncryptionBudget::new(MemoryUsageCounterStore::new());
+/// budget.set_soft_limit(1).set_hard_limit(2);
+/// let mut coffio = Coffio::new(&ikm_list);
+/// coffio.set_encryption_budget(budget);
+///
+/// assert!(coffio.encrypt(&key_ctx, &[].into(), b"1").is_ok());
+/// assert!(coffio.encrypt(&key_ctx, &[].into(), b"2").is_ok()); // warning
+/// assert!(coffio.encrypt(&key_ctx, &[].into(), b"3").is_err());
+/// # Ok::<(), coffio::Error>(())
+/// ```
+pub struct EncryptionBudget {
+	store: Box<dyn UsageCounterStore>,
+	soft_limit: Option<u64>,
+	hard_limit: Option<u64>,
+}
+
+impl EncryptionBudget {
+	/// Create a new budget without any limit that will keep the counters in the specified store.
+	pub fn new(store: impl UsageCounterStore + 'static) -> Self {
+		Self {
+			store: Box::new(store),
+			soft_limit: None,
+			hard_limit: None,
+		}
+	}
+
+	/// Set the number of encryptions per derived key above which a warning is emitted.
+	pub fn set_soft_limit(&mut self, limit: u64) -> &mut Self {
+		self.soft_limit = Some(limit);
+		self
+	}
+
+	/// Set the number of encryptions per derived key above which the encryption is refused.
+	pub fn set_hard_limit(&mut self, limit: u64) -> &mut Self {
+		self.hard_limit = Some(limit);
+		self
+	}
+
+	pub(crate) fn consume(
+		&self,
+		ikm_id: IkmId,
+		key_context: &KeyContext,
+		time_period: Option<u64>,
+	) -> Result<()> {
+		if self.soft_limit.is_none() && self.hard_limit.is_none() {
+			return Ok(());
+		}
+		let key = UsageKey::new(ikm_id, key_context, time_period);
+		let count = self.store.increment(&key)?;
+		if self.hard_limit.is_some_and(|limit| count > limit) {
+			return Err(Error::EncryptionBudgetExhausted(ikm_id));
+		}
+		if self.soft_limit.is_some_and(|limit| count > limit) {
+			#[cfg(feature = "tracing")]
+			tracing::warn!(
+				ikm_id,
+				count,
+				"ikm {ikm_id}: encryption budget almost exhausted: {count} encryptions"
+			);
+			#[cfg(not(feature = "tracing"))]
+			log::warn!("ikm {ikm_id}: encryption budget almost exhausted: {count} encryptions");
+		}
+		Ok(())
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	fn get_ctx() -> KeyContext {
+		["db_name", "table_name", "column_name"].into()
+	}
+
+	#[test]
+	fn memory_store() {
+		let store = MemoryUsageCounterStore::new();
+		let key_1 = UsageKey::new(1, &get_ctx(), Some(42));
+		let key_2 = UsageKey::new(1, &get_ctx(), Some(43));
+		assert_eq!(store.increment(&key_1), Ok(1));
+		assert_eq!(store.increment(&key_1), Ok(2));
+		assert_eq!(store.increment(&key_2), Ok(1));
+		assert_eq!(store.increment(&key_1), Ok(3));
+	}
+
+	#[test]
+	fn usage_key() {
+		let key = UsageKey::new(1, &get_ctx(), Some(42));
+		assert_eq!(key.get_ikm_id(), 1);
+		assert_eq!(
+			key.get_key_context(),
+			"ZGJfbmFtZQ:dGFibGVfbmFtZQ:Y29sdW1uX25hbWU"
+		);
+		assert_eq!(key.get_time_period(), Some(42));
+		assert_ne!(key, UsageKey::new(2, &get_ctx(), Some(42)));
+		assert_ne!(key, UsageKey::new(1, &["db_name"].into(), Some(42)));
+	}
+
+	#[test]
+	fn consume() {
+		let mut budget = EncryptionBudget::new(MemoryUsageCounterStore::new());
+		budget.set_soft_limit(1).set_hard_limit(3);
+		let ctx = get_ctx();
+		for _ in 0..3 {
+			assert_eq!(budget.consume(1, &ctx, None), Ok(()));
+		}
+		assert_eq!(
+			budget.consume(1, &ctx, None),
+			Err(Error::EncryptionBudgetExhausted(1))
+		);
+		assert_eq!(budget.consume(1, &ctx, Some(1)), Ok(()));
+		assert_eq!(budget.consume(2, &ctx, None), Ok(()));
+	}
+
+	#[test]
+	fn consume_no_limit() {
+		let budget = EncryptionBudget::new(MemoryUsageCounterStore::new());
+		for _ in 0..10 {
+			assert_eq!(budget.consume(1, &get_ctx(), None), Ok(()));
+		}
+	}
+}
@@ -1,3 +1,4 @@
+use alloc::string::String;
 use base64ct::{Base64UrlUnpadded, Encoding};
 
 const CANONICALIZATION_BUFFER_SIZE: usize = 1024;
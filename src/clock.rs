@@ -0,0 +1,262 @@
+#[cfg(feature = "std")]
+use crate::error::Error;
+use crate::error::Result;
+use core::ops::{Add, Sub};
+use core::time::Duration;
+#[cfg(any(test, feature = "test-utils"))]
+use std::sync::{Arc, Mutex};
+#[cfg(feature = "std")]
+use std::time::SystemTime;
+
+/// Point in time used throughout Coffio, expressed as the number of seconds elapsed since the Unix
+/// epoch (1970-01-01T00:00:00Z), not counting leap seconds.
+///
+/// This type is the same whether or not the `std` feature is enabled. If it is, timestamps can be
+/// converted from and to [std::time::SystemTime], the sub-second part being truncated.
+///
+/// # Examples
+///
+/// ```
+/// use coffio::Timestamp;
+/// use std::time::{Duration, SystemTime};
+///
+/// let ts = Timestamp::from_secs(1_700_000_000);
+/// assert_eq!(ts + Duration::from_secs(42), Timestamp::from_secs(1_700_000_042));
+/// let system_time = SystemTime::try_from(ts)?;
+/// assert_eq!(Timestamp::try_from(system_time)?, ts);
+/// # Ok::<(), coffio::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Timestamp(u64);
+
+impl Timestamp {
+	/// The Unix epoch (1970-01-01T00:00:00Z).
+	pub const UNIX_EPOCH: Self = Self(0);
+
+	/// Returns the time corresponding to the specified number of seconds since the Unix epoch.
+	pub const fn from_secs(secs: u64) -> Self {
+		Self(secs)
+	}
+
+	/// Returns the number of seconds elapsed between the Unix epoch and this time.
+	pub const fn as_secs(self) -> u64 {
+		self.0
+	}
+
+	/// Returns the current system time.
+	///
+	/// Fails if the system time is before the Unix epoch, which means the system clock is not
+	/// set properly.
+	///
+	/// On `wasm32-unknown-unknown`, the standard library is unable to retrieve the system time.
+	/// Therefore, if the `wasm-js` feature is enabled, it is retrieved from JavaScript.
+	#[cfg(feature = "std")]
+	pub fn now() -> Result<Self> {
+		#[cfg(all(feature = "wasm-js", target_arch = "wasm32", target_os = "unknown"))]
+		return Ok(Self((js_sys::Date::now() / 1000.0) as u64));
+		#[cfg(not(all(feature = "wasm-js", target_arch = "wasm32", target_os = "unknown")))]
+		SystemTime::now().try_into()
+	}
+
+	/// Adds a duration to this time, returning `None` on overflow.
+	pub fn checked_add(self, duration: Duration) -> Option<Self> {
+		self.0.checked_add(duration.as_secs()).map(Self)
+	}
+
+	/// Subtracts a duration from this time, returning `None` if the result would be before the
+	/// Unix epoch.
+	pub fn checked_sub(self, duration: Duration) -> Option<Self> {
+		self.0.checked_sub(duration.as_secs()).map(Self)
+	}
+}
+
+impl From<u64> for Timestamp {
+	fn from(secs: u64) -> Self {
+		Self(secs)
+	}
+}
+
+impl From<Timestamp> for u64 {
+	fn from(time: Timestamp) -> Self {
+		time.0
+	}
+}
+
+impl Add<Duration> for Timestamp {
+	type Output = Self;
+
+	/// # Panics
+	///
+	/// This function panics if the resulting time cannot be represented.
+	fn add(self, duration: Duration) -> Self {
+		self.checked_add(duration)
+			.expect("overflow when adding duration to timestamp")
+	}
+}
+
+impl Sub<Duration> for Timestamp {
+	type Output = Self;
+
+	/// # Panics
+	///
+	/// This function panics if the resulting time would be before the Unix epoch.
+	fn sub(self, duration: Duration) -> Self {
+		self.checked_sub(duration)
+			.expect("overflow when subtracting duration from timestamp")
+	}
+}
+
+/// Fails if the system time is before the Unix epoch.
+#[cfg(feature = "std")]
+impl TryFrom<SystemTime> for Timestamp {
+	type Error = Error;
+
+	fn try_from(time: SystemTime) -> core::result::Result<Self, Self::Error> {
+		Ok(Self(time.duration_since(SystemTime::UNIX_EPOCH)?.as_secs()))
+	}
+}
+
+/// Fails if the timestamp cannot be represented as a system time on the current platform.
+#[cfg(feature = "std")]
+impl TryFrom<Timestamp> for SystemTime {
+	type Error = Error;
+
+	fn try_from(time: Timestamp) -> core::result::Result<Self, Self::Error> {
+		SystemTime::UNIX_EPOCH
+			.checked_add(Duration::from_secs(time.0))
+			.ok_or(Error::SystemTimeReprError(time.0))
+	}
+}
+
+/// Source of the current time used by [Coffio][crate::Coffio] and
+/// [DecryptionPolicy][crate::DecryptionPolicy].
+pub trait Clock: Send + Sync {
+	/// Returns the current time, or an error if it cannot be retrieved.
+	fn now(&self) -> Result<Timestamp>;
+}
+
+/// Clock returning the system time. This is the default clock.
+#[cfg(feature = "std")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct SystemClock;
+
+#[cfg(feature = "std")]
+impl Clock for SystemClock {
+	fn now(&self) -> Result<Timestamp> {
+		Timestamp::now()
+	}
+}
+
+/// Controllable clock, useful to test how time-dependent operations behave.
+///
+/// Clones share the same time, which allows to keep control of the clock once it has been given to
+/// [Coffio][crate::Coffio].
+///
+/// # Examples
+///
+/// ```
+/// # #[cfg(feature = "test-utils")]
+/// # {
+/// use coffio::{Clock, MockClock, Timestamp};
+/// use std::time::Duration;
+///
+/// let clock = MockClock::new(Timestamp::UNIX_EPOCH);
+/// let clock_bis = clock.clone();
+/// clock.advance(Duration::from_secs(42));
+/// assert_eq!(clock_bis.now()?, Timestamp::from_secs(42));
+/// # }
+/// # Ok::<(), coffio::Error>(())
+/// ```
+#[cfg(any(test, feature = "test-utils"))]
+#[derive(Clone, Debug)]
+pub struct MockClock {
+	time: Arc<Mutex<Timestamp>>,
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl MockClock {
+	/// Create a new clock set to the specified time.
+	pub fn new(time: Timestamp) -> Self {
+		Self {
+			time: Arc::new(Mutex::new(time)),
+		}
+	}
+
+	/// Set the clock to the specified time.
+	pub fn set(&self, time: Timestamp) {
+		*self.time.lock().unwrap_or_else(|e| e.into_inner()) = time;
+	}
+
+	/// Move the clock forward by the specified duration.
+	pub fn advance(&self, duration: Duration) {
+		let mut time = self.time.lock().unwrap_or_else(|e| e.into_inner());
+		*time = time
+			.checked_add(duration)
+			.expect("overflow when advancing the clock");
+	}
+}
+
+#[cfg(any(test, feature = "test-utils"))]
+impl Clock for MockClock {
+	fn now(&self) -> Result<Timestamp> {
+		Ok(*self.time.lock().unwrap_or_else(|e| e.into_inner()))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn mock_clock() {
+		let clock = MockClock::new(Timestamp::UNIX_EPOCH);
+		let clock_bis = clock.clone();
+		assert_eq!(clock_bis.now(), Ok(Timestamp::UNIX_EPOCH));
+		clock.advance(Duration::from_secs(42));
+		assert_eq!(clock_bis.now(), Ok(Timestamp::from_secs(42)));
+		let time = Timestamp::from_secs(1_700_000_000);
+		clock_bis.set(time);
+		assert_eq!(clock.now(), Ok(time));
+	}
+
+	#[test]
+	fn timestamp_arithmetic() {
+		let time = Timestamp::from_secs(1_700_000_000);
+		assert_eq!(time.as_secs(), 1_700_000_000);
+		assert_eq!(u64::from(time), 1_700_000_000);
+		assert_eq!(Timestamp::from(1_700_000_000), time);
+		assert_eq!(Timestamp::UNIX_EPOCH.as_secs(), 0);
+		assert_eq!(
+			time.checked_add(Duration::from_secs(42)),
+			Some(Timestamp::from_secs(1_700_000_042))
+		);
+		assert_eq!(
+			time - Duration::from_millis(1_500),
+			Timestamp::from_secs(1_699_999_999)
+		);
+		assert!(time.checked_add(Duration::MAX).is_none());
+		assert!(
+			time.checked_sub(Duration::from_secs(1_700_000_001))
+				.is_none()
+		);
+	}
+
+	#[test]
+	fn system_time_conversion() {
+		use std::time::SystemTime;
+
+		let system_time = SystemTime::UNIX_EPOCH + Duration::from_millis(1_700_000_000_500);
+		let time = Timestamp::try_from(system_time).unwrap();
+		assert_eq!(time, Timestamp::from_secs(1_700_000_000));
+		assert_eq!(
+			SystemTime::try_from(time),
+			Ok(SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000))
+		);
+		assert!(Timestamp::try_from(SystemTime::UNIX_EPOCH - Duration::from_secs(1)).is_err());
+		assert_eq!(
+			SystemTime::try_from(Timestamp::from_secs(u64::MAX)),
+			Err(Error::SystemTimeReprError(u64::MAX))
+		);
+		assert!(Timestamp::now().unwrap() > time);
+	}
+}
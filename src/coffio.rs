@@ -1,10 +1,18 @@
+use crate::audit::{AuditEvent, AuditObserver, AuditOperation};
+use crate::budget::EncryptionBudget;
 use crate::canonicalization::{canonicalize, join_canonicalized_str};
+use crate::clock::{Clock, Timestamp};
 use crate::context::{DataContext, KeyContext};
-use crate::error::Result;
+use crate::error::{Error, Result};
 use crate::kdf::derive_key;
+#[cfg(feature = "metrics")]
+use crate::metrics::{MetricsSnapshot, UsageMetrics};
 use crate::policy::DecryptionPolicy;
+use crate::selection::{IkmSelectionStrategy, LatestIkm};
 use crate::{IkmId, InputKeyMaterialList, storage};
-use std::time::{SystemTime, UNIX_EPOCH};
+use alloc::boxed::Box;
+use alloc::string::String;
+use alloc::vec::Vec;
 
 /// Base structure used to encrypt and decrypt data.
 ///
@@ -37,6 +45,12 @@ use std::time::{SystemTime, UNIX_EPOCH};
 pub struct Coffio<'a> {
 	ikm_list: &'a InputKeyMaterialList,
 	decryption_policy: DecryptionPolicy,
+	selection_strategy: Box<dyn IkmSelectionStrategy>,
+	encryption_budget: Option<EncryptionBudget>,
+	audit_observer: Option<Box<dyn AuditObserver>>,
+	clock: Option<Box<dyn Clock>>,
+	#[cfg(feature = "metrics")]
+	metrics: Option<UsageMetrics>,
 }
 
 impl<'a> Coffio<'a> {
@@ -45,6 +59,12 @@ impl<'a> Coffio<'a> {
 		Self {
 			ikm_list,
 			decryption_policy: DecryptionPolicy::default(),
+			selection_strategy: Box::new(LatestIkm),
+			encryption_budget: None,
+			audit_observer: None,
+			clock: None,
+			#[cfg(feature = "metrics")]
+			metrics: None,
 		}
 	}
 
@@ -56,11 +76,121 @@ impl<'a> Coffio<'a> {
 		Self {
 			ikm_list,
 			decryption_policy: *policy,
+			selection_strategy: Box::new(LatestIkm),
+			encryption_budget: None,
+			audit_observer: None,
+			clock: None,
+			#[cfg(feature = "metrics")]
+			metrics: None,
+		}
+	}
+
+	/// Set the strategy used to select the IKM when encrypting data.
+	/// Default value is [LatestIkm][crate::LatestIkm].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{Coffio, InputKeyMaterialList, SkipExpiringWithin};
+	/// use std::time::Duration;
+	///
+	/// let mut ikm_list = InputKeyMaterialList::new();
+	/// let _ = ikm_list.add_ikm()?;
+	/// let mut coffio = Coffio::new(&ikm_list);
+	/// coffio.set_selection_strategy(SkipExpiringWithin(Duration::from_secs(2_592_000)));
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn set_selection_strategy(
+		&mut self,
+		strategy: impl IkmSelectionStrategy + 'static,
+	) -> &mut Self {
+		self.selection_strategy = Box::new(strategy);
+		self
+	}
+
+	/// Set the budget limiting the number of encryptions performed using each derived key.
+	/// There is no budget by default.
+	pub fn set_encryption_budget(&mut self, budget: EncryptionBudget) -> &mut Self {
+		self.encryption_budget = Some(budget);
+		self
+	}
+
+	/// Set the clock used to get the current time when encrypting and decrypting data.
+	/// Default value is [SystemClock][crate::SystemClock].
+	///
+	/// If the `std` feature is disabled, there is no default clock: encrypting and decrypting data
+	/// fails with [ClockUnavailable][Error::ClockUnavailable] until a clock has been set.
+	pub fn set_clock(&mut self, clock: impl Clock + 'static) -> &mut Self {
+		self.clock = Some(Box::new(clock));
+		self
+	}
+
+	fn now(&self) -> Result<Timestamp> {
+		match &self.clock {
+			Some(clock) => clock.now(),
+			#[cfg(feature = "std")]
+			None => Timestamp::now(),
+			#[cfg(not(feature = "std"))]
+			None => Err(Error::ClockUnavailable),
+		}
+	}
+
+	/// Set the observer notified after each encryption and decryption.
+	/// There is no observer by default.
+	pub fn set_audit_observer(&mut self, observer: impl AuditObserver + 'static) -> &mut Self {
+		self.audit_observer = Some(Box::new(observer));
+		self
+	}
+
+	/// Start counting the encryptions, decryptions and failures performed by this instance.
+	/// The counters are broken down by IKM, scheme and key context.
+	#[cfg(feature = "metrics")]
+	pub fn enable_metrics(&mut self) -> &mut Self {
+		if self.metrics.is_none() {
+			self.metrics = Some(UsageMetrics::default());
+		}
+		self
+	}
+
+	/// Returns a copy of the current usage counters, or `None` if the metrics have not been
+	/// enabled.
+	#[cfg(feature = "metrics")]
+	pub fn get_metrics_snapshot(&self) -> Option<MetricsSnapshot> {
+		self.metrics.as_ref().map(|metrics| metrics.snapshot())
+	}
+
+	fn notify<'b, T>(&self, mut event: AuditEvent<'b>, res: &'b Result<T>) {
+		event.error = res.as_ref().err();
+		#[cfg(any(feature = "metrics", feature = "tracing"))]
+		let scheme = event
+			.ikm_id
+			.and_then(|id| self.ikm_list.get_ikm_by_id(id).ok())
+			.map(|ikm| ikm.scheme);
+		#[cfg(feature = "tracing")]
+		{
+			let span = tracing::Span::current();
+			span.record("ikm_id", event.ikm_id);
+			span.record("scheme", scheme.map(tracing::field::debug));
+			span.record("time_period", event.time_period);
+			if event.operation == AuditOperation::Decrypt && event.ikm_id.is_some() {
+				span.record(
+					"policy_outcome",
+					crate::policy::get_policy_outcome(&event.policy_decisions),
+				);
+			}
+			span.record("success", event.is_success());
+		}
+		#[cfg(feature = "metrics")]
+		if let Some(metrics) = &self.metrics {
+			metrics.record(&event, scheme);
+		}
+		if let Some(observer) = &self.audit_observer {
+			observer.on_event(&event);
 		}
 	}
 
 	#[inline]
-	fn generate_aad(
+	pub(crate) fn generate_aad(
 		ikm_id: IkmId,
 		nonce: &[u8],
 		key_context: &KeyContext,
@@ -88,7 +218,7 @@ impl<'a> Coffio<'a> {
 		data_context: &DataContext,
 		data: impl AsRef<[u8]>,
 	) -> Result<String> {
-		self.process_encrypt_at(key_context, data_context, data, SystemTime::now())
+		self.process_encrypt_at(key_context, data_context, data, self.now()?)
 	}
 
 	/// Encrypt data using a key context and a data context. If the key is periodic, use the
@@ -99,7 +229,7 @@ impl<'a> Coffio<'a> {
 		key_context: &KeyContext,
 		data_context: &DataContext,
 		data: impl AsRef<[u8]>,
-		encryption_time: SystemTime,
+		encryption_time: Timestamp,
 	) -> Result<String> {
 		self.process_encrypt_at(key_context, data_context, data, encryption_time)
 	}
@@ -109,15 +239,54 @@ impl<'a> Coffio<'a> {
 		key_context: &KeyContext,
 		data_context: &DataContext,
 		data: impl AsRef<[u8]>,
-		encryption_time: SystemTime,
+		encryption_time: Timestamp,
+	) -> Result<String> {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::info_span!(
+			"coffio.encrypt",
+			ikm_id = tracing::field::Empty,
+			scheme = tracing::field::Empty,
+			time_period = tracing::field::Empty,
+			payload_size = data.as_ref().len(),
+			success = tracing::field::Empty,
+		)
+		.entered();
+		let mut event = AuditEvent::new(AuditOperation::Encrypt, key_context);
+		let res = self.process_encrypt_with_event(
+			key_context,
+			data_context,
+			data,
+			encryption_time,
+			&mut event,
+		);
+		self.notify(event, &res);
+		res
+	}
+
+	fn process_encrypt_with_event(
+		&self,
+		key_context: &KeyContext,
+		data_context: &DataContext,
+		data: impl AsRef<[u8]>,
+		encryption_time: Timestamp,
+		event: &mut AuditEvent<'_>,
 	) -> Result<String> {
 		let tp = if key_context.is_periodic() {
-			let ts = encryption_time.duration_since(UNIX_EPOCH)?.as_secs();
+			let ts = encryption_time.as_secs();
 			key_context.get_time_period(ts)
 		} else {
 			None
 		};
-		let ikm = self.ikm_list.get_latest_ikm(encryption_time)?;
+		event.time_period = tp;
+		let candidates = self.ikm_list.get_usable_ikms(encryption_time);
+		let ikm = self
+			.selection_strategy
+			.select(&candidates, encryption_time)
+			.ok_or(Error::IkmNoneAvailable)?;
+		event.ikm_id = Some(ikm.id);
+		if let Some(budget) = &self.encryption_budget {
+			budget.consume(ikm.id, key_context, tp)?;
+		}
 		let key = derive_key(ikm, key_context, tp);
 		let gen_nonce_function = ikm.scheme.get_gen_nonce();
 		let nonce = gen_nonce_function()?;
@@ -133,10 +302,43 @@ impl<'a> Coffio<'a> {
 		key_context: &KeyContext,
 		data_context: &DataContext,
 		stored_data: &str,
+	) -> Result<Vec<u8>> {
+		#[cfg(feature = "tracing")]
+		let _span = tracing::info_span!(
+			"coffio.decrypt",
+			ikm_id = tracing::field::Empty,
+			scheme = tracing::field::Empty,
+			time_period = tracing::field::Empty,
+			payload_size = stored_data.len(),
+			policy_outcome = tracing::field::Empty,
+			success = tracing::field::Empty,
+		)
+		.entered();
+		let mut event = AuditEvent::new(AuditOperation::Decrypt, key_context);
+		let res =
+			self.process_decrypt_with_event(key_context, data_context, stored_data, &mut event);
+		self.notify(event, &res);
+		res
+	}
+
+	fn process_decrypt_with_event(
+		&self,
+		key_context: &KeyContext,
+		data_context: &DataContext,
+		stored_data: &str,
+		event: &mut AuditEvent<'_>,
 	) -> Result<Vec<u8>> {
 		let (ikm_id, encrypted_data, tp) = storage::decode_cipher(stored_data)?;
+		event.ikm_id = Some(ikm_id);
+		event.time_period = tp;
 		let ikm = self.ikm_list.get_ikm_by_id(ikm_id)?;
-		self.decryption_policy.check(ikm, key_context, tp)?;
+		self.decryption_policy.check_with_decisions(
+			ikm,
+			key_context,
+			tp,
+			self.now()?,
+			&mut event.policy_decisions,
+		)?;
 		let key = derive_key(ikm, key_context, tp);
 		let aad = Self::generate_aad(ikm.id, &encrypted_data.nonce, key_context, data_context, tp);
 		let decryption_function = ikm.scheme.get_decryption();
@@ -147,7 +349,8 @@ impl<'a> Coffio<'a> {
 #[cfg(test)]
 mod tests {
 	use super::*;
-	use crate::{DataContext, DecryptionPolicyAction, Error, KeyContext};
+	use crate::{DataContext, DecryptionPolicyAction, KeyContext, PolicyViolation};
+	use std::sync::{Arc, Mutex};
 
 	const TEST_CIPHERTEXT: &str = "enc-v1:AQAAAA:qpVDbGvu0wl2tQgfF5jngCWCoCq5d9gj:eTkOSKz9YyvJE8PyT1lAFn4hyeK_0l6tWU4yyHA-7WRCJ9G-HWNpqoKBxg:NgAAAAAAAAA";
 	const TEST_DATA: &[u8] = b"Lorem ipsum dolor sit amet.";
@@ -375,11 +578,27 @@ mod tests {
 
 		let invalid_key_ctx = KeyContext::from(["invalid", "key", "context"]);
 		let res = cb.decrypt(&invalid_key_ctx, &data_ctx, TEST_CIPHERTEXT);
-		assert!(res.is_err(), "failed error detection: invalid key context");
+		assert_eq!(
+			res,
+			Err(Error::DecryptionFailed),
+			"failed error detection: invalid key context"
+		);
 
 		let invalid_data_ctx = DataContext::from(["invalid", "data", "context"]);
 		let res = cb.decrypt(&key_ctx, &invalid_data_ctx, TEST_CIPHERTEXT);
-		assert!(res.is_err(), "failed error detection: invalid key context");
+		assert_eq!(
+			res,
+			Err(Error::DecryptionFailed),
+			"failed error detection: invalid data context"
+		);
+
+		let altered_ciphertext = TEST_CIPHERTEXT.replace(":qpVD", ":qpVE");
+		let res = cb.decrypt(&key_ctx, &data_ctx, &altered_ciphertext);
+		assert_eq!(
+			res,
+			Err(Error::DecryptionFailed),
+			"failed error detection: altered ciphertext"
+		);
 	}
 
 	#[test]
@@ -446,4 +665,268 @@ mod tests {
 		let res = cb.decrypt(&key_ctx, &data_ctx, &ciphertext);
 		assert_eq!(res, Err(Error::PolicyDecryptionEarly));
 	}
+
+	#[test]
+	#[cfg(all(feature = "ikm-management", feature = "encrypt-at"))]
+	fn with_revoked_ikm() {
+		use std::time::Duration;
+
+		const DAY: Duration = Duration::from_secs(86_400);
+		let now = Timestamp::now().unwrap();
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_custom_ikm(crate::DEFAULT_SCHEME, now - 1_000 * DAY, now + DAY);
+		let key_ctx = KeyContext::from(TEST_KEY_CTX);
+		let data_ctx = DataContext::from(TEST_DATA_CTX);
+		let res = Coffio::new(&lst).encrypt_at(&key_ctx, &data_ctx, TEST_DATA, now - 900 * DAY);
+		assert!(res.is_ok(), "res: {res:?}");
+		let ciphertext = res.unwrap();
+
+		let _ = lst.revoke_ikm(1);
+		let mut policy = DecryptionPolicy::default();
+		policy.set_revoked(DecryptionPolicyAction::Deny);
+		let cb = Coffio::with_decryption_policy(&lst, &policy);
+		let res = cb.decrypt(&key_ctx, &data_ctx, &ciphertext);
+		assert_eq!(res, Err(Error::PolicyDecryptionRevokedEncBefore));
+	}
+
+	#[test]
+	#[cfg(feature = "ikm-management")]
+	fn with_selection_strategy() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let key_ctx = get_static_key_ctx();
+		let data_ctx = DataContext::from(TEST_DATA_CTX);
+
+		let mut cb = Coffio::new(&lst);
+		cb.set_selection_strategy(crate::RoundRobin::new());
+		let prefixes = ["enc-v1:AQAAAA:", "enc-v1:AgAAAA:", "enc-v1:AQAAAA:"];
+		for prefix in prefixes {
+			let res = cb.encrypt(&key_ctx, &data_ctx, TEST_DATA);
+			assert!(res.is_ok(), "res: {res:?}");
+			let ciphertext = res.unwrap();
+			assert!(ciphertext.starts_with(prefix), "ciphertext: {ciphertext}");
+			let res = cb.decrypt(&key_ctx, &data_ctx, &ciphertext);
+			assert_eq!(res, Ok(TEST_DATA.to_vec()));
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "ikm-management")]
+	fn with_clock() {
+		use crate::clock::MockClock;
+		use std::time::Duration;
+
+		const DAY: Duration = Duration::from_secs(86_400);
+		let start = Timestamp::UNIX_EPOCH + Duration::from_secs(1_700_000_000);
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_custom_ikm(crate::DEFAULT_SCHEME, start, start + 10 * DAY);
+		let key_ctx = KeyContext::from(TEST_KEY_CTX);
+		let data_ctx = DataContext::from(TEST_DATA_CTX);
+		let mut policy = DecryptionPolicy::default();
+		policy.set_expired_now(DecryptionPolicyAction::Deny);
+		let clock = MockClock::new(start + DAY);
+
+		let mut cb = Coffio::with_decryption_policy(&lst, &policy);
+		cb.set_clock(clock.clone());
+		let res = cb.encrypt(&key_ctx, &data_ctx, TEST_DATA);
+		assert!(res.is_ok(), "res: {res:?}");
+		let ciphertext = res.unwrap();
+		assert!(ciphertext.ends_with(":NQAAAAAAAAA"), "{ciphertext}");
+		let res = cb.decrypt(&key_ctx, &data_ctx, &ciphertext);
+		assert_eq!(res, Ok(TEST_DATA.to_vec()));
+
+		clock.advance(30 * DAY);
+		let res = cb.decrypt(&key_ctx, &data_ctx, &ciphertext);
+		assert_eq!(res, Err(Error::PolicyDecryptionExpiredNow));
+		let res = cb.encrypt(&key_ctx, &data_ctx, TEST_DATA);
+		assert_eq!(res, Err(Error::IkmNoneAvailable));
+	}
+
+	#[test]
+	#[cfg(all(feature = "chacha", feature = "insecure-rng-for-testing"))]
+	fn with_seeded_random_source() {
+		use crate::rand::insecure::{SeededRandomSource, with_random_source};
+
+		let lst = get_ikm_lst_chacha20poly1305_blake3();
+		let key_ctx = get_static_key_ctx();
+		let data_ctx = DataContext::from(TEST_DATA_CTX);
+		let cb = Coffio::new(&lst);
+		let encrypt = || cb.encrypt(&key_ctx, &data_ctx, TEST_DATA).unwrap();
+		let ciphertext_1 = with_random_source(SeededRandomSource::new(42), encrypt);
+		let ciphertext_2 = with_random_source(SeededRandomSource::new(42), encrypt);
+		assert_eq!(ciphertext_1, ciphertext_2);
+		assert_ne!(encrypt(), ciphertext_1);
+	}
+
+	type TestEvent = (AuditOperation, Option<IkmId>, Vec<PolicyViolation>, bool);
+
+	#[derive(Default)]
+	struct TestObserver {
+		events: Arc<Mutex<Vec<TestEvent>>>,
+	}
+
+	impl AuditObserver for TestObserver {
+		fn on_event(&self, event: &AuditEvent<'_>) {
+			let violations = event
+				.get_policy_decisions()
+				.iter()
+				.map(|d| d.get_violation())
+				.collect();
+			self.events.lock().unwrap().push((
+				event.get_operation(),
+				event.get_ikm_id(),
+				violations,
+				event.is_success(),
+			));
+		}
+	}
+
+	#[test]
+	#[cfg(feature = "chacha")]
+	fn with_audit_observer() {
+		let lst = get_ikm_lst_chacha20poly1305_blake3();
+		let key_ctx = get_static_empty_key_ctx();
+		let data_ctx = DataContext::from([]);
+		let observer = TestObserver::default();
+		let events = observer.events.clone();
+		let mut cb = Coffio::new(&lst);
+		cb.set_audit_observer(observer);
+
+		let ciphertext = cb.encrypt(&key_ctx, &data_ctx, TEST_DATA).unwrap();
+		let _ = cb.decrypt(&key_ctx, &data_ctx, &ciphertext);
+		let key_ctx = KeyContext::from([]);
+		let ciphertext = "enc-v1:AQAAAA:jWn478VdGxXZ5R2SYBFBmoD9YT8t2ftO:w2Z-sLdBnJ8LO84x_YsNvFBOxw2VwH6C1JLEI50l4ELJH1cQbesyzhuxQA:AAAAAAAAAAA";
+		let _ = cb.decrypt(&key_ctx, &data_ctx, ciphertext);
+		let _ = cb.decrypt(&key_ctx, &data_ctx, "invalid");
+
+		let events = events.lock().unwrap();
+		assert_eq!(
+			*events,
+			vec![
+				(AuditOperation::Encrypt, Some(1), vec![], true),
+				(AuditOperation::Decrypt, Some(1), vec![], true),
+				(
+					AuditOperation::Decrypt,
+					Some(1),
+					vec![PolicyViolation::EarlyEnc],
+					false
+				),
+				(AuditOperation::Decrypt, None, vec![], false),
+			]
+		);
+	}
+
+	#[cfg(feature = "tracing")]
+	mod tracing_spans {
+		use super::*;
+		use std::collections::HashMap;
+		use tracing::field::{Field, Visit};
+		use tracing::span::{Attributes, Id, Record};
+		use tracing::{Event, Metadata};
+		use tracing_core::span::Current;
+
+		type SpanFields = HashMap<&'static str, String>;
+		type SpanList = Arc<Mutex<Vec<(&'static Metadata<'static>, SpanFields)>>>;
+
+		#[derive(Default)]
+		struct TestSubscriber {
+			spans: SpanList,
+			stack: Mutex<Vec<u64>>,
+		}
+
+		struct FieldVisitor<'a>(&'a mut SpanFields);
+
+		impl Visit for FieldVisitor<'_> {
+			fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+				self.0.insert(field.name(), format!("{value:?}"));
+			}
+
+			fn record_str(&mut self, field: &Field, value: &str) {
+				self.0.insert(field.name(), value.to_string());
+			}
+		}
+
+		impl tracing::Subscriber for TestSubscriber {
+			fn enabled(&self, _metadata: &Metadata<'_>) -> bool {
+				true
+			}
+
+			fn new_span(&self, span: &Attributes<'_>) -> Id {
+				let mut fields = SpanFields::new();
+				span.record(&mut FieldVisitor(&mut fields));
+				let mut spans = self.spans.lock().unwrap();
+				spans.push((span.metadata(), fields));
+				Id::from_u64(spans.len() as u64)
+			}
+
+			fn record(&self, span: &Id, values: &Record<'_>) {
+				let mut spans = self.spans.lock().unwrap();
+				let (_, fields) = &mut spans[span.into_u64() as usize - 1];
+				values.record(&mut FieldVisitor(fields));
+			}
+
+			fn record_follows_from(&self, _span: &Id, _follows: &Id) {}
+
+			fn event(&self, _event: &Event<'_>) {}
+
+			fn enter(&self, span: &Id) {
+				self.stack.lock().unwrap().push(span.into_u64());
+			}
+
+			fn exit(&self, _span: &Id) {
+				self.stack.lock().unwrap().pop();
+			}
+
+			fn current_span(&self) -> Current {
+				match self.stack.lock().unwrap().last() {
+					Some(id) => {
+						let metadata = self.spans.lock().unwrap()[*id as usize - 1].0;
+						Current::new(Id::from_u64(*id), metadata)
+					}
+					None => Current::none(),
+				}
+			}
+		}
+
+		#[test]
+		#[cfg(feature = "chacha")]
+		fn spans() {
+			let subscriber = TestSubscriber::default();
+			let spans = subscriber.spans.clone();
+			tracing::subscriber::with_default(subscriber, || {
+				let lst = get_ikm_lst_chacha20poly1305_blake3();
+				let key_ctx = get_static_key_ctx();
+				let data_ctx = DataContext::from(TEST_DATA_CTX);
+				let cb = Coffio::new(&lst);
+				let ciphertext = cb.encrypt(&key_ctx, &data_ctx, TEST_DATA).unwrap();
+				let _ = cb.decrypt(&key_ctx, &data_ctx, &ciphertext).unwrap();
+			});
+
+			let spans = spans.lock().unwrap();
+			let names: Vec<_> = spans.iter().map(|(m, _)| m.name()).collect();
+			assert_eq!(
+				names,
+				vec![
+					"coffio.import",
+					"coffio.encrypt",
+					"coffio.derive_key",
+					"coffio.decrypt",
+					"coffio.derive_key"
+				]
+			);
+			let get = |i: usize, name: &str| spans[i].1.get(name).map(String::as_str);
+			assert_eq!(get(0, "ikm_count"), Some("1"));
+			assert_eq!(get(1, "ikm_id"), Some("1"));
+			assert_eq!(get(1, "scheme"), Some("XChaCha20Poly1305WithBlake3"));
+			assert_eq!(get(1, "time_period"), None);
+			assert_eq!(get(1, "payload_size"), Some("27"));
+			assert_eq!(get(1, "success"), Some("true"));
+			assert_eq!(get(2, "ikm_id"), Some("1"));
+			assert_eq!(get(3, "ikm_id"), Some("1"));
+			assert_eq!(get(3, "payload_size"), Some("105"));
+			assert_eq!(get(3, "policy_outcome"), Some("pass"));
+			assert_eq!(get(3, "success"), Some("true"));
+		}
+	}
 }
@@ -1,4 +1,6 @@
-use std::num::NonZeroU64;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+use core::num::NonZeroU64;
 
 macro_rules! data_ctx_from_iter {
 	($self: ident, $ctx: ident) => {
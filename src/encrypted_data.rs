@@ -1,5 +1,54 @@
+use crate::error::Result;
+use crate::ikm::IkmId;
+use crate::storage;
+use alloc::vec::Vec;
+
 #[derive(Debug)]
 pub(crate) struct EncryptedData {
 	pub(crate) nonce: Vec<u8>,
 	pub(crate) ciphertext: Vec<u8>,
 }
+
+/// Non-secret metadata stored along with encrypted data.
+///
+/// This metadata can be read without the IKM list nor the contexts, which allows to know which
+/// IKM has been used to encrypt the data without decrypting it.
+///
+/// # Examples
+///
+/// ```
+/// use coffio::CiphertextInfo;
+///
+/// let stored_data = "enc-v1:AQAAAA:qpVDbGvu0wl2tQgfF5jngCWCoCq5d9gj:eTkOSKz9YyvJE8PyT1lAFn4hyeK_0l6tWU4yyHA-7WRCJ9G-HWNpqoKBxg:NgAAAAAAAAA";
+/// let info = CiphertextInfo::parse(stored_data)?;
+/// assert_eq!(info.get_ikm_id(), 1);
+/// assert_eq!(info.get_time_period(), Some(54));
+/// # Ok::<(), coffio::Error>(())
+/// ```
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CiphertextInfo {
+	ikm_id: IkmId,
+	time_period: Option<u64>,
+}
+
+impl CiphertextInfo {
+	/// Parse the metadata of some encrypted data. Since the data is not decrypted, this metadata
+	/// is not authenticated.
+	pub fn parse(stored_data: &str) -> Result<Self> {
+		let (ikm_id, _, time_period) = storage::decode_cipher(stored_data)?;
+		Ok(Self {
+			ikm_id,
+			time_period,
+		})
+	}
+
+	/// Returns the identifier of the IKM used to encrypt the data.
+	pub fn get_ikm_id(&self) -> IkmId {
+		self.ikm_id
+	}
+
+	/// Returns the time period used to encrypt the data, if the key context was periodic.
+	pub fn get_time_period(&self) -> Option<u64> {
+		self.time_period
+	}
+}
@@ -1,7 +1,55 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
 pub(crate) type Result<T, E = Error> = core::result::Result<T, E>;
 
+/// Broad classification of the errors.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[non_exhaustive]
+pub enum ErrorKind {
+	/// Some encoded data, such as an IKM list or encrypted data, is malformed.
+	Parse,
+	/// The operation has been denied by a policy.
+	Policy,
+	/// Some data could not be authenticated: it has been altered, or the wrong key or context has
+	/// been used.
+	Integrity,
+	/// The required IKM is not available.
+	KeyUnavailable,
+	/// The requested operation is invalid, for example because it conflicts with the current state
+	/// of the IKM list.
+	InvalidOperation,
+	/// Something went wrong within the system, such as the random source or the clock.
+	System,
+}
+
+impl ErrorKind {
+	/// Returns a stable string representation of the kind.
+	pub fn as_str(&self) -> &'static str {
+		match self {
+			ErrorKind::Parse => "parse",
+			ErrorKind::Policy => "policy",
+			ErrorKind::Integrity => "integrity",
+			ErrorKind::KeyUnavailable => "key_unavailable",
+			ErrorKind::InvalidOperation => "invalid_operation",
+			ErrorKind::System => "system",
+		}
+	}
+}
+
+impl core::fmt::Display for ErrorKind {
+	fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::fmt::Result {
+		f.write_str(self.as_str())
+	}
+}
+
 /// An error type representing all the things that can go wrong.
+///
+/// Each error has a [kind][Error::get_kind] as well as a numeric [code][Error::get_code] and a
+/// string [code][Error::get_code_str] which are stable across versions and may therefore be used
+/// to report errors across services. The hundreds of the numeric code match the kind.
 #[derive(thiserror::Error, Debug, PartialEq)]
+#[non_exhaustive]
 pub enum Error {
 	/// Something went wrong during the encryption or decryption using AES-GCM.
 	#[cfg(feature = "aes")]
@@ -11,12 +59,69 @@ pub enum Error {
 	#[cfg(feature = "chacha")]
 	#[error("cipher error: {0}")]
 	ChaCha20Poly1305Error(chacha20poly1305::Error),
+	/// The data could not be decrypted. In order not to reveal any information to an attacker,
+	/// this error is the same whether the ciphertext has been altered or the wrong key context or
+	/// data context has been used.
+	#[error("cipher error: unable to decrypt data")]
+	DecryptionFailed,
+	/// The encryption budget of the key derived from the IKM has been exhausted.
+	#[cfg(feature = "encryption")]
+	#[error("encryption error: {0}: encryption budget exhausted")]
+	EncryptionBudgetExhausted(crate::ikm::IkmId),
 	/// The IKM list does not contain any usable IKM.
 	#[error("ikm error: no input key material available")]
 	IkmNoneAvailable,
 	/// The requested IKM has not been found in the list.
 	#[error("ikm error: {0}: input key material not found")]
 	IkmNotFound(crate::ikm::IkmId),
+	/// The content of the IKM does not meet the size required by its scheme.
+	#[error("ikm error: invalid content length: got {1} instead of {0}")]
+	IkmInvalidContentLength(usize, usize),
+	/// An IKM with the same identifier is already present in the list.
+	#[error("ikm error: {0}: identifier already used")]
+	IkmDuplicateId(crate::ikm::IkmId),
+	/// The IKM identifier is not allowed.
+	#[error("ikm error: {0}: invalid identifier")]
+	IkmInvalidId(crate::ikm::IkmId),
+	/// An IKM with the same content is already present in the list.
+	#[error("ikm error: {0}: input key material already present")]
+	IkmDuplicateContent(crate::ikm::IkmId),
+	/// The IKM is not revoked.
+	#[error("ikm error: {0}: input key material not revoked")]
+	IkmNotRevoked(crate::ikm::IkmId),
+	/// The validity period of the IKM is inverted or empty.
+	#[error("ikm error: {0}: invalid validity period")]
+	IkmInvalidValidityPeriod(crate::ikm::IkmId),
+	/// The validity period of the IKM would either contain or be contained in the one of another
+	/// IKM using the same scheme, or would change which one of them starts first.
+	#[error("ikm error: {0}: validity period conflicts with IKM {1}")]
+	IkmValidityOrderConflict(crate::ikm::IkmId, crate::ikm::IkmId),
+	/// The requested IKM lifecycle state transition is not allowed.
+	#[error("ikm error: {0}: invalid state transition from {1:?} to {2:?}")]
+	IkmInvalidStateTransition(
+		crate::ikm::IkmId,
+		crate::state::IkmState,
+		crate::state::IkmState,
+	),
+	/// No reason has been given for an operation which requires one.
+	#[error("ikm error: missing reason")]
+	IkmMissingReason,
+	/// The requested IKM rotation schedule has an empty interval or ends too far in the future.
+	#[error("ikm error: invalid rotation schedule")]
+	IkmInvalidRotationSchedule,
+	/// Two IKM lists contain different IKMs sharing the same identifier.
+	#[error("ikm error: {0}: identifier used by different input key materials")]
+	IkmMergeIdCollision(crate::ikm::IkmId),
+	/// Two IKM lists contain the same IKM with a different validity period.
+	#[error("ikm error: {0}: conflicting validity periods")]
+	IkmMergeConflict(crate::ikm::IkmId),
+	/// An edited IKM list has not been derived from the current version of the list.
+	#[error("ikm error: stale IKM list: got generation {1} instead of {0}")]
+	IkmListStaleGeneration(crate::ikm::Generation, crate::ikm::Generation),
+	/// The signature of the IKM list is either missing or invalid.
+	#[cfg(feature = "ikm-signature")]
+	#[error("ikm error: invalid IKM list signature")]
+	IkmListInvalidSignature,
 	/// The nonce does not meet the required size.
 	#[error("encoded data: invalid nonce size: got {1} instead of {0}")]
 	InvalidNonceSize(usize, usize),
@@ -50,9 +155,25 @@ pub enum Error {
 	/// When parsing some encoded data, an invalid IKM list version has been encountered.
 	#[error("parsing error: encoded data: invalid IKML version")]
 	ParsingEncodedDataInvalidIkmlVersion,
+	/// When parsing some encoded data, an invalid IKM list record type has been encountered.
+	#[error("parsing error: encoded data: {0}: invalid IKML record type")]
+	ParsingEncodedDataInvalidIkmlRecordType(u8),
+	/// When parsing some encoded data, an invalid IKM list journal entry has been encountered.
+	#[error("parsing error: encoded data: invalid IKML journal entry")]
+	ParsingEncodedDataInvalidJournalEntry,
+	/// When parsing some encoded data, an invalid IKM revocation has been encountered.
+	#[error("parsing error: encoded data: invalid IKM revocation")]
+	ParsingEncodedDataInvalidRevocation,
+	/// When parsing some encoded data, an invalid IKM lifecycle state has been encountered.
+	#[error("parsing error: encoded data: invalid IKM state")]
+	ParsingEncodedDataInvalidIkmState,
 	/// When parsing some encoded data, an invalid encrypted data version has been encountered.
 	#[error("parsing error: encoded data: invalid encrypted data version")]
 	ParsingEncodedDataInvalidEncVersion,
+	/// When parsing an IKM list signing or verifying key, an invalid key has been encountered.
+	#[cfg(feature = "ikm-signature")]
+	#[error("parsing error: encoded data: invalid IKM list signature key")]
+	ParsingEncodedDataInvalidSignatureKey,
 	/// An invalid scheme has been encountered.
 	#[error("parsing error: scheme: {0}: unknown scheme")]
 	ParsingSchemeUnknownScheme(crate::scheme::SchemeSerializeType),
@@ -76,15 +197,193 @@ pub enum Error {
 	/// it.
 	#[error("policy error: decryption: currently revoked IKM")]
 	PolicyDecryptionRevoked,
+	/// Attempting to decrypt data previously encrypted using a pending IKM while policy denies it.
+	#[error("policy error: decryption: pending IKM")]
+	PolicyDecryptionPending,
+	/// Attempting to decrypt data previously encrypted using a destroyed IKM.
+	#[error("policy error: decryption: destroyed IKM")]
+	PolicyDecryptionDestroyed,
+	/// Attempting to decrypt data previously encrypted using a now revoked IKM before its
+	/// revocation date while policy denies it.
+	#[error("policy error: decryption: encrypted before the IKM revocation")]
+	PolicyDecryptionRevokedEncBefore,
+	/// Attempting to decrypt data previously encrypted using a revoked IKM after its revocation
+	/// date while policy denies it.
+	#[error("policy error: decryption: encrypted after the IKM revocation")]
+	PolicyDecryptionRevokedEncAfter,
 	/// Something went wrong when retrieving random data from the system.
 	#[error("unable to generate random values: {0}")]
 	RandomSourceError(getrandom::Error),
 	/// A `std::time::SystemTimeError` has been encountered.
 	#[error("system time error: {0}")]
 	SystemTimeError(String),
+	/// Something went wrong when accessing the usage counter store.
+	#[cfg(feature = "encryption")]
+	#[error("usage counter store error: {0}")]
+	UsageCounterStoreError(String),
 	/// Something went wrong when trying to parse a timestamp.
 	#[error("system time error: {0}: unable to represent this timestamp as a system time")]
 	SystemTimeReprError(u64),
+	/// The current time is required but no clock has been set. This can only happen if the `std`
+	/// feature is disabled.
+	#[cfg(feature = "encryption")]
+	#[error("system time error: no clock has been set")]
+	ClockUnavailable,
+}
+
+impl Error {
+	/// Returns the kind of the error.
+	pub fn get_kind(&self) -> ErrorKind {
+		self.get_description().0
+	}
+
+	/// Returns the stable numeric code of the error.
+	pub fn get_code(&self) -> u16 {
+		self.get_description().1
+	}
+
+	/// Returns the stable string code of the error.
+	pub fn get_code_str(&self) -> &'static str {
+		self.get_description().2
+	}
+
+	fn get_description(&self) -> (ErrorKind, u16, &'static str) {
+		match self {
+			// Parse
+			Error::ParsingBase64Error(_) => (ErrorKind::Parse, 100, "parsing_base64"),
+			Error::ParsingEncodedDataEmptyNonce => (ErrorKind::Parse, 101, "parsing_empty_nonce"),
+			Error::ParsingEncodedDataEmptyCiphertext => {
+				(ErrorKind::Parse, 102, "parsing_empty_ciphertext")
+			}
+			Error::ParsingEncodedDataInvalidIkmId(_) => {
+				(ErrorKind::Parse, 103, "parsing_invalid_ikm_id")
+			}
+			Error::ParsingEncodedDataInvalidIkmLen(_) => {
+				(ErrorKind::Parse, 104, "parsing_invalid_ikm_length")
+			}
+			Error::ParsingEncodedDataInvalidIkmListId(_) => {
+				(ErrorKind::Parse, 105, "parsing_invalid_ikm_list_id")
+			}
+			Error::ParsingEncodedDataInvalidIkmListLen(_) => {
+				(ErrorKind::Parse, 106, "parsing_invalid_ikm_list_length")
+			}
+			Error::ParsingEncodedDataInvalidPartLen(_, _) => {
+				(ErrorKind::Parse, 107, "parsing_invalid_part_count")
+			}
+			Error::ParsingEncodedDataInvalidTimestamp(_) => {
+				(ErrorKind::Parse, 108, "parsing_invalid_timestamp")
+			}
+			Error::ParsingEncodedDataInvalidIkmlVersion => {
+				(ErrorKind::Parse, 109, "parsing_invalid_ikm_list_version")
+			}
+			Error::ParsingEncodedDataInvalidIkmlRecordType(_) => (
+				ErrorKind::Parse,
+				110,
+				"parsing_invalid_ikm_list_record_type",
+			),
+			Error::ParsingEncodedDataInvalidJournalEntry => {
+				(ErrorKind::Parse, 111, "parsing_invalid_journal_entry")
+			}
+			Error::ParsingEncodedDataInvalidRevocation => {
+				(ErrorKind::Parse, 112, "parsing_invalid_revocation")
+			}
+			Error::ParsingEncodedDataInvalidIkmState => {
+				(ErrorKind::Parse, 113, "parsing_invalid_ikm_state")
+			}
+			Error::ParsingEncodedDataInvalidEncVersion => (
+				ErrorKind::Parse,
+				114,
+				"parsing_invalid_encrypted_data_version",
+			),
+			#[cfg(feature = "ikm-signature")]
+			Error::ParsingEncodedDataInvalidSignatureKey => {
+				(ErrorKind::Parse, 115, "parsing_invalid_signature_key")
+			}
+			Error::ParsingSchemeUnknownScheme(_) => {
+				(ErrorKind::Parse, 116, "parsing_unknown_scheme")
+			}
+			Error::InvalidNonceSize(_, _) => (ErrorKind::Parse, 117, "invalid_nonce_size"),
+			// Policy
+			Error::PolicyDecryptionEarly => (ErrorKind::Policy, 200, "policy_early_encryption"),
+			Error::PolicyDecryptionExpiredEnc => {
+				(ErrorKind::Policy, 201, "policy_expired_at_encryption")
+			}
+			Error::PolicyDecryptionExpiredNow => (ErrorKind::Policy, 202, "policy_expired_now"),
+			Error::PolicyDecryptionFuture => (ErrorKind::Policy, 203, "policy_future_encryption"),
+			Error::PolicyDecryptionRevoked => (ErrorKind::Policy, 204, "policy_revoked"),
+			Error::PolicyDecryptionPending => (ErrorKind::Policy, 205, "policy_pending"),
+			Error::PolicyDecryptionDestroyed => (ErrorKind::Policy, 206, "policy_destroyed"),
+			Error::PolicyDecryptionRevokedEncBefore => {
+				(ErrorKind::Policy, 207, "policy_encrypted_before_revocation")
+			}
+			Error::PolicyDecryptionRevokedEncAfter => {
+				(ErrorKind::Policy, 208, "policy_encrypted_after_revocation")
+			}
+			#[cfg(feature = "encryption")]
+			Error::EncryptionBudgetExhausted(_) => (ErrorKind::Policy, 209, "encryption_budget_exhausted"),
+			// Integrity
+			Error::DecryptionFailed => (ErrorKind::Integrity, 300, "decryption_failed"),
+			#[cfg(feature = "ikm-signature")]
+			Error::IkmListInvalidSignature => (ErrorKind::Integrity, 301, "ikm_list_invalid_signature"),
+			// Key unavailable
+			Error::IkmNoneAvailable => (ErrorKind::KeyUnavailable, 400, "ikm_none_available"),
+			Error::IkmNotFound(_) => (ErrorKind::KeyUnavailable, 401, "ikm_not_found"),
+			// Invalid operation
+			Error::IkmInvalidContentLength(_, _) => (
+				ErrorKind::InvalidOperation,
+				500,
+				"ikm_invalid_content_length",
+			),
+			Error::IkmDuplicateId(_) => (ErrorKind::InvalidOperation, 501, "ikm_duplicate_id"),
+			Error::IkmDuplicateContent(_) => {
+				(ErrorKind::InvalidOperation, 502, "ikm_duplicate_content")
+			}
+			Error::IkmNotRevoked(_) => (ErrorKind::InvalidOperation, 503, "ikm_not_revoked"),
+			Error::IkmInvalidValidityPeriod(_) => (
+				ErrorKind::InvalidOperation,
+				504,
+				"ikm_invalid_validity_period",
+			),
+			Error::IkmValidityOrderConflict(_, _) => (
+				ErrorKind::InvalidOperation,
+				505,
+				"ikm_validity_order_conflict",
+			),
+			Error::IkmInvalidStateTransition(_, _, _) => (
+				ErrorKind::InvalidOperation,
+				506,
+				"ikm_invalid_state_transition",
+			),
+			Error::IkmMissingReason => (ErrorKind::InvalidOperation, 507, "ikm_missing_reason"),
+			Error::IkmInvalidRotationSchedule => (
+				ErrorKind::InvalidOperation,
+				508,
+				"ikm_invalid_rotation_schedule",
+			),
+			Error::IkmMergeIdCollision(_) => {
+				(ErrorKind::InvalidOperation, 509, "ikm_merge_id_collision")
+			}
+			Error::IkmMergeConflict(_) => (ErrorKind::InvalidOperation, 510, "ikm_merge_conflict"),
+			Error::IkmListStaleGeneration(_, _) => (
+				ErrorKind::InvalidOperation,
+				511,
+				"ikm_list_stale_generation",
+			),
+			Error::IkmInvalidId(_) => (ErrorKind::InvalidOperation, 512, "ikm_invalid_id"),
+			// System
+			#[cfg(feature = "aes")]
+			Error::AesGcmError(_) => (ErrorKind::System, 600, "aes_gcm_error"),
+			#[cfg(feature = "chacha")]
+			Error::ChaCha20Poly1305Error(_) => (ErrorKind::System, 601, "chacha20poly1305_error"),
+			Error::RandomSourceError(_) => (ErrorKind::System, 602, "random_source_error"),
+			Error::SystemTimeError(_) => (ErrorKind::System, 603, "system_time_error"),
+			Error::SystemTimeReprError(_) => (ErrorKind::System, 604, "system_time_repr_error"),
+			#[cfg(feature = "encryption")]
+			Error::UsageCounterStoreError(_) => (ErrorKind::System, 605, "usage_counter_store_error"),
+			#[cfg(feature = "encryption")]
+			Error::ClockUnavailable => (ErrorKind::System, 606, "clock_unavailable"),
+		}
+	}
 }
 
 impl From<base64ct::Error> for Error {
@@ -113,8 +412,110 @@ impl From<getrandom::Error> for Error {
 	}
 }
 
+#[cfg(feature = "std")]
 impl From<std::time::SystemTimeError> for Error {
 	fn from(error: std::time::SystemTimeError) -> Self {
 		Error::SystemTimeError(error.to_string())
 	}
 }
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn description() {
+		let err = Error::DecryptionFailed;
+		assert_eq!(err.get_kind(), ErrorKind::Integrity);
+		assert_eq!(err.get_code(), 300);
+		assert_eq!(err.get_code_str(), "decryption_failed");
+		let err = Error::IkmNotFound(42);
+		assert_eq!(err.get_kind(), ErrorKind::KeyUnavailable);
+		assert_eq!(err.get_code(), 401);
+		assert_eq!(err.get_kind().to_string(), "key_unavailable");
+	}
+
+	#[test]
+	fn codes() {
+		use crate::state::IkmState;
+		use std::collections::HashSet;
+
+		// One instance of every variant, so that duplicated codes are detected.
+		let errors = [
+			#[cfg(feature = "aes")]
+			Error::AesGcmError(aes_gcm::Error),
+			#[cfg(feature = "chacha")]
+			Error::ChaCha20Poly1305Error(chacha20poly1305::Error),
+			Error::DecryptionFailed,
+			#[cfg(feature = "encryption")]
+			Error::EncryptionBudgetExhausted(42),
+			Error::IkmNoneAvailable,
+			Error::IkmNotFound(42),
+			Error::IkmInvalidContentLength(32, 16),
+			Error::IkmDuplicateId(42),
+			Error::IkmInvalidId(0),
+			Error::IkmDuplicateContent(42),
+			Error::IkmNotRevoked(42),
+			Error::IkmInvalidValidityPeriod(42),
+			Error::IkmValidityOrderConflict(42, 43),
+			Error::IkmInvalidStateTransition(42, IkmState::Active, IkmState::Pending),
+			Error::IkmMissingReason,
+			Error::IkmInvalidRotationSchedule,
+			Error::IkmMergeIdCollision(42),
+			Error::IkmMergeConflict(42),
+			Error::IkmListStaleGeneration(1, 2),
+			#[cfg(feature = "ikm-signature")]
+			Error::IkmListInvalidSignature,
+			Error::InvalidNonceSize(24, 12),
+			Error::ParsingBase64Error(base64ct::Error::InvalidEncoding),
+			Error::ParsingEncodedDataEmptyNonce,
+			Error::ParsingEncodedDataEmptyCiphertext,
+			Error::ParsingEncodedDataInvalidIkmId(vec![42]),
+			Error::ParsingEncodedDataInvalidIkmLen(42),
+			Error::ParsingEncodedDataInvalidIkmListId(vec![42]),
+			Error::ParsingEncodedDataInvalidIkmListLen(42),
+			Error::ParsingEncodedDataInvalidPartLen(4, 2),
+			Error::ParsingEncodedDataInvalidTimestamp(vec![42]),
+			Error::ParsingEncodedDataInvalidIkmlVersion,
+			Error::ParsingEncodedDataInvalidIkmlRecordType(42),
+			Error::ParsingEncodedDataInvalidJournalEntry,
+			Error::ParsingEncodedDataInvalidRevocation,
+			Error::ParsingEncodedDataInvalidIkmState,
+			Error::ParsingEncodedDataInvalidEncVersion,
+			#[cfg(feature = "ikm-signature")]
+			Error::ParsingEncodedDataInvalidSignatureKey,
+			Error::ParsingSchemeUnknownScheme(42),
+			Error::PolicyDecryptionEarly,
+			Error::PolicyDecryptionExpiredEnc,
+			Error::PolicyDecryptionExpiredNow,
+			Error::PolicyDecryptionFuture,
+			Error::PolicyDecryptionRevoked,
+			Error::PolicyDecryptionPending,
+			Error::PolicyDecryptionDestroyed,
+			Error::PolicyDecryptionRevokedEncBefore,
+			Error::PolicyDecryptionRevokedEncAfter,
+			Error::RandomSourceError(getrandom::Error::UNSUPPORTED),
+			Error::SystemTimeError("error".to_string()),
+			#[cfg(feature = "encryption")]
+			Error::UsageCounterStoreError("error".to_string()),
+			Error::SystemTimeReprError(42),
+			#[cfg(feature = "encryption")]
+			Error::ClockUnavailable,
+		];
+		for err in &errors {
+			let kind_digit = match err.get_kind() {
+				ErrorKind::Parse => 1,
+				ErrorKind::Policy => 2,
+				ErrorKind::Integrity => 3,
+				ErrorKind::KeyUnavailable => 4,
+				ErrorKind::InvalidOperation => 5,
+				ErrorKind::System => 6,
+			};
+			assert_eq!(err.get_code() / 100, kind_digit, "{err:?}");
+		}
+		let codes: HashSet<_> = errors.iter().map(|e| e.get_code()).collect();
+		assert_eq!(codes.len(), errors.len());
+		let codes_str: HashSet<_> = errors.iter().map(|e| e.get_code_str()).collect();
+		assert_eq!(codes_str.len(), errors.len());
+	}
+}
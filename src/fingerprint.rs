@@ -0,0 +1,171 @@
+use crate::ikm::{InputKeyMaterial, InputKeyMaterialList};
+use crate::scheme::SchemeSerializeType;
+use alloc::string::String;
+use base64ct::{Base64UrlUnpadded, Encoding};
+use sha2::{Digest, Sha256};
+
+const KCV_DOMAIN: &[u8] = b"coffio ikm key check value v1";
+const LIST_FINGERPRINT_DOMAIN: &[u8] = b"coffio ikm list fingerprint v1";
+const KCV_SIZE: usize = 16;
+
+impl InputKeyMaterial {
+	/// Returns the key check value (KCV) of the IKM.
+	///
+	/// The key check value is derived from the IKM's scheme and content using a one-way function.
+	/// It is not secret and can therefore be logged or displayed in order to check that two IKMs
+	/// share the same secret without having to compare the secrets themselves. The key check value
+	/// of a destroyed IKM is the one it had before its content has been erased.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let mut ikml = coffio::InputKeyMaterialList::new();
+	/// let _ = ikml.add_ikm()?;
+	/// let ikml2 = coffio::InputKeyMaterialList::import(&ikml.export()?)?;
+	/// assert_eq!(ikml[0].get_key_check_value(), ikml2[0].get_key_check_value());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn get_key_check_value(&self) -> String {
+		Base64UrlUnpadded::encode_string(&self.get_content_digest()[..KCV_SIZE])
+	}
+
+	pub(crate) fn get_content_digest(&self) -> [u8; 32] {
+		if let Some(digest) = self.content_digest {
+			return digest;
+		}
+		Sha256::new()
+			.chain_update(KCV_DOMAIN)
+			.chain_update((self.scheme as SchemeSerializeType).to_le_bytes())
+			.chain_update(&self.content)
+			.finalize()
+			.into()
+	}
+}
+
+impl InputKeyMaterialList {
+	/// Returns the fingerprint of the IKM list.
+	///
+	/// The fingerprint covers the identifier, the secret, the validity period, the revocation
+	/// status, date and reason, and the lifecycle state of every IKM in the list. It does not
+	/// depend on the list's [generation][Self::get_generation], hence two lists containing the same
+	/// IKMs share the same fingerprint. Like the
+	/// [key check value][InputKeyMaterial::get_key_check_value], it is not secret and may be logged
+	/// in order to check that several deployments use the same IKM list.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let mut ikml = coffio::InputKeyMaterialList::new();
+	/// let _ = ikml.add_ikm()?;
+	/// let ikml2 = coffio::InputKeyMaterialList::import(&ikml.export()?)?;
+	/// assert_eq!(ikml.get_fingerprint(), ikml2.get_fingerprint());
+	///
+	/// let _ = ikml.revoke_ikm(1)?;
+	/// assert_ne!(ikml.get_fingerprint(), ikml2.get_fingerprint());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn get_fingerprint(&self) -> String {
+		let mut hasher = Sha256::new().chain_update(LIST_FINGERPRINT_DOMAIN);
+		for ikm in &self.ikm_lst {
+			hasher.update(ikm.id.to_le_bytes());
+			hasher.update(ikm.get_content_digest());
+			hasher.update(ikm.not_before.as_secs().to_le_bytes());
+			hasher.update(ikm.not_after.as_secs().to_le_bytes());
+			hasher.update([ikm.is_revoked as u8]);
+			match ikm.get_revocation_reason() {
+				Some(reason) => hasher.update([1, reason as u8]),
+				None => hasher.update([0]),
+			}
+			match ikm.revoked_at.filter(|_| ikm.is_revoked) {
+				Some(revoked_at) => {
+					hasher.update([1]);
+					hasher.update(revoked_at.as_secs().to_le_bytes());
+				}
+				None => hasher.update([0]),
+			}
+			hasher.update([ikm.state as u8]);
+		}
+		Base64UrlUnpadded::encode_string(&hasher.finalize())
+	}
+}
+
+#[cfg(all(test, feature = "chacha"))]
+mod tests {
+	use super::*;
+
+	const TEST_STR: &str = "ikml-v1:AQAAAA:AQAAAAEAAAC_vYEw1ujVG5i-CtoPYSzik_6xaAq59odjPm5ij01-e6zz4mUAAAAALJGBiwAAAAAA";
+
+	#[test]
+	fn key_check_value() {
+		let lst = InputKeyMaterialList::import(TEST_STR).unwrap();
+		assert_eq!(lst[0].get_key_check_value(), "COtkoD4R3HnKzpjGCuvvww");
+	}
+
+	#[test]
+	#[cfg(feature = "aes")]
+	fn key_check_value_scheme() {
+		let lst = InputKeyMaterialList::import(TEST_STR).unwrap();
+		let mut ikm = lst[0].clone();
+		ikm.scheme = crate::Scheme::Aes128GcmWithSha256;
+		assert_ne!(ikm.get_key_check_value(), lst[0].get_key_check_value());
+		ikm.scheme = lst[0].scheme;
+		ikm.id = 42;
+		assert_eq!(ikm.get_key_check_value(), lst[0].get_key_check_value());
+	}
+
+	#[test]
+	fn list_fingerprint() {
+		let lst = InputKeyMaterialList::import(TEST_STR).unwrap();
+		assert_eq!(
+			lst.get_fingerprint(),
+			"xKitDI7Gw-K6jGpu9NxX5ktnkZ9_Gwh5yHwOfLoBfWU"
+		);
+		assert_ne!(
+			InputKeyMaterialList::new().get_fingerprint(),
+			lst.get_fingerprint()
+		);
+	}
+
+	#[test]
+	fn list_fingerprint_generation() {
+		let mut lst = InputKeyMaterialList::import(TEST_STR).unwrap();
+		let fingerprint = lst.get_fingerprint();
+		lst.is_modified = true;
+		lst.generation = 42;
+		assert_eq!(lst.get_fingerprint(), fingerprint);
+		let _ = lst.revoke_ikm(1);
+		assert_ne!(lst.get_fingerprint(), fingerprint);
+	}
+
+	#[test]
+	fn list_fingerprint_revocation_and_state() {
+		let lst = InputKeyMaterialList::import(TEST_STR).unwrap();
+		let fingerprint = lst.get_fingerprint();
+		let revoked_at = lst[0].not_before + core::time::Duration::from_secs(3_600);
+
+		let mut revoked = InputKeyMaterialList::import(TEST_STR).unwrap();
+		revoked
+			.revoke_ikm_at(1, revoked_at, crate::RevocationReason::Superseded)
+			.unwrap();
+		let revoked_fingerprint = revoked.get_fingerprint();
+		assert_ne!(revoked_fingerprint, fingerprint);
+
+		let mut other_reason = InputKeyMaterialList::import(TEST_STR).unwrap();
+		other_reason
+			.revoke_ikm_at(1, revoked_at, crate::RevocationReason::KeyCompromise)
+			.unwrap();
+		assert_ne!(other_reason.get_fingerprint(), revoked_fingerprint);
+
+		let mut other_date = InputKeyMaterialList::import(TEST_STR).unwrap();
+		other_date
+			.revoke_ikm_at(1, lst[0].not_before, crate::RevocationReason::Superseded)
+			.unwrap();
+		assert_ne!(other_date.get_fingerprint(), revoked_fingerprint);
+
+		let mut decrypt_only = InputKeyMaterialList::import(TEST_STR).unwrap();
+		decrypt_only
+			.set_ikm_state(1, crate::IkmState::DecryptOnly)
+			.unwrap();
+		assert_ne!(decrypt_only.get_fingerprint(), fingerprint);
+	}
+}
@@ -1,10 +1,22 @@
+use crate::clock::Timestamp;
 use crate::error::{Error, Result};
+use crate::revocation::RevocationReason;
 use crate::scheme::{Scheme, SchemeSerializeType};
-use std::time::{Duration, SystemTime};
+use crate::state::IkmState;
+use alloc::vec::Vec;
+#[cfg(feature = "ikm-management")]
+use alloc::{string::String, vec};
+#[cfg(any(test, all(feature = "ikm-management", feature = "std")))]
+use core::time::Duration;
+#[cfg(feature = "ikm-management")]
+use subtle::ConstantTimeEq;
+use zeroize::Zeroize;
 
 pub(crate) const IKM_BASE_STRUCT_SIZE: usize = 25;
 
 pub(crate) type CounterId = u32;
+/// Abstract type representing the generation of an [InputKeyMaterialList].
+pub type Generation = u64;
 /// Abstract type representing the identifier of an [InputKeyMaterial].
 pub type IkmId = u32;
 
@@ -16,14 +28,27 @@ pub type IkmId = u32;
 ///
 /// This struct is exposed so you can display its informations when managing your IKMs using an
 /// [InputKeyMaterialList]. It it not meant to be used otherwise.
-#[derive(Debug)]
+///
+/// The IKM's content is erased from memory when it is dropped, including for clones.
+#[derive(Clone, Debug)]
 pub struct InputKeyMaterial {
 	pub(crate) id: IkmId,
 	pub(crate) scheme: Scheme,
 	pub(crate) content: Vec<u8>,
-	pub(crate) not_before: SystemTime,
-	pub(crate) not_after: SystemTime,
+	pub(crate) not_before: Timestamp,
+	pub(crate) not_after: Timestamp,
 	pub(crate) is_revoked: bool,
+	pub(crate) revoked_at: Option<Timestamp>,
+	pub(crate) revocation_reason: RevocationReason,
+	pub(crate) state: IkmState,
+	/// Digest of the content, kept once the content has been erased.
+	pub(crate) content_digest: Option<[u8; 32]>,
+}
+
+impl Drop for InputKeyMaterial {
+	fn drop(&mut self) {
+		self.content.zeroize();
+	}
 }
 
 impl InputKeyMaterial {
@@ -41,13 +66,13 @@ impl InputKeyMaterial {
 
 	/// Returns the date before which the IKM must not be used to encrypt data.
 	#[cfg(feature = "ikm-management")]
-	pub fn get_not_before(&self) -> SystemTime {
+	pub fn get_not_before(&self) -> Timestamp {
 		self.not_before
 	}
 
 	/// Returns the date after which the IKM must not be used to encrypt data.
 	#[cfg(feature = "ikm-management")]
-	pub fn get_not_after(&self) -> SystemTime {
+	pub fn get_not_after(&self) -> Timestamp {
 		self.not_after
 	}
 
@@ -63,20 +88,8 @@ impl InputKeyMaterial {
 		res.extend_from_slice(&self.id.to_le_bytes());
 		res.extend_from_slice(&(self.scheme as SchemeSerializeType).to_le_bytes());
 		res.extend_from_slice(&self.content);
-		res.extend_from_slice(
-			&self
-				.not_before
-				.duration_since(SystemTime::UNIX_EPOCH)?
-				.as_secs()
-				.to_le_bytes(),
-		);
-		res.extend_from_slice(
-			&self
-				.not_after
-				.duration_since(SystemTime::UNIX_EPOCH)?
-				.as_secs()
-				.to_le_bytes(),
-		);
+		res.extend_from_slice(&self.not_before.as_secs().to_le_bytes());
+		res.extend_from_slice(&self.not_after.as_secs().to_le_bytes());
 		res.push(self.is_revoked as u8);
 		Ok(res)
 	}
@@ -95,18 +108,55 @@ impl InputKeyMaterial {
 			id: IkmId::from_le_bytes(b[0..4].try_into().unwrap()),
 			scheme,
 			content: b[8..8 + is].into(),
-			not_before: InputKeyMaterial::bytes_to_system_time(&b[8 + is..8 + is + 8])?,
-			not_after: InputKeyMaterial::bytes_to_system_time(&b[8 + is + 8..8 + is + 8 + 8])?,
+			not_before: InputKeyMaterial::bytes_to_timestamp(&b[8 + is..8 + is + 8]),
+			not_after: InputKeyMaterial::bytes_to_timestamp(&b[8 + is + 8..8 + is + 8 + 8]),
 			is_revoked: b[8 + is + 8 + 8] != 0,
+			revoked_at: None,
+			revocation_reason: RevocationReason::default(),
+			state: IkmState::default(),
+			content_digest: None,
+		})
+	}
+
+	/// Check whether or not the IKM may be used to encrypt data at the specified time, which
+	/// requires it to be active, not revoked and within its validity period. The validity period
+	/// includes `not_before` but excludes `not_after`, so that an IKM starting exactly when the
+	/// previous one expires takes over without any gap nor overlap.
+	#[cfg(any(test, feature = "encryption"))]
+	pub fn is_usable_at(&self, encryption_time: Timestamp) -> bool {
+		!self.is_revoked
+			&& self.state == IkmState::Active
+			&& self.not_before <= encryption_time
+			&& encryption_time < self.not_after
+	}
+
+	#[cfg(feature = "ikm-management")]
+	fn generate(
+		id: IkmId,
+		scheme: Scheme,
+		not_before: Timestamp,
+		not_after: Timestamp,
+		state: IkmState,
+	) -> Result<Self> {
+		let mut content: Vec<u8> = vec![0; scheme.get_ikm_size()];
+		crate::rand::fill(content.as_mut_slice())?;
+		Ok(Self {
+			id,
+			scheme,
+			not_before,
+			not_after,
+			is_revoked: false,
+			revoked_at: None,
+			revocation_reason: RevocationReason::default(),
+			state,
+			content_digest: None,
+			content,
 		})
 	}
 
-	fn bytes_to_system_time(ts_slice: &[u8]) -> Result<SystemTime> {
+	fn bytes_to_timestamp(ts_slice: &[u8]) -> Timestamp {
 		let ts_array: [u8; 8] = ts_slice.try_into().unwrap();
-		let ts = u64::from_le_bytes(ts_array);
-		SystemTime::UNIX_EPOCH
-			.checked_add(Duration::from_secs(ts))
-			.ok_or(Error::SystemTimeReprError(ts))
+		Timestamp::from_secs(u64::from_le_bytes(ts_array))
 	}
 }
 
@@ -134,8 +184,8 @@ impl InputKeyMaterial {
 /// # Examples
 ///
 /// ```
-/// use coffio::{InputKeyMaterialList, Scheme, DEFAULT_SCHEME};
-/// use std::time::{Duration, SystemTime};
+/// use coffio::{InputKeyMaterialList, Scheme, DEFAULT_SCHEME, Timestamp};
+/// use std::time::Duration;
 ///
 /// // Create an empty IKM list.
 /// let mut ikml = InputKeyMaterialList::new();
@@ -146,7 +196,7 @@ impl InputKeyMaterial {
 /// assert_eq!(ikml.len(), 1);
 ///
 /// // Add an IKM to the list with custom settings.
-/// let not_before = SystemTime::now();
+/// let not_before = Timestamp::now()?;
 /// let not_after = not_before + Duration::from_secs(315_569_252);
 /// let ikm_id_2 = ikml.add_custom_ikm(
 ///     DEFAULT_SCHEME,
@@ -177,6 +227,9 @@ pub struct InputKeyMaterialList {
 	pub(crate) ikm_lst: Vec<InputKeyMaterial>,
 	#[allow(dead_code)]
 	pub(crate) id_counter: CounterId,
+	pub(crate) generation: Generation,
+	pub(crate) journal: Vec<crate::journal::IkmJournalEntry>,
+	pub(crate) is_modified: bool,
 }
 
 impl InputKeyMaterialList {
@@ -193,6 +246,28 @@ impl InputKeyMaterialList {
 		Self::default()
 	}
 
+	/// Returns the generation of the IKM list.
+	///
+	/// The generation is stored in the exported IKM list. Every time a list is modified, its
+	/// generation becomes the one it had when it was imported, incremented by one. Two lists
+	/// concurrently modified from the same original list therefore share the same generation,
+	/// which allows [update][Self::update] to detect stale edits.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let mut ikml = coffio::InputKeyMaterialList::new();
+	/// assert_eq!(ikml.get_generation(), 0);
+	/// let _ = ikml.add_ikm()?;
+	/// let _ = ikml.add_ikm()?;
+	/// assert_eq!(ikml.get_generation(), 1);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "ikm-management")]
+	pub fn get_generation(&self) -> Generation {
+		self.generation + Generation::from(self.is_modified)
+	}
+
 	/// Add a new IKM to the list. The scheme will be set to the value of
 	/// [DEFAULT_SCHEME][crate::DEFAULT_SCHEME], the `not_before` field will be set to the current
 	/// timestamp and the `not_after` will be set to the current timestamp incremented with the
@@ -207,9 +282,9 @@ impl InputKeyMaterialList {
 	/// assert_eq!(ikml.len(), 1);
 	/// # Ok::<(), coffio::Error>(())
 	/// ```
-	#[cfg(feature = "ikm-management")]
+	#[cfg(all(feature = "ikm-management", feature = "std"))]
 	pub fn add_ikm(&mut self) -> Result<IkmId> {
-		let not_before = SystemTime::now();
+		let not_before = Timestamp::now()?;
 		let not_after = not_before + Duration::from_secs(crate::DEFAULT_IKM_DURATION);
 		self.add_custom_ikm(crate::DEFAULT_SCHEME, not_before, not_after)
 	}
@@ -219,12 +294,12 @@ impl InputKeyMaterialList {
 	/// # Examples
 	///
 	/// ```
-	/// use coffio::{InputKeyMaterialList, Scheme, DEFAULT_SCHEME};
-	/// use std::time::{Duration, SystemTime};
+	/// use coffio::{InputKeyMaterialList, Scheme, DEFAULT_SCHEME, Timestamp};
+	/// use std::time::Duration;
 	///
 	/// let mut ikml = InputKeyMaterialList::new();
 	/// assert_eq!(ikml.len(), 0);
-	/// let not_before = SystemTime::now();
+	/// let not_before = Timestamp::now()?;
 	/// let not_after = not_before + Duration::from_secs(315_569_252);
 	/// let _ = ikml.add_custom_ikm(
 	///     DEFAULT_SCHEME,
@@ -238,22 +313,130 @@ impl InputKeyMaterialList {
 	pub fn add_custom_ikm(
 		&mut self,
 		scheme: Scheme,
-		not_before: SystemTime,
-		not_after: SystemTime,
+		not_before: Timestamp,
+		not_after: Timestamp,
+	) -> Result<IkmId> {
+		self.add_custom_ikm_with_state(scheme, not_before, not_after, IkmState::Active)
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn add_custom_ikm_with_state(
+		&mut self,
+		scheme: Scheme,
+		not_before: Timestamp,
+		not_after: Timestamp,
+		state: IkmState,
+	) -> Result<IkmId> {
+		let id = self.id_counter + 1;
+		let ikm = InputKeyMaterial::generate(id, scheme, not_before, not_after, state)?;
+		self.id_counter = ikm.id;
+		self.is_modified = true;
+		self.ikm_lst.push(ikm);
+		Ok(self.id_counter)
+	}
+
+	/// Add a new IKM whose content has been generated outside of coffio, for example in order to
+	/// migrate existing secrets under coffio management.
+	///
+	/// The content's length must match the scheme's IKM size. If no identifier is specified, a new
+	/// one is assigned, otherwise the specified one, which cannot be 0, is used. An IKM sharing the
+	/// same identifier or the same content as an IKM already present in the list is refused.
+	///
+	/// <div class="warning">
+	/// The content must have been generated using a cryptographically secure random number
+	/// generator and must not be used elsewhere. Do not import a password or any other low-entropy
+	/// secret.
+	/// </div>
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{InputKeyMaterialList, DEFAULT_SCHEME, Timestamp};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let legacy_secret = [0x2a; 32];
+	/// let not_before = Timestamp::now()?;
+	/// let not_after = not_before + Duration::from_secs(315_569_252);
+	/// let ikm_id = ikml.add_ikm_from_bytes(
+	///     DEFAULT_SCHEME,
+	///     &legacy_secret,
+	///     not_before,
+	///     not_after,
+	///     Some(42),
+	/// )?;
+	/// assert_eq!(ikm_id, 42);
+	/// assert_eq!(ikml.add_ikm()?, 43);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "ikm-management")]
+	pub fn add_ikm_from_bytes(
+		&mut self,
+		scheme: Scheme,
+		content: &[u8],
+		not_before: Timestamp,
+		not_after: Timestamp,
+		id: Option<IkmId>,
+	) -> Result<IkmId> {
+		self.add_ikm_from_bytes_with_state(
+			scheme,
+			content,
+			not_before,
+			not_after,
+			id,
+			IkmState::Active,
+		)
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn add_ikm_from_bytes_with_state(
+		&mut self,
+		scheme: Scheme,
+		content: &[u8],
+		not_before: Timestamp,
+		not_after: Timestamp,
+		id: Option<IkmId>,
+		state: IkmState,
 	) -> Result<IkmId> {
 		let ikm_len = scheme.get_ikm_size();
-		let mut content: Vec<u8> = vec![0; ikm_len];
-		getrandom::fill(content.as_mut_slice())?;
-		self.id_counter += 1;
-		self.ikm_lst.push(InputKeyMaterial {
-			id: self.id_counter,
+		if content.len() != ikm_len {
+			return Err(Error::IkmInvalidContentLength(ikm_len, content.len()));
+		}
+		// The content is secret, hence it must be compared in constant time.
+		if let Some(ikm) = self.ikm_lst.iter().find(|ikm| {
+			ikm.state != IkmState::Destroyed && bool::from(ikm.content.as_slice().ct_eq(content))
+		}) {
+			return Err(Error::IkmDuplicateContent(ikm.id));
+		}
+		let id = match id {
+			Some(0) => return Err(Error::IkmInvalidId(0)),
+			Some(id) => {
+				if self.ikm_lst.iter().any(|ikm| ikm.id == id) {
+					return Err(Error::IkmDuplicateId(id));
+				}
+				id
+			}
+			None => self.id_counter + 1,
+		};
+		let ikm = InputKeyMaterial {
+			id,
 			scheme,
 			not_before,
 			not_after,
 			is_revoked: false,
-			content,
-		});
-		Ok(self.id_counter)
+			revoked_at: None,
+			revocation_reason: RevocationReason::default(),
+			state,
+			content_digest: None,
+			content: content.to_vec(),
+		};
+		match self.ikm_lst.iter().position(|ikm| ikm.id > id) {
+			Some(pos) => self.ikm_lst.insert(pos, ikm),
+			None => self.ikm_lst.push(ikm),
+		}
+		self.id_counter = self.id_counter.max(id);
+		self.is_modified = true;
+		Ok(id)
 	}
 
 	/// Delete the specified IKM from the list.
@@ -275,6 +458,7 @@ impl InputKeyMaterialList {
 		if self.ikm_lst.len() == initial_len {
 			Err(Error::IkmNotFound(id))
 		} else {
+			self.is_modified = true;
 			Ok(id)
 		}
 	}
@@ -291,17 +475,116 @@ impl InputKeyMaterialList {
 	/// assert_eq!(ikml.len(), 1);
 	/// # Ok::<(), coffio::Error>(())
 	/// ```
-	#[cfg(feature = "ikm-management")]
+	#[cfg(all(feature = "ikm-management", feature = "std"))]
 	pub fn revoke_ikm(&mut self, id: IkmId) -> Result<IkmId> {
+		let now = Timestamp::now()?;
 		let ikm = self
 			.ikm_lst
 			.iter_mut()
 			.find(|ikm| ikm.id == id)
 			.ok_or(Error::IkmNotFound(id))?;
-		ikm.is_revoked = true;
+		if !ikm.is_revoked {
+			ikm.is_revoked = true;
+			ikm.revoked_at = Some(now);
+			ikm.revocation_reason = RevocationReason::default();
+		}
+		self.is_modified = true;
 		Ok(id)
 	}
 
+	/// Pre-provision IKMs so that the list follows a rotation schedule.
+	///
+	/// New IKMs using the specified scheme are added to the list so that each one of them is valid
+	/// for `interval` and starts exactly when the previous one expires. Since an IKM is usable from
+	/// its `not_before` date included to its `not_after` date excluded, exactly one of them is
+	/// usable at any time of the schedule. IKMs are added until the one covering the current
+	/// timestamp incremented with `horizon` is in the list. This way, every node receiving the
+	/// updated list already holds the IKMs that will be used in the near future before they become
+	/// active.
+	///
+	/// The schedule continues from the non-revoked IKM using the same scheme which can still be
+	/// used for encryption and expires last. Only active IKMs are considered, as well as pending
+	/// ones when the schedule adds pending IKMs. If none of them is still valid, the schedule
+	/// starts at the current timestamp. Calling this function several times is therefore
+	/// idempotent: IKMs are only added when the horizon moves past the end of the schedule.
+	///
+	/// Returns the identifiers of the newly added IKMs.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{InputKeyMaterialList, DEFAULT_SCHEME};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let day = Duration::from_secs(86_400);
+	/// let new_ikms = ikml.schedule_ikm_rotation(DEFAULT_SCHEME, 30 * day, 60 * day)?;
+	/// assert_eq!(new_ikms.len(), 3);
+	/// let new_ikms = ikml.schedule_ikm_rotation(DEFAULT_SCHEME, 30 * day, 60 * day)?;
+	/// assert!(new_ikms.is_empty());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(all(feature = "ikm-management", feature = "std"))]
+	pub fn schedule_ikm_rotation(
+		&mut self,
+		scheme: Scheme,
+		interval: Duration,
+		horizon: Duration,
+	) -> Result<Vec<IkmId>> {
+		let state = IkmState::Active;
+		self.process_schedule_ikm_rotation(scheme, interval, horizon, state, Timestamp::now()?)
+	}
+
+	#[cfg(all(feature = "ikm-management", feature = "std"))]
+	pub(crate) fn process_schedule_ikm_rotation(
+		&mut self,
+		scheme: Scheme,
+		interval: Duration,
+		horizon: Duration,
+		state: IkmState,
+		now: Timestamp,
+	) -> Result<Vec<IkmId>> {
+		if interval.is_zero() {
+			return Err(Error::IkmInvalidRotationSchedule);
+		}
+		let target = now
+			.checked_add(horizon)
+			.ok_or(Error::IkmInvalidRotationSchedule)?;
+		let mut not_before =
+			self.ikm_lst
+				.iter()
+				.filter(|ikm| {
+					ikm.scheme == scheme
+						&& !ikm.is_revoked && ikm.not_after > now
+						&& (ikm.state == IkmState::Active
+							|| (state == IkmState::Pending && ikm.state == IkmState::Pending))
+				})
+				.map(|ikm| ikm.not_after)
+				.max()
+				.unwrap_or(now);
+		// The new IKMs are only added once all of them have been generated, so that the list is
+		// left untouched if an error occurs.
+		let mut new_ikms = Vec::new();
+		let mut id = self.id_counter;
+		while not_before <= target {
+			let not_after = not_before
+				.checked_add(interval)
+				.ok_or(Error::IkmInvalidRotationSchedule)?;
+			id += 1;
+			new_ikms.push(InputKeyMaterial::generate(
+				id, scheme, not_before, not_after, state,
+			)?);
+			not_before = not_after;
+		}
+		let new_ids = new_ikms.iter().map(|ikm| ikm.id).collect();
+		if !new_ikms.is_empty() {
+			self.id_counter = id;
+			self.is_modified = true;
+			self.ikm_lst.extend(new_ikms);
+		}
+		Ok(new_ids)
+	}
+
 	/// Export the IKM list to a displayable string.
 	///
 	/// # Examples
@@ -319,6 +602,11 @@ impl InputKeyMaterialList {
 
 	/// Import an IKM list.
 	///
+	/// This function never verifies signatures: lists exported using `export_signed` are rejected
+	/// and unsigned lists are accepted as is, hence anyone having write access to the exported list
+	/// can alter it. In order to require a valid signature, use `import_signed` instead (requires
+	/// the `ikm-signature` feature).
+	///
 	#[cfg_attr(
 		feature = "chacha",
 		doc = r##"
@@ -333,22 +621,38 @@ assert_eq!(ikml.len(), 1);
 "##
 	)]
 	pub fn import(s: &str) -> Result<Self> {
-		crate::storage::decode_ikm_list(s)
+		#[cfg(feature = "tracing")]
+		let _span = tracing::info_span!(
+			"coffio.import",
+			ikm_count = tracing::field::Empty,
+			generation = tracing::field::Empty,
+		)
+		.entered();
+		let lst = crate::storage::decode_ikm_list(s)?;
+		#[cfg(feature = "tracing")]
+		tracing::Span::current()
+			.record("ikm_count", lst.ikm_lst.len())
+			.record("generation", lst.generation);
+		Ok(lst)
 	}
 
-	#[cfg(any(test, feature = "encryption"))]
-	pub(crate) fn get_latest_ikm(&self, encryption_time: SystemTime) -> Result<&InputKeyMaterial> {
+	#[cfg(test)]
+	pub(crate) fn get_latest_ikm(&self, encryption_time: Timestamp) -> Result<&InputKeyMaterial> {
 		self.ikm_lst
 			.iter()
 			.rev()
-			.find(|&ikm| {
-				!ikm.is_revoked
-					&& ikm.not_before < encryption_time
-					&& ikm.not_after > encryption_time
-			})
+			.find(|&ikm| ikm.is_usable_at(encryption_time))
 			.ok_or(Error::IkmNoneAvailable)
 	}
 
+	#[cfg(feature = "encryption")]
+	pub(crate) fn get_usable_ikms(&self, encryption_time: Timestamp) -> Vec<&InputKeyMaterial> {
+		self.ikm_lst
+			.iter()
+			.filter(|&ikm| ikm.is_usable_at(encryption_time))
+			.collect()
+	}
+
 	#[cfg(feature = "encryption")]
 	pub(crate) fn get_ikm_by_id(&self, id: IkmId) -> Result<&InputKeyMaterial> {
 		self.ikm_lst
@@ -358,7 +662,7 @@ assert_eq!(ikml.len(), 1);
 	}
 }
 
-impl std::str::FromStr for InputKeyMaterialList {
+impl core::str::FromStr for InputKeyMaterialList {
 	type Err = Error;
 
 	fn from_str(s: &str) -> Result<Self, Self::Err> {
@@ -367,7 +671,7 @@ impl std::str::FromStr for InputKeyMaterialList {
 }
 
 #[cfg(feature = "ikm-management")]
-impl std::ops::Deref for InputKeyMaterialList {
+impl core::ops::Deref for InputKeyMaterialList {
 	type Target = Vec<InputKeyMaterial>;
 
 	fn deref(&self) -> &Self::Target {
@@ -426,8 +730,8 @@ mod tests {
 }
 
 #[cfg(test)]
-fn get_default_time_period() -> (SystemTime, SystemTime) {
-	let not_before = SystemTime::now();
+fn get_default_time_period() -> (Timestamp, Timestamp) {
+	let not_before = Timestamp::now().unwrap();
 	let not_after = not_before + Duration::from_secs(crate::DEFAULT_IKM_DURATION);
 	(not_before, not_after)
 }
@@ -435,6 +739,7 @@ fn get_default_time_period() -> (SystemTime, SystemTime) {
 #[cfg(all(test, feature = "ikm-management"))]
 mod ikm_management {
 	use super::*;
+	use std::time::Duration;
 
 	// This list contains the folowing IKM:
 	// 1: * not_before: Monday 1 April 2019 10:21:42
@@ -457,13 +762,6 @@ mod ikm_management {
 	//    * is_revoked: false
 	const TEST_STR: &str = "ikml-v1:BgAAAA:AQAAAAEAAACUAPcqngJ46_HMtJSdIw-WeUtImcCVxOA47n6UIN5K2TbmoVwAAAAANmuEXgAAAAAB:AgAAAAEAAADf7CR8vl_aWOUyfsO0ek0YQr_Yi7L_sJmF2nIt_XOaCzYNal4AAAAAtkBLYAAAAAAA:AwAAAAEAAAAMoNIW9gIGkzegUDEsU3N1Rf_Zz0OMuylUSiQjUzLXqzY0MmAAAAAANsk0iwEAAAAA:BAAAAAEAAABbwRrMz3x3DkfOEFg1BHfLLRHoNqg6d_xGWwdh48hH8rZm9mEAAAAANjy9YwAAAAAA:BQAAAAEAAAA2LwnTgDUF7qn7dy79VA24JSSgo6vllAtU5zmhrxNJu7YIz4sBAAAANoUMjgEAAAAB:BgAAAAEAAAAn0Vqe2f9YRXBt6xVYaeSLs0Gf0S0_5B-hk-a2b0rhlraCJbwAAAAAtlErjAEAAAAA";
 
-	fn round_time(t: SystemTime) -> SystemTime {
-		let secs = t.duration_since(SystemTime::UNIX_EPOCH).unwrap().as_secs();
-		SystemTime::UNIX_EPOCH
-			.checked_add(Duration::from_secs(secs))
-			.unwrap()
-	}
-
 	#[test]
 	#[cfg(feature = "chacha")]
 	fn gen_ikm_list() {
@@ -493,6 +791,46 @@ mod ikm_management {
 		assert_eq!(lst.ikm_lst.len(), 3);
 	}
 
+	#[test]
+	fn add_ikm_from_bytes() {
+		let scheme = crate::DEFAULT_SCHEME;
+		let (not_before, not_after) = get_default_time_period();
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+
+		let res = lst.add_ikm_from_bytes(scheme, &[0x01; 32], not_before, not_after, None);
+		assert_eq!(res, Ok(2));
+		let res = lst.add_ikm_from_bytes(scheme, &[0x02; 32], not_before, not_after, Some(10));
+		assert_eq!(res, Ok(10));
+		let res = lst.add_ikm_from_bytes(scheme, &[0x03; 32], not_before, not_after, Some(5));
+		assert_eq!(res, Ok(5));
+		assert_eq!(lst.id_counter, 10);
+		let ids: Vec<IkmId> = lst.iter().map(|ikm| ikm.id).collect();
+		assert_eq!(ids, vec![1, 2, 5, 10]);
+		assert_eq!(lst[2].content, vec![0x03; 32]);
+		assert_eq!(lst.add_ikm(), Ok(11));
+	}
+
+	#[test]
+	fn add_ikm_from_bytes_invalid() {
+		let scheme = crate::DEFAULT_SCHEME;
+		let (not_before, not_after) = get_default_time_period();
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm_from_bytes(scheme, &[0x01; 32], not_before, not_after, Some(3));
+
+		let res = lst.add_ikm_from_bytes(scheme, &[0x02; 16], not_before, not_after, None);
+		assert_eq!(res, Err(Error::IkmInvalidContentLength(32, 16)));
+		let res = lst.add_ikm_from_bytes(scheme, &[], not_before, not_after, None);
+		assert_eq!(res, Err(Error::IkmInvalidContentLength(32, 0)));
+		let res = lst.add_ikm_from_bytes(scheme, &[0x01; 32], not_before, not_after, None);
+		assert_eq!(res, Err(Error::IkmDuplicateContent(3)));
+		let res = lst.add_ikm_from_bytes(scheme, &[0x02; 32], not_before, not_after, Some(3));
+		assert_eq!(res, Err(Error::IkmDuplicateId(3)));
+		let res = lst.add_ikm_from_bytes(scheme, &[0x02; 32], not_before, not_after, Some(0));
+		assert_eq!(res, Err(Error::IkmInvalidId(0)));
+		assert_eq!(lst.len(), 1);
+	}
+
 	#[test]
 	fn export_empty() {
 		let lst = InputKeyMaterialList::new();
@@ -502,7 +840,7 @@ mod ikm_management {
 		let res = lst.export();
 		assert!(res.is_ok(), "res: {res:?}");
 		let s = res.unwrap();
-		assert_eq!(&s, "ikml-v1:AAAAAA");
+		assert_eq!(&s, "ikml-v2:AAAAAAAAAAAAAAAA");
 	}
 
 	#[test]
@@ -513,7 +851,7 @@ mod ikm_management {
 		let res = lst.export();
 		assert!(res.is_ok(), "res: {res:?}");
 		let s = res.unwrap();
-		assert_eq!(s.len(), 91);
+		assert_eq!(s.len(), 103);
 	}
 
 	#[test]
@@ -567,8 +905,8 @@ mod ikm_management {
 			let el_bis = &lst_bis.ikm_lst[i];
 			assert_eq!(el_bis.id, el.id);
 			assert_eq!(el_bis.content, el.content);
-			assert_eq!(el_bis.not_before, round_time(el.not_before));
-			assert_eq!(el_bis.not_after, round_time(el.not_after));
+			assert_eq!(el_bis.not_before, el.not_before);
+			assert_eq!(el_bis.not_after, el.not_after);
 			assert_eq!(el_bis.is_revoked, el.is_revoked);
 		}
 	}
@@ -579,19 +917,19 @@ mod ikm_management {
 		let _ = lst.add_ikm();
 		let _ = lst.add_ikm();
 
-		let latest_ikm = lst.get_latest_ikm(SystemTime::now()).unwrap();
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
 		assert_eq!(latest_ikm.id, 2);
 
 		let res = lst.delete_ikm(2);
 		assert!(res.is_ok(), "res: {res:?}");
 		assert_eq!(res.unwrap(), 2);
-		let latest_ikm = lst.get_latest_ikm(SystemTime::now()).unwrap();
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
 		assert_eq!(latest_ikm.id, 1);
 
 		let res = lst.delete_ikm(1);
 		assert!(res.is_ok(), "res: {res:?}");
 		assert_eq!(res.unwrap(), 1);
-		let res = lst.get_latest_ikm(SystemTime::now());
+		let res = lst.get_latest_ikm(Timestamp::now().unwrap());
 		assert!(res.is_err());
 
 		let res = lst.delete_ikm(42);
@@ -604,25 +942,163 @@ mod ikm_management {
 		let _ = lst.add_ikm();
 		let _ = lst.add_ikm();
 
-		let latest_ikm = lst.get_latest_ikm(SystemTime::now()).unwrap();
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
 		assert_eq!(latest_ikm.id, 2);
 
 		let res = lst.revoke_ikm(2);
 		assert!(res.is_ok(), "res: {res:?}");
 		assert_eq!(res.unwrap(), 2);
-		let latest_ikm = lst.get_latest_ikm(SystemTime::now()).unwrap();
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
 		assert_eq!(latest_ikm.id, 1);
 
 		let res = lst.revoke_ikm(1);
 		assert!(res.is_ok(), "res: {res:?}");
 		assert_eq!(res.unwrap(), 1);
-		let res = lst.get_latest_ikm(SystemTime::now());
+		let res = lst.get_latest_ikm(Timestamp::now().unwrap());
 		assert!(res.is_err());
 
 		let res = lst.revoke_ikm(42);
 		assert!(res.is_err(), "res: {res:?}");
 	}
 
+	#[test]
+	fn schedule_ikm_rotation() {
+		let scheme = crate::DEFAULT_SCHEME;
+		let day = Duration::from_secs(86_400);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_712_475_802);
+		let mut lst = InputKeyMaterialList::new();
+
+		let res =
+			lst.process_schedule_ikm_rotation(scheme, 7 * day, 21 * day, IkmState::Active, now);
+		assert_eq!(res, Ok(vec![1, 2, 3, 4]));
+		for window in lst.ikm_lst.windows(2) {
+			assert_eq!(window[0].not_after, window[1].not_before);
+			assert_eq!(window[0].not_after, window[0].not_before + 7 * day);
+		}
+		assert_eq!(lst.ikm_lst[0].not_before, now);
+		assert_eq!(lst.get_latest_ikm(now).unwrap().id, 1);
+		assert_eq!(lst.get_latest_ikm(now + day).unwrap().id, 1);
+		assert_eq!(lst.get_latest_ikm(now + 7 * day).unwrap().id, 2);
+		assert_eq!(lst.get_latest_ikm(now + 8 * day).unwrap().id, 2);
+
+		// Idempotency
+		let res =
+			lst.process_schedule_ikm_rotation(scheme, 7 * day, 21 * day, IkmState::Active, now);
+		assert_eq!(res, Ok(vec![]));
+		let res = lst.process_schedule_ikm_rotation(
+			scheme,
+			7 * day,
+			21 * day,
+			IkmState::Active,
+			now + 6 * day,
+		);
+		assert_eq!(res, Ok(vec![]));
+		assert_eq!(lst.ikm_lst.len(), 4);
+
+		// Moving horizon
+		let res = lst.process_schedule_ikm_rotation(
+			scheme,
+			7 * day,
+			21 * day,
+			IkmState::Active,
+			now + 8 * day,
+		);
+		assert_eq!(res, Ok(vec![5]));
+		assert_eq!(lst.ikm_lst[4].not_before, lst.ikm_lst[3].not_after);
+
+		// Revoked IKMs are not part of the schedule
+		let _ = lst.revoke_ikm(5);
+		let res = lst.process_schedule_ikm_rotation(
+			scheme,
+			7 * day,
+			21 * day,
+			IkmState::Active,
+			now + 8 * day,
+		);
+		assert_eq!(res, Ok(vec![6]));
+		assert_eq!(lst.ikm_lst[5].not_before, lst.ikm_lst[3].not_after);
+	}
+
+	#[test]
+	fn schedule_ikm_rotation_retired() {
+		let scheme = crate::DEFAULT_SCHEME;
+		let day = Duration::from_secs(86_400);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_712_475_802);
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_custom_ikm_with_state(
+			scheme,
+			now - day,
+			now + 365 * day,
+			IkmState::DecryptOnly,
+		);
+
+		let res =
+			lst.process_schedule_ikm_rotation(scheme, 7 * day, 14 * day, IkmState::Active, now);
+		assert_eq!(res, Ok(vec![2, 3, 4]));
+		assert_eq!(lst.ikm_lst[1].not_before, now);
+		assert_eq!(lst.get_latest_ikm(now).unwrap().id, 2);
+
+		// Pending IKMs are only considered when scheduling pending IKMs
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_custom_ikm_with_state(scheme, now, now + 365 * day, IkmState::Pending);
+		let res =
+			lst.process_schedule_ikm_rotation(scheme, 7 * day, 14 * day, IkmState::Active, now);
+		assert_eq!(res, Ok(vec![2, 3, 4]));
+		assert_eq!(lst.ikm_lst[1].not_before, now);
+		let res =
+			lst.process_schedule_ikm_rotation(scheme, 7 * day, 14 * day, IkmState::Pending, now);
+		assert_eq!(res, Ok(vec![]));
+	}
+
+	#[test]
+	#[cfg(feature = "encrypt-at")]
+	fn schedule_ikm_rotation_boundaries() {
+		use crate::{CiphertextInfo, Coffio, DataContext, KeyContext};
+
+		let day = Duration::from_secs(86_400);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_712_475_802);
+		let mut lst = InputKeyMaterialList::new();
+		let res = lst.process_schedule_ikm_rotation(
+			crate::DEFAULT_SCHEME,
+			day,
+			3 * day,
+			IkmState::Active,
+			now,
+		);
+		assert_eq!(res, Ok(vec![1, 2, 3, 4]));
+
+		let key_ctx = KeyContext::from(["db_name", "table_name", "column_name"]);
+		let data_ctx = DataContext::from(["018db876-3d9d-79af-9460-55d17da991d8"]);
+		let cb = Coffio::new(&lst);
+		for ikm in lst.iter() {
+			let res = cb.encrypt_at(&key_ctx, &data_ctx, b"data", ikm.not_before);
+			assert!(res.is_ok(), "{}: res: {res:?}", ikm.id);
+			let info = CiphertextInfo::parse(&res.unwrap()).unwrap();
+			assert_eq!(info.get_ikm_id(), ikm.id);
+		}
+		let end = lst.ikm_lst[3].not_after;
+		let res = cb.encrypt_at(&key_ctx, &data_ctx, b"data", end);
+		assert_eq!(res, Err(Error::IkmNoneAvailable));
+	}
+
+	#[test]
+	fn schedule_ikm_rotation_invalid() {
+		let mut lst = InputKeyMaterialList::new();
+		let res = lst.schedule_ikm_rotation(
+			crate::DEFAULT_SCHEME,
+			Duration::ZERO,
+			Duration::from_secs(86_400),
+		);
+		assert_eq!(res, Err(Error::IkmInvalidRotationSchedule));
+		let res = lst.schedule_ikm_rotation(
+			crate::DEFAULT_SCHEME,
+			Duration::from_secs(86_400),
+			Duration::MAX,
+		);
+		assert_eq!(res, Err(Error::IkmInvalidRotationSchedule));
+		assert_eq!(lst.ikm_lst.len(), 0);
+	}
+
 	#[test]
 	fn iterate() {
 		let mut lst = InputKeyMaterialList::new();
@@ -642,14 +1118,14 @@ mod ikm_management {
 		let res = InputKeyMaterialList::import(TEST_STR);
 		assert!(res.is_ok(), "res: {res:?}");
 		let lst = res.unwrap();
-		let res = lst.get_latest_ikm(SystemTime::UNIX_EPOCH);
+		let res = lst.get_latest_ikm(Timestamp::UNIX_EPOCH);
 		assert_eq!(res.err(), Some(Error::IkmNoneAvailable))
 	}
 
 	#[test]
 	#[cfg(feature = "chacha")]
 	fn get_latest_ikm_1_712_475_802() {
-		let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(1_712_475_802);
+		let ts = Timestamp::UNIX_EPOCH + Duration::from_secs(1_712_475_802);
 		let res = InputKeyMaterialList::import(TEST_STR);
 		assert!(res.is_ok(), "res: {res:?}");
 		let lst = res.unwrap();
@@ -662,7 +1138,7 @@ mod ikm_management {
 	#[test]
 	#[cfg(feature = "chacha")]
 	fn get_latest_ikm_1_592_734_902() {
-		let ts = SystemTime::UNIX_EPOCH + Duration::from_secs(1_592_734_902);
+		let ts = Timestamp::UNIX_EPOCH + Duration::from_secs(1_592_734_902);
 		let res = InputKeyMaterialList::import(TEST_STR);
 		assert!(res.is_ok(), "res: {res:?}");
 		let lst = res.unwrap();
@@ -685,7 +1161,7 @@ mod encryption {
 		let _ = lst.add_ikm();
 		let (not_before, not_after) = get_default_time_period();
 		let _ = lst.add_custom_ikm(Scheme::XChaCha20Poly1305WithBlake3, not_before, not_after);
-		let res = lst.get_latest_ikm(SystemTime::now());
+		let res = lst.get_latest_ikm(Timestamp::now().unwrap());
 		assert!(res.is_ok(), "res: {res:?}");
 		let latest_ikm = res.unwrap();
 		assert_eq!(latest_ikm.id, 3);
@@ -701,7 +1177,7 @@ mod encryption {
 		let _ = lst.add_ikm();
 		let (not_before, not_after) = get_default_time_period();
 		let _ = lst.add_custom_ikm(Scheme::Aes128GcmWithSha256, not_before, not_after);
-		let res = lst.get_latest_ikm(SystemTime::now());
+		let res = lst.get_latest_ikm(Timestamp::now().unwrap());
 		assert!(res.is_ok(), "res: {res:?}");
 		let latest_ikm = res.unwrap();
 		assert_eq!(latest_ikm.id, 3);
@@ -709,10 +1185,26 @@ mod encryption {
 		assert_eq!(latest_ikm.content.len(), 32);
 	}
 
+	#[test]
+	fn get_latest_ikm_state() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let _ = lst.add_pending_ikm();
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
+		assert_eq!(latest_ikm.id, 2);
+		let _ = lst.set_ikm_state(2, IkmState::DecryptOnly);
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
+		assert_eq!(latest_ikm.id, 1);
+		let _ = lst.set_ikm_state(3, IkmState::Active);
+		let latest_ikm = lst.get_latest_ikm(Timestamp::now().unwrap()).unwrap();
+		assert_eq!(latest_ikm.id, 3);
+	}
+
 	#[test]
 	fn get_latest_ikm_empty() {
 		let lst = InputKeyMaterialList::new();
-		let res = lst.get_latest_ikm(SystemTime::now());
+		let res = lst.get_latest_ikm(Timestamp::now().unwrap());
 		assert!(res.is_err());
 	}
 
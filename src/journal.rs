@@ -0,0 +1,433 @@
+use crate::clock::Timestamp;
+use crate::error::{Error, Result};
+use crate::ikm::IkmId;
+#[cfg(feature = "ikm-management")]
+use crate::ikm::InputKeyMaterialList;
+#[cfg(all(feature = "ikm-management", feature = "std"))]
+use crate::state::IkmState;
+use alloc::string::String;
+#[cfg(all(feature = "ikm-management", feature = "std"))]
+use alloc::string::ToString;
+#[cfg(feature = "ikm-management")]
+use alloc::vec::Vec;
+
+pub(crate) const JOURNAL_ENTRY_BASE_SIZE: usize = 13;
+
+/// Management operation recorded in the journal of an [InputKeyMaterialList].
+#[derive(Clone, Copy, Debug, PartialEq)]
+#[repr(u8)]
+pub enum IkmOperation {
+	/// The validity period of the IKM has been changed using
+	/// [set_ikm_validity][InputKeyMaterialList::set_ikm_validity].
+	ValidityChanged = 1,
+	/// The IKM has been reinstated using [reinstate_ikm][InputKeyMaterialList::reinstate_ikm].
+	Reinstated = 2,
+	/// The IKM has been reactivated using [reactivate_ikm][InputKeyMaterialList::reactivate_ikm].
+	Reactivated = 3,
+}
+
+impl TryFrom<u8> for IkmOperation {
+	type Error = Error;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			1 => Ok(IkmOperation::ValidityChanged),
+			2 => Ok(IkmOperation::Reinstated),
+			3 => Ok(IkmOperation::Reactivated),
+			_ => Err(Error::ParsingEncodedDataInvalidJournalEntry),
+		}
+	}
+}
+
+/// Entry of the journal of an [InputKeyMaterialList].
+///
+/// Operations which alter an existing IKM in a way that is not obvious when comparing two versions
+/// of the list, such as extending its validity period, reinstating it after a revocation or
+/// reactivating it after its retirement, are recorded in the list along with the reason given by
+/// the operator.
+#[derive(Clone, Debug, PartialEq)]
+pub struct IkmJournalEntry {
+	pub(crate) ikm_id: IkmId,
+	pub(crate) operation: IkmOperation,
+	pub(crate) date: Timestamp,
+	pub(crate) reason: String,
+}
+
+impl IkmJournalEntry {
+	/// Returns the identifier of the IKM concerned by the operation.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_ikm_id(&self) -> IkmId {
+		self.ikm_id
+	}
+
+	/// Returns the operation that has been applied to the IKM.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_operation(&self) -> IkmOperation {
+		self.operation
+	}
+
+	/// Returns the date at which the operation has been applied.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_date(&self) -> Timestamp {
+		self.date
+	}
+
+	/// Returns the reason given by the operator.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_reason(&self) -> &str {
+		&self.reason
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn is_same_entry(&self, other: &Self) -> bool {
+		self.ikm_id == other.ikm_id
+			&& self.operation == other.operation
+			&& self.reason == other.reason
+			&& self.date == other.date
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn as_bytes(&self) -> Result<Vec<u8>> {
+		let mut res = Vec::with_capacity(JOURNAL_ENTRY_BASE_SIZE + self.reason.len());
+		res.extend_from_slice(&self.ikm_id.to_le_bytes());
+		res.push(self.operation as u8);
+		res.extend_from_slice(&self.date.as_secs().to_le_bytes());
+		res.extend_from_slice(self.reason.as_bytes());
+		Ok(res)
+	}
+
+	pub(crate) fn from_bytes(b: &[u8]) -> Result<Self> {
+		if b.len() < JOURNAL_ENTRY_BASE_SIZE {
+			return Err(Error::ParsingEncodedDataInvalidJournalEntry);
+		}
+		let ts = u64::from_le_bytes(b[5..13].try_into().unwrap());
+		Ok(Self {
+			ikm_id: IkmId::from_le_bytes(b[0..4].try_into().unwrap()),
+			operation: b[4].try_into()?,
+			date: Timestamp::from_secs(ts),
+			reason: String::from_utf8(b[13..].to_vec())
+				.map_err(|_| Error::ParsingEncodedDataInvalidJournalEntry)?,
+		})
+	}
+}
+
+#[cfg(feature = "ikm-management")]
+impl InputKeyMaterialList {
+	/// Returns the journal of the operations which altered existing IKMs, from the oldest to the
+	/// most recent one.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{IkmOperation, InputKeyMaterialList};
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// ikml.revoke_ikm(ikm_id)?;
+	/// ikml.reinstate_ikm(ikm_id, "revoked by mistake")?;
+	///
+	/// let journal = ikml.get_journal();
+	/// assert_eq!(journal.len(), 1);
+	/// assert_eq!(journal[0].get_ikm_id(), ikm_id);
+	/// assert_eq!(journal[0].get_operation(), IkmOperation::Reinstated);
+	/// assert_eq!(journal[0].get_reason(), "revoked by mistake");
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn get_journal(&self) -> &[IkmJournalEntry] {
+		&self.journal
+	}
+
+	/// Change the validity period of the specified IKM, which allows to either extend or shorten
+	/// it.
+	///
+	/// The new validity period must be neither empty nor inverted. It must also stay ordered with
+	/// the ones of the other non-revoked IKMs using the same scheme which are either active or
+	/// pending: it can neither contain nor be contained in one of them, and the IKM which starts
+	/// first must remain the same. The reason, which cannot be empty, is recorded in the list's
+	/// [journal][Self::get_journal].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::InputKeyMaterialList;
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// let not_before = ikml[0].get_not_before();
+	/// let not_after = ikml[0].get_not_after() + Duration::from_secs(2_592_000);
+	/// ikml.set_ikm_validity(ikm_id, not_before, not_after, "delayed rotation")?;
+	/// assert_eq!(ikml[0].get_not_after(), not_after);
+	///
+	/// assert!(ikml.set_ikm_validity(ikm_id, not_after, not_before, "inverted").is_err());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn set_ikm_validity(
+		&mut self,
+		id: IkmId,
+		not_before: Timestamp,
+		not_after: Timestamp,
+		reason: &str,
+	) -> Result<IkmId> {
+		self.process_set_ikm_validity(id, not_before, not_after, reason, Timestamp::now()?)
+	}
+
+	#[cfg(feature = "std")]
+	fn process_set_ikm_validity(
+		&mut self,
+		id: IkmId,
+		not_before: Timestamp,
+		not_after: Timestamp,
+		reason: &str,
+		now: Timestamp,
+	) -> Result<IkmId> {
+		let pos = self.get_ikm_position(id)?;
+		check_reason(reason)?;
+		if not_before >= not_after {
+			return Err(Error::IkmInvalidValidityPeriod(id));
+		}
+		self.check_validity_order(pos, not_before, not_after)?;
+		let ikm = &mut self.ikm_lst[pos];
+		ikm.not_before = not_before;
+		ikm.not_after = not_after;
+		self.add_journal_entry(id, IkmOperation::ValidityChanged, reason, now);
+		Ok(id)
+	}
+
+	/// Reinstate a revoked IKM.
+	///
+	/// This is meant to recover from an accidental revocation. The IKM's validity period is left
+	/// unchanged, but it must still be ordered with the ones of the other IKMs as described in
+	/// [set_ikm_validity][Self::set_ikm_validity], which may not be the case if IKMs have been
+	/// added or changed since the revocation. The reason, which cannot be empty, is recorded in the
+	/// list's [journal][Self::get_journal].
+	///
+	/// <div class="warning">
+	/// Never reinstate an IKM that has been revoked because it has leaked.
+	/// </div>
+	///
+	/// # Examples
+	///
+	/// ```
+	/// let mut ikml = coffio::InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// ikml.revoke_ikm(ikm_id)?;
+	/// assert!(ikml[0].is_revoked());
+	/// ikml.reinstate_ikm(ikm_id, "revoked by mistake")?;
+	/// assert!(!ikml[0].is_revoked());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn reinstate_ikm(&mut self, id: IkmId, reason: &str) -> Result<IkmId> {
+		self.process_reinstate_ikm(id, reason, Timestamp::now()?)
+	}
+
+	#[cfg(feature = "std")]
+	fn process_reinstate_ikm(&mut self, id: IkmId, reason: &str, now: Timestamp) -> Result<IkmId> {
+		let pos = self.get_ikm_position(id)?;
+		check_reason(reason)?;
+		let ikm = &self.ikm_lst[pos];
+		if !ikm.is_revoked {
+			return Err(Error::IkmNotRevoked(id));
+		}
+		self.check_validity_order(pos, ikm.not_before, ikm.not_after)?;
+		let ikm = &mut self.ikm_lst[pos];
+		ikm.is_revoked = false;
+		ikm.revoked_at = None;
+		ikm.revocation_reason = Default::default();
+		self.add_journal_entry(id, IkmOperation::Reinstated, reason, now);
+		Ok(id)
+	}
+
+	#[cfg(feature = "std")]
+	pub(crate) fn get_ikm_position(&self, id: IkmId) -> Result<usize> {
+		self.ikm_lst
+			.iter()
+			.position(|ikm| ikm.id == id)
+			.ok_or(Error::IkmNotFound(id))
+	}
+
+	#[cfg(feature = "std")]
+	fn check_validity_order(
+		&self,
+		pos: usize,
+		not_before: Timestamp,
+		not_after: Timestamp,
+	) -> Result<()> {
+		let ikm = &self.ikm_lst[pos];
+		let conflict = self.ikm_lst.iter().find(|other| {
+			other.id != ikm.id
+				&& other.scheme == ikm.scheme
+				&& !other.is_revoked
+				&& matches!(other.state, IkmState::Active | IkmState::Pending)
+				&& ((not_before < other.not_before && not_after > other.not_after)
+					|| (not_before > other.not_before && not_after < other.not_after)
+					|| (not_before < other.not_before && ikm.not_before > other.not_before)
+					|| (not_before > other.not_before && ikm.not_before < other.not_before))
+		});
+		match conflict {
+			Some(other) => Err(Error::IkmValidityOrderConflict(ikm.id, other.id)),
+			None => Ok(()),
+		}
+	}
+
+	#[cfg(feature = "std")]
+	pub(crate) fn add_journal_entry(
+		&mut self,
+		ikm_id: IkmId,
+		operation: IkmOperation,
+		reason: &str,
+		now: Timestamp,
+	) {
+		self.journal.push(IkmJournalEntry {
+			ikm_id,
+			operation,
+			date: now,
+			reason: reason.to_string(),
+		});
+		self.is_modified = true;
+	}
+}
+
+#[cfg(all(feature = "ikm-management", feature = "std"))]
+pub(crate) fn check_reason(reason: &str) -> Result<()> {
+	if reason.trim().is_empty() {
+		return Err(Error::IkmMissingReason);
+	}
+	Ok(())
+}
+
+#[cfg(all(test, feature = "ikm-management"))]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	const DAY: Duration = Duration::from_secs(86_400);
+
+	fn get_ikm_list(now: Timestamp) -> InputKeyMaterialList {
+		let scheme = crate::DEFAULT_SCHEME;
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_custom_ikm(scheme, now - 100 * DAY, now + 10 * DAY);
+		let _ = lst.add_custom_ikm(scheme, now, now + 100 * DAY);
+		let _ = lst.add_custom_ikm(scheme, now + 90 * DAY, now + 200 * DAY);
+		lst
+	}
+
+	#[test]
+	fn set_ikm_validity() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let res = lst.process_set_ikm_validity(2, now - DAY, now + 150 * DAY, "test", now);
+		assert_eq!(res, Ok(2));
+		assert_eq!(lst[1].not_before, now - DAY);
+		assert_eq!(lst[1].not_after, now + 150 * DAY);
+		let journal = lst.get_journal();
+		assert_eq!(journal.len(), 1);
+		assert_eq!(journal[0].get_ikm_id(), 2);
+		assert_eq!(journal[0].get_operation(), IkmOperation::ValidityChanged);
+		assert_eq!(journal[0].get_date(), now);
+		assert_eq!(journal[0].get_reason(), "test");
+	}
+
+	#[test]
+	fn set_ikm_validity_invalid() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let res = lst.process_set_ikm_validity(2, now + DAY, now, "inverted", now);
+		assert_eq!(res, Err(Error::IkmInvalidValidityPeriod(2)));
+		let res = lst.process_set_ikm_validity(2, now, now, "empty", now);
+		assert_eq!(res, Err(Error::IkmInvalidValidityPeriod(2)));
+		let res =
+			lst.process_set_ikm_validity(2, now - 200 * DAY, now + 100 * DAY, "contains 1", now);
+		assert_eq!(res, Err(Error::IkmValidityOrderConflict(2, 1)));
+		let res = lst.process_set_ikm_validity(2, now + 95 * DAY, now + 150 * DAY, "within 3", now);
+		assert_eq!(res, Err(Error::IkmValidityOrderConflict(2, 3)));
+		let res = lst.process_set_ikm_validity(2, now + 95 * DAY, now + 300 * DAY, "after 3", now);
+		assert_eq!(res, Err(Error::IkmValidityOrderConflict(2, 3)));
+		let res = lst.process_set_ikm_validity(1, now - 100 * DAY, now + 150 * DAY, "extend", now);
+		assert_eq!(res, Err(Error::IkmValidityOrderConflict(1, 2)));
+		let res = lst.process_set_ikm_validity(2, now, now + DAY, " ", now);
+		assert_eq!(res, Err(Error::IkmMissingReason));
+		let res = lst.process_set_ikm_validity(42, now, now + DAY, "test", now);
+		assert_eq!(res, Err(Error::IkmNotFound(42)));
+		assert!(lst.get_journal().is_empty());
+		assert_eq!(lst[1].not_before, now);
+	}
+
+	#[test]
+	fn reinstate_ikm() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let res = lst.process_reinstate_ikm(2, "test", now);
+		assert_eq!(res, Err(Error::IkmNotRevoked(2)));
+		let _ = lst.revoke_ikm(2);
+		let res = lst.process_reinstate_ikm(2, "", now);
+		assert_eq!(res, Err(Error::IkmMissingReason));
+		let res = lst.process_reinstate_ikm(2, "test", now);
+		assert_eq!(res, Ok(2));
+		assert!(!lst[1].is_revoked);
+		assert_eq!(lst.get_journal().len(), 1);
+		assert_eq!(
+			lst.get_journal()[0].get_operation(),
+			IkmOperation::Reinstated
+		);
+	}
+
+	#[test]
+	fn reinstate_ikm_order_conflict() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let _ = lst.revoke_ikm(2);
+		let res = lst.process_set_ikm_validity(1, now - 100 * DAY, now + 150 * DAY, "test", now);
+		assert_eq!(res, Ok(1));
+		let res = lst.process_reinstate_ikm(2, "revoked by mistake", now);
+		assert_eq!(res, Err(Error::IkmValidityOrderConflict(2, 1)));
+		assert!(lst[1].is_revoked);
+		assert_eq!(lst.get_journal().len(), 1);
+	}
+
+	#[test]
+	fn reinstate_ikm_unordered_list() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let _ = lst.add_custom_ikm(crate::DEFAULT_SCHEME, now - 200 * DAY, now - 150 * DAY);
+		let _ = lst.revoke_ikm(2);
+		let res = lst.process_reinstate_ikm(2, "revoked by mistake", now);
+		assert_eq!(res, Ok(2));
+		assert!(!lst[1].is_revoked);
+	}
+
+	#[test]
+	fn export_import_journal() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let _ = lst.revoke_ikm(1);
+		let _ = lst.process_reinstate_ikm(1, "revoked by mistake", now);
+		let _ = lst.process_set_ikm_validity(3, now + 90 * DAY, now + 150 * DAY, "shorter", now);
+		let lst_bis = InputKeyMaterialList::import(&lst.export().unwrap()).unwrap();
+		let journal = lst_bis.get_journal();
+		assert_eq!(journal.len(), 2);
+		assert_eq!(journal[0].get_ikm_id(), 1);
+		assert_eq!(journal[0].get_operation(), IkmOperation::Reinstated);
+		assert_eq!(journal[0].get_reason(), "revoked by mistake");
+		assert_eq!(journal[1].get_ikm_id(), 3);
+		assert_eq!(journal[1].get_operation(), IkmOperation::ValidityChanged);
+		assert_eq!(journal[1].get_reason(), "shorter");
+	}
+
+	#[test]
+	fn journal_entry_from_bytes_invalid() {
+		let tests: &[&[u8]] = &[
+			&[],
+			&[1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0],
+			&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0x74],
+			&[1, 0, 0, 0, 4, 0, 0, 0, 0, 0, 0, 0, 0, 0x74],
+			&[1, 0, 0, 0, 1, 0, 0, 0, 0, 0, 0, 0, 0, 0xff],
+		];
+		for b in tests {
+			let res = IkmJournalEntry::from_bytes(b);
+			assert_eq!(res, Err(Error::ParsingEncodedDataInvalidJournalEntry));
+		}
+	}
+}
@@ -1,6 +1,7 @@
 use crate::canonicalization::canonicalize;
 use crate::context::KeyContext;
 use crate::ikm::InputKeyMaterial;
+use alloc::vec::Vec;
 
 pub(crate) type KdfFunction = dyn Fn(&str, &[u8]) -> Vec<u8>;
 
@@ -9,6 +10,14 @@ pub(crate) fn derive_key(
 	ctx: &KeyContext,
 	time_period: Option<u64>,
 ) -> Vec<u8> {
+	#[cfg(feature = "tracing")]
+	let _span = tracing::debug_span!(
+		"coffio.derive_key",
+		ikm_id = ikm.id,
+		scheme = ?ikm.scheme,
+		time_period = time_period,
+	)
+	.entered();
 	let mut elems = ctx.get_ctx_elems(time_period);
 	elems.push(ikm.scheme.get_key_len().to_le_bytes().to_vec());
 	let key_context = canonicalize(&elems);
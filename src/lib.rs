@@ -1,3 +1,4 @@
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
 #![warn(missing_docs)]
 
 //! # Supported use case
@@ -28,9 +29,24 @@
 //!
 //! The following features allows you to control which interfaces are exposed.
 //!
+//! - `std` (default): use the standard library, see below
 //! - `encryption` (default): interfaces related to data encryption and decryption
 //! - `ikm-management` (default): interfaces related to the IKM list management
 //! - `encrypt-at` (default): add a function allowing to encrypt data using a specified timestamp
+//! - `ikm-signature`: interfaces related to signed IKM lists, which allows to detect whether or not
+//!   an IKM list has been altered
+//! - `metrics`: in-process usage counters, which allows to know whether or not an IKM is still
+//!   used
+//! - `prometheus`: export the usage counters using the Prometheus text format
+//! - `insecure-rng-for-testing`: allow to replace the random source used to generate IKMs and
+//!   nonces, which is useful to produce reproducible test vectors. **This feature is insecure and
+//!   must never be enabled in production.**
+//! - `test-utils`: utilities useful to test applications using Coffio, such as a controllable
+//!   clock
+//! - `tracing`: emit [tracing](https://docs.rs/tracing) spans for encryptions, decryptions, key
+//!   derivations and IKM list imports, and emit the warnings as tracing events instead of logs
+//! - `wasm-js`: on `wasm32-unknown-unknown`, retrieve the random values and the system time using
+//!   JavaScript, which is required to use Coffio in a web browser or in Node.js
 //!
 //! The following features allows you to control which encryption algorithms are activated.
 //!
@@ -40,6 +56,50 @@
 //! Other features are:
 //!
 //! - `benchmark`: useful only to run the benchmark
+//! - `cli`: build the `coffio` command-line tool, which allows to create an IKM list, to add,
+//!   revoke, delete and list its IKMs, to encrypt, decrypt and inspect data, and to re-encrypt
+//!   CSV and JSON Lines files using the latest IKM
+//!
+//! # Using Coffio without the standard library
+//!
+//! Coffio can be used in `no_std` environments, such as embedded devices, as long as a global
+//! allocator is available. To do so, disable the default features and enable the ones you need:
+//!
+//! ```toml
+//! [dependencies]
+//! coffio = { version = "0.1", default-features = false, features = ["chacha", "encrypt-at"] }
+//! ```
+//!
+//! Without the `std` feature:
+//! - [Timestamp] cannot be converted from and to
+//!   [SystemTime](https://doc.rust-lang.org/std/time/struct.SystemTime.html), nor be set to the
+//!   current time using `Timestamp::now`;
+//! - there is no system clock, hence a [Clock] must be set using `Coffio::set_clock` before
+//!   encrypting or decrypting data, otherwise [Error::ClockUnavailable] is returned;
+//! - the functions relying on the current time, such as `InputKeyMaterialList::add_ikm` or
+//!   `InputKeyMaterialList::revoke_ikm`, are not available, use the ones taking a timestamp
+//!   instead, such as `InputKeyMaterialList::add_custom_ikm` or
+//!   `InputKeyMaterialList::revoke_ikm_at`;
+//! - `MemoryUsageCounterStore` is not available and the `metrics`, `tracing`, `test-utils`,
+//!   `insecure-rng-for-testing` and `wasm-js` features cannot be enabled.
+//!
+//! Random values are retrieved using [getrandom](https://docs.rs/getrandom). On targets it does
+//! not support, such as bare-metal microcontrollers, you have to provide the random source, for
+//! example the device's hardware random number generator, using getrandom's [custom
+//! backend](https://docs.rs/getrandom/0.3/getrandom/#custom-backend): build with
+//! `RUSTFLAGS='--cfg getrandom_backend="custom"'` and define the following function. **The random
+//! source must be cryptographically secure.**
+//!
+//! ```ignore
+//! #[unsafe(no_mangle)]
+//! unsafe extern "Rust" fn __getrandom_v03_custom(
+//!     dest: *mut u8,
+//!     len: usize,
+//! ) -> Result<(), getrandom::Error> {
+//!     let buf = unsafe { core::slice::from_raw_parts_mut(dest, len) };
+//!     my_hardware_rng::fill(buf).map_err(|_| getrandom::Error::UNSUPPORTED)
+//! }
+//! ```
 //!
 //! # Examples
 //!
@@ -84,8 +144,16 @@
 //! # Ok::<(), coffio::Error>(())
 //! ```
 
+extern crate alloc;
+
+#[cfg(feature = "encryption")]
+mod audit;
+#[cfg(feature = "encryption")]
+mod budget;
 #[cfg(feature = "encryption")]
 mod canonicalization;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+mod clock;
 #[cfg(feature = "encryption")]
 mod coffio;
 #[cfg(feature = "encryption")]
@@ -94,29 +162,90 @@ mod context;
 mod encrypted_data;
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
 mod error;
+#[cfg(feature = "ikm-management")]
+mod fingerprint;
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
 mod ikm;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+mod journal;
 #[cfg(feature = "encryption")]
 mod kdf;
+#[cfg(feature = "ikm-management")]
+mod merge;
+#[cfg(feature = "metrics")]
+mod metrics;
 #[cfg(feature = "encryption")]
 mod policy;
+#[cfg(feature = "ikm-management")]
+mod pruning;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+mod rand;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+mod revocation;
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
 mod scheme;
+#[cfg(feature = "encryption")]
+mod selection;
+#[cfg(feature = "ikm-signature")]
+mod signature;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+mod state;
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
 mod storage;
+#[cfg(all(test, feature = "aes", feature = "chacha", feature = "ikm-management"))]
+mod vectors;
 
 #[cfg(feature = "encryption")]
 pub use crate::coffio::Coffio;
 #[cfg(feature = "encryption")]
+pub use audit::{AuditEvent, AuditObserver, AuditOperation};
+#[cfg(all(feature = "encryption", feature = "std"))]
+pub use budget::MemoryUsageCounterStore;
+#[cfg(feature = "encryption")]
+pub use budget::{EncryptionBudget, UsageCounterStore, UsageKey};
+#[cfg(feature = "encryption")]
+pub use clock::Clock;
+#[cfg(all(feature = "encryption", feature = "test-utils"))]
+pub use clock::MockClock;
+#[cfg(all(feature = "encryption", feature = "std"))]
+pub use clock::SystemClock;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+pub use clock::Timestamp;
+#[cfg(feature = "encryption")]
 pub use context::{DataContext, KeyContext};
+#[cfg(feature = "encryption")]
+pub use encrypted_data::CiphertextInfo;
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+pub use error::{Error, ErrorKind};
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
-pub use error::Error;
+pub use ikm::{Generation, IkmId, InputKeyMaterial, InputKeyMaterialList};
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
-pub use ikm::{IkmId, InputKeyMaterial, InputKeyMaterialList};
+pub use journal::{IkmJournalEntry, IkmOperation};
+#[cfg(feature = "ikm-management")]
+pub use merge::IkmListDiff;
+#[cfg(feature = "metrics")]
+pub use metrics::{MetricsCounters, MetricsKey, MetricsSnapshot};
 #[cfg(feature = "encryption")]
-pub use policy::{DecryptionPolicy, DecryptionPolicyAction};
+pub use policy::{DecryptionPolicy, DecryptionPolicyAction, PolicyDecision, PolicyViolation};
+#[cfg(feature = "ikm-management")]
+pub use pruning::{PruneReason, PruneReport};
+#[cfg(all(
+	feature = "insecure-rng-for-testing",
+	any(feature = "encryption", feature = "ikm-management")
+))]
+pub use rand::insecure::{RandomSource, SeededRandomSource, with_random_source};
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+pub use revocation::RevocationReason;
 #[cfg(any(feature = "encryption", feature = "ikm-management"))]
 pub use scheme::Scheme;
+#[cfg(feature = "encryption")]
+pub use selection::{
+	IkmSelectionStrategy, LatestIkm, PreferScheme, RoundRobin, SkipExpiringWithin,
+};
+#[cfg(feature = "ikm-signature")]
+pub use signature::{IkmListSigningKey, IkmListVerifyingKey};
+#[cfg(any(feature = "encryption", feature = "ikm-management"))]
+pub use state::IkmState;
 
 /// Default amount of time during which the input key material will be considered valid once it has
 /// been generated. This value is expressed in seconds.
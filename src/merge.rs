@@ -0,0 +1,689 @@
+use crate::clock::Timestamp;
+use crate::error::{Error, Result};
+use crate::ikm::{IkmId, InputKeyMaterial, InputKeyMaterialList};
+use crate::journal::IkmOperation;
+use crate::state::IkmState;
+use alloc::vec::Vec;
+
+/// Differences between two [InputKeyMaterialList].
+///
+/// The differences are always expressed from the point of view of the list on which
+/// [diff][InputKeyMaterialList::diff] has been called: an IKM is considered added if it is only
+/// present in the other list.
+#[derive(Debug, Default, PartialEq)]
+pub struct IkmListDiff {
+	added: Vec<IkmId>,
+	removed: Vec<IkmId>,
+	revoked: Vec<IkmId>,
+	reinstated: Vec<IkmId>,
+	changed: Vec<IkmId>,
+}
+
+enum IkmMergeUpdate {
+	Validity,
+	Revocation,
+	Reinstatement,
+	State(IkmState),
+}
+
+impl IkmListDiff {
+	/// Returns the identifiers of the IKMs which are only present in the other list.
+	pub fn get_added(&self) -> &[IkmId] {
+		&self.added
+	}
+
+	/// Returns the identifiers of the IKMs which are missing from the other list.
+	pub fn get_removed(&self) -> &[IkmId] {
+		&self.removed
+	}
+
+	/// Returns the identifiers of the IKMs which have been revoked in the other list.
+	pub fn get_revoked(&self) -> &[IkmId] {
+		&self.revoked
+	}
+
+	/// Returns the identifiers of the IKMs which have been
+	/// [reinstated][InputKeyMaterialList::reinstate_ikm] in the other list.
+	pub fn get_reinstated(&self) -> &[IkmId] {
+		&self.reinstated
+	}
+
+	/// Returns the identifiers of the IKMs sharing the same identifier in both lists but whose
+	/// scheme, content, validity period, lifecycle state or revocation status differs, excluding
+	/// IKMs which have only been revoked or reinstated.
+	///
+	/// When returned by [merge][InputKeyMaterialList::merge], this only contains the IKMs whose
+	/// validity period or lifecycle state has been updated.
+	pub fn get_changed(&self) -> &[IkmId] {
+		&self.changed
+	}
+
+	/// Check whether or not both lists contain the same IKMs.
+	pub fn is_empty(&self) -> bool {
+		self.added.is_empty()
+			&& self.removed.is_empty()
+			&& self.revoked.is_empty()
+			&& self.reinstated.is_empty()
+			&& self.changed.is_empty()
+	}
+}
+
+impl InputKeyMaterial {
+	fn has_same_secret(&self, other: &Self) -> bool {
+		// The content of a destroyed IKM has been erased but its digest has been kept.
+		self.scheme == other.scheme && self.get_content_digest() == other.get_content_digest()
+	}
+
+	fn has_same_validity(&self, other: &Self) -> bool {
+		self.not_before == other.not_before && self.not_after == other.not_after
+	}
+}
+
+impl InputKeyMaterialList {
+	/// Check whether or not this list's journal contains an operation on the specified IKM which
+	/// is missing from the other list's journal.
+	fn has_unmerged_operation(
+		&self,
+		other: &InputKeyMaterialList,
+		id: IkmId,
+		operation: IkmOperation,
+	) -> bool {
+		self.get_unmerged_operation_date(other, id, operation)
+			.is_some()
+	}
+
+	/// Returns the date of the most recent operation on the specified IKM in this list's journal
+	/// which is missing from the other list's journal.
+	fn get_unmerged_operation_date(
+		&self,
+		other: &InputKeyMaterialList,
+		id: IkmId,
+		operation: IkmOperation,
+	) -> Option<Timestamp> {
+		self.journal
+			.iter()
+			.filter(|e| {
+				e.ikm_id == id
+					&& e.operation == operation
+					&& !other.journal.iter().any(|o| o.is_same_entry(e))
+			})
+			.map(|e| e.date)
+			.max()
+	}
+
+	/// Check whether or not this list reinstated an IKM which is still revoked in the other list.
+	///
+	/// The other list may have revoked the IKM again after it has been reinstated, in which case
+	/// the reinstatement is outdated. Revocations whose date is unknown are considered older than
+	/// the reinstatement.
+	fn has_reinstated(&self, other: &InputKeyMaterialList, revoked: &InputKeyMaterial) -> bool {
+		self.get_unmerged_operation_date(other, revoked.id, IkmOperation::Reinstated)
+			.is_some_and(|date| {
+				revoked
+					.revoked_at
+					.is_none_or(|revoked_at| revoked_at <= date)
+			})
+	}
+
+	/// Check whether or not the other list's validity period of an IKM should replace this one.
+	///
+	/// The validity period of the list that [changed][Self::set_ikm_validity] it is kept. If both
+	/// lists changed it, or if none did, there is no way to tell which one is the right one.
+	fn has_newer_validity(
+		&self,
+		other: &InputKeyMaterialList,
+		ikm: &InputKeyMaterial,
+		o: &InputKeyMaterial,
+	) -> Result<bool> {
+		if ikm.has_same_validity(o) {
+			return Ok(false);
+		}
+		let op = IkmOperation::ValidityChanged;
+		match (
+			self.has_unmerged_operation(other, ikm.id, op),
+			other.has_unmerged_operation(self, ikm.id, op),
+		) {
+			(true, false) => Ok(false),
+			(false, true) => Ok(true),
+			_ => Err(Error::IkmMergeConflict(ikm.id)),
+		}
+	}
+
+	/// Returns the lifecycle state an IKM should have once the other list has been merged into
+	/// this one.
+	///
+	/// A state may only move forward, except when a decrypt-only IKM has been reactivated: the
+	/// state of the list that reactivated it is kept. If both lists reactivated it, there is no
+	/// way to tell which state is the most recent one.
+	fn get_merged_state(
+		&self,
+		other: &InputKeyMaterialList,
+		ikm: &InputKeyMaterial,
+		o: &InputKeyMaterial,
+	) -> Result<IkmState> {
+		if ikm.state == o.state || ikm.state == IkmState::Destroyed {
+			return Ok(ikm.state);
+		}
+		if o.state == IkmState::Destroyed {
+			return Ok(o.state);
+		}
+		let op = IkmOperation::Reactivated;
+		match (
+			self.has_unmerged_operation(other, ikm.id, op),
+			other.has_unmerged_operation(self, ikm.id, op),
+		) {
+			(true, true) => Err(Error::IkmMergeConflict(ikm.id)),
+			(true, false) => Ok(ikm.state),
+			(false, true) => Ok(o.state),
+			(false, false) => Ok(if o.state > ikm.state {
+				o.state
+			} else {
+				ikm.state
+			}),
+		}
+	}
+
+	/// Compare this list with another one.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::InputKeyMaterialList;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// let mut ikml_bis = InputKeyMaterialList::import(&ikml.export()?)?;
+	/// ikml_bis.revoke_ikm(ikm_id)?;
+	/// let new_ikm_id = ikml_bis.add_ikm()?;
+	///
+	/// let diff = ikml.diff(&ikml_bis);
+	/// assert_eq!(diff.get_added(), &[new_ikm_id]);
+	/// assert_eq!(diff.get_revoked(), &[ikm_id]);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn diff(&self, other: &InputKeyMaterialList) -> IkmListDiff {
+		let mut diff = IkmListDiff::default();
+		for ikm in &self.ikm_lst {
+			match other.ikm_lst.iter().find(|o| o.id == ikm.id) {
+				Some(o) => {
+					if !ikm.has_same_secret(o) || !ikm.has_same_validity(o) || ikm.state != o.state
+					{
+						diff.changed.push(ikm.id);
+					} else if ikm.is_revoked != o.is_revoked {
+						if o.is_revoked && !self.has_reinstated(other, o) {
+							diff.revoked.push(ikm.id);
+						} else if ikm.is_revoked && other.has_reinstated(self, ikm) {
+							diff.reinstated.push(ikm.id);
+						} else {
+							diff.changed.push(ikm.id);
+						}
+					}
+				}
+				None => diff.removed.push(ikm.id),
+			}
+		}
+		for o in &other.ikm_lst {
+			if !self.ikm_lst.iter().any(|ikm| ikm.id == o.id) {
+				diff.added.push(o.id);
+			}
+		}
+		diff
+	}
+
+	/// Merge another list into this one.
+	///
+	/// The IKMs that are only present in the other list are added to this one and the IKMs revoked
+	/// in the other list are revoked in this one. IKMs which are missing from the other list are
+	/// kept, hence an IKM deleted from this list is added back if the other list still contains
+	/// it. This is useful when several operators added IKMs to copies of the same list.
+	///
+	/// The operations recorded in the [journal][Self::get_journal] are used to resolve the
+	/// differences of the IKMs present in both lists:
+	/// - an IKM [reinstated][Self::reinstate_ikm] in one list is not revoked again by the other
+	///   list, unless the other list revoked it after the reinstatement;
+	/// - the validity period of an IKM is taken from the list which
+	///   [changed][Self::set_ikm_validity] it;
+	/// - when the [lifecycle state][crate::IkmState] of an IKM differs, the most advanced one is
+	///   kept unless the IKM has been [reactivated][Self::reactivate_ikm] in only one of the lists,
+	///   in which case the state of this list is kept.
+	///
+	/// The merge fails without modifying the list if both lists contain an IKM with the same
+	/// identifier but a different scheme or content, which happens when two operators added an
+	/// IKM to their own copy of the list, if the validity period of an IKM differs and has been
+	/// changed in both lists or in none of them, or if an IKM has been reactivated in both lists
+	/// but its state differs.
+	///
+	/// Returns the differences that have been applied to this list.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::InputKeyMaterialList;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let _ = ikml.add_ikm()?;
+	/// let exported_ikml = ikml.export()?;
+	///
+	/// let mut ikml_eu = InputKeyMaterialList::import(&exported_ikml)?;
+	/// let mut ikml_us = InputKeyMaterialList::import(&exported_ikml)?;
+	/// let _ = ikml_eu.add_ikm()?;
+	/// ikml_us.revoke_ikm(1)?;
+	///
+	/// let diff = ikml_eu.merge(&ikml_us)?;
+	/// assert_eq!(diff.get_revoked(), &[1]);
+	/// assert_eq!(ikml_eu.len(), 2);
+	///
+	/// // The revocation was a mistake.
+	/// ikml_eu.reinstate_ikm(1, "revoked by mistake")?;
+	/// let diff = ikml_eu.merge(&ikml_us)?;
+	/// assert!(diff.get_revoked().is_empty());
+	/// assert!(!ikml_eu[0].is_revoked());
+	///
+	/// // Both operators added an IKM with the same identifier.
+	/// let _ = ikml_us.add_ikm()?;
+	/// assert!(ikml_eu.merge(&ikml_us).is_err());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn merge(&mut self, other: &InputKeyMaterialList) -> Result<IkmListDiff> {
+		let mut updates = Vec::new();
+		for ikm in &self.ikm_lst {
+			if let Some(o) = other.ikm_lst.iter().find(|o| o.id == ikm.id) {
+				if !ikm.has_same_secret(o) {
+					return Err(Error::IkmMergeIdCollision(ikm.id));
+				}
+				if self.has_newer_validity(other, ikm, o)? {
+					updates.push((ikm.id, IkmMergeUpdate::Validity));
+				}
+				if !ikm.is_revoked && o.is_revoked && !self.has_reinstated(other, o) {
+					updates.push((ikm.id, IkmMergeUpdate::Revocation));
+				} else if ikm.is_revoked && !o.is_revoked && other.has_reinstated(self, ikm) {
+					updates.push((ikm.id, IkmMergeUpdate::Reinstatement));
+				}
+				let state = self.get_merged_state(other, ikm, o)?;
+				if state != ikm.state {
+					updates.push((ikm.id, IkmMergeUpdate::State(state)));
+				}
+			}
+		}
+		let mut diff = IkmListDiff::default();
+		for (id, update) in updates {
+			let (Some(ikm), Some(o)) = (
+				self.ikm_lst.iter_mut().find(|ikm| ikm.id == id),
+				other.ikm_lst.iter().find(|o| o.id == id),
+			) else {
+				continue;
+			};
+			match update {
+				IkmMergeUpdate::Validity => {
+					ikm.not_before = o.not_before;
+					ikm.not_after = o.not_after;
+					diff.changed.push(id);
+				}
+				IkmMergeUpdate::Revocation => {
+					ikm.is_revoked = true;
+					ikm.revoked_at = o.revoked_at;
+					ikm.revocation_reason = o.revocation_reason;
+					diff.revoked.push(id);
+				}
+				IkmMergeUpdate::Reinstatement => {
+					ikm.is_revoked = false;
+					ikm.revoked_at = None;
+					ikm.revocation_reason = Default::default();
+					diff.reinstated.push(id);
+				}
+				IkmMergeUpdate::State(state) => {
+					ikm.set_state(state);
+					if !diff.changed.contains(&id) {
+						diff.changed.push(id);
+					}
+				}
+			}
+		}
+		for o in &other.ikm_lst {
+			if !self.ikm_lst.iter().any(|ikm| ikm.id == o.id) {
+				// Keep the list ordered so the most recent IKMs stay at the end.
+				let pos = self
+					.ikm_lst
+					.iter()
+					.position(|ikm| ikm.id > o.id)
+					.unwrap_or(self.ikm_lst.len());
+				self.ikm_lst.insert(pos, o.clone());
+				diff.added.push(o.id);
+			}
+		}
+		let mut has_new_entries = false;
+		for entry in &other.journal {
+			if !self.journal.iter().any(|e| e.is_same_entry(entry)) {
+				self.journal.push(entry.clone());
+				has_new_entries = true;
+			}
+		}
+		self.id_counter = self.id_counter.max(other.id_counter);
+		self.generation = self.generation.max(other.generation);
+		if !diff.is_empty() || has_new_entries || other.is_modified {
+			self.is_modified = true;
+		}
+		Ok(diff)
+	}
+
+	/// Replace this list with an edited copy of it.
+	///
+	/// The edited copy is accepted only if it has been derived from the current version of this
+	/// list, which means its [generation][Self::get_generation] must be the one of this list
+	/// incremented by one. Otherwise, someone else modified the list in the meantime and the
+	/// edited copy is stale: you should import the current list and apply your modifications again,
+	/// or [merge][Self::merge] them.
+	///
+	/// Returns the differences that have been applied to this list.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::InputKeyMaterialList;
+	///
+	/// let mut stored_ikml = InputKeyMaterialList::new();
+	/// let _ = stored_ikml.add_ikm()?;
+	/// let exported_ikml = stored_ikml.export()?;
+	/// let mut stored_ikml = InputKeyMaterialList::import(&exported_ikml)?;
+	///
+	/// let mut edit_1 = InputKeyMaterialList::import(&exported_ikml)?;
+	/// let mut edit_2 = InputKeyMaterialList::import(&exported_ikml)?;
+	/// let _ = edit_1.add_ikm()?;
+	/// let _ = edit_2.revoke_ikm(1)?;
+	///
+	/// assert!(stored_ikml.update(edit_1).is_ok());
+	/// assert!(stored_ikml.update(edit_2).is_err());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn update(&mut self, mut edited: InputKeyMaterialList) -> Result<IkmListDiff> {
+		let expected = self.get_generation() + 1;
+		let got = edited.get_generation();
+		if got != expected {
+			return Err(Error::IkmListStaleGeneration(expected, got));
+		}
+		let diff = self.diff(&edited);
+		edited.generation = got;
+		edited.is_modified = false;
+		*self = edited;
+		Ok(diff)
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::RevocationReason;
+	use std::time::Duration;
+
+	const DAY: Duration = Duration::from_secs(86_400);
+
+	fn get_ikm_list() -> InputKeyMaterialList {
+		let mut lst = InputKeyMaterialList::new();
+		for _ in 0..4 {
+			let _ = lst.add_ikm();
+		}
+		let s = lst.export().unwrap();
+		InputKeyMaterialList::import(&s).unwrap()
+	}
+
+	fn copy(lst: &InputKeyMaterialList) -> InputKeyMaterialList {
+		InputKeyMaterialList::import(&lst.export().unwrap()).unwrap()
+	}
+
+	#[test]
+	fn diff_empty() {
+		let lst = get_ikm_list();
+		let lst_bis = copy(&lst);
+		let diff = lst.diff(&lst_bis);
+		assert!(diff.is_empty(), "diff: {diff:?}");
+	}
+
+	#[test]
+	fn diff() {
+		let lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		let _ = lst_bis.add_ikm();
+		let _ = lst_bis.delete_ikm(1);
+		let _ = lst_bis.revoke_ikm(2);
+		lst_bis.ikm_lst[1].content = vec![0; 32];
+		lst_bis.ikm_lst[2].not_after = Timestamp::UNIX_EPOCH;
+
+		let diff = lst.diff(&lst_bis);
+		assert_eq!(diff.get_added(), &[5]);
+		assert_eq!(diff.get_removed(), &[1]);
+		assert_eq!(diff.get_revoked(), &[2]);
+		assert_eq!(diff.get_changed(), &[3, 4]);
+
+		let diff = lst_bis.diff(&lst);
+		assert_eq!(diff.get_added(), &[1]);
+		assert_eq!(diff.get_removed(), &[5]);
+		assert!(diff.get_revoked().is_empty());
+		assert_eq!(diff.get_changed(), &[2, 3, 4]);
+	}
+
+	#[test]
+	fn merge() {
+		let mut lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		let _ = lst.revoke_ikm(1);
+		let _ = lst.delete_ikm(3);
+		let _ = lst_bis.revoke_ikm(2);
+		let _ = lst_bis.add_ikm();
+
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		let diff = res.unwrap();
+		assert_eq!(diff.get_added(), &[3, 5]);
+		assert!(diff.get_removed().is_empty());
+		assert_eq!(diff.get_revoked(), &[2]);
+		assert!(diff.get_changed().is_empty());
+		let ids: Vec<IkmId> = lst.iter().map(|ikm| ikm.id).collect();
+		assert_eq!(ids, vec![1, 2, 3, 4, 5]);
+		assert!(lst.iter().take(2).all(|ikm| ikm.is_revoked));
+		assert_eq!(lst.id_counter, 5);
+		assert_eq!(lst.get_generation(), 2);
+	}
+
+	#[test]
+	fn merge_journal() {
+		let mut lst = get_ikm_list();
+		let _ = lst.revoke_ikm(1);
+		let _ = lst.reinstate_ikm(1, "revoked by mistake");
+		let mut lst_bis = copy(&lst);
+		let _ = lst_bis.revoke_ikm(2);
+		let _ = lst_bis.reinstate_ikm(2, "revoked by mistake");
+
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		let journal = lst.get_journal();
+		assert_eq!(journal.len(), 2);
+		assert_eq!(journal[0].get_ikm_id(), 1);
+		assert_eq!(journal[1].get_ikm_id(), 2);
+	}
+
+	#[test]
+	fn merge_reinstated() {
+		let mut lst = get_ikm_list();
+		let _ = lst.revoke_ikm(1);
+		let _ = lst.revoke_ikm(2);
+		let mut lst_bis = copy(&lst);
+		let _ = lst.reinstate_ikm(1, "revoked by mistake");
+		let _ = lst_bis.reinstate_ikm(2, "revoked by mistake");
+
+		let diff = lst.diff(&lst_bis);
+		assert!(diff.get_revoked().is_empty());
+		assert_eq!(diff.get_reinstated(), &[2]);
+		assert_eq!(diff.get_changed(), &[1]);
+
+		let mut lst_ter = copy(&lst_bis);
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		let diff = res.unwrap();
+		assert!(diff.get_revoked().is_empty());
+		assert_eq!(diff.get_reinstated(), &[2]);
+		assert!(lst.iter().all(|ikm| !ikm.is_revoked));
+		let res = lst_ter.merge(&lst);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(res.unwrap().get_reinstated(), &[1]);
+		assert!(lst_ter.iter().all(|ikm| !ikm.is_revoked));
+		assert!(lst.diff(&lst_ter).is_empty());
+	}
+
+	#[test]
+	fn merge_revoked_after_reinstatement() {
+		let now = Timestamp::now().unwrap();
+		let reason = RevocationReason::KeyCompromise;
+		let mut lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		let _ = lst.revoke_ikm(1);
+		let _ = lst.reinstate_ikm(1, "revoked by mistake");
+		let _ = lst.revoke_ikm(2);
+		let _ = lst.reinstate_ikm(2, "revoked by mistake");
+		let _ = lst_bis.revoke_ikm_at(1, now + DAY, reason);
+		let _ = lst_bis.revoke_ikm_at(2, now - DAY, reason);
+
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(res.unwrap().get_revoked(), &[1]);
+		assert!(lst[0].is_revoked);
+		assert!(!lst[1].is_revoked);
+	}
+
+	#[test]
+	fn merge_validity() {
+		let lst_orig = get_ikm_list();
+		let mut lst = copy(&lst_orig);
+		let mut lst_bis = copy(&lst_orig);
+		let not_before = lst[3].not_before;
+		let not_after = lst[3].not_after + 30 * DAY;
+		let _ = lst_bis.set_ikm_validity(4, not_before, not_after, "delayed rotation");
+
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(res.unwrap().get_changed(), &[4]);
+		assert_eq!(lst[3].not_after, not_after);
+		let res = lst_bis.merge(&lst_orig);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert!(res.unwrap().get_changed().is_empty());
+		assert_eq!(lst_bis[3].not_after, not_after);
+
+		let mut lst_ter = copy(&lst_orig);
+		let _ = lst_ter.set_ikm_validity(4, not_before, not_after - DAY, "other rotation");
+		let res = lst_ter.merge(&lst_bis);
+		assert_eq!(res, Err(Error::IkmMergeConflict(4)));
+		assert_eq!(lst_ter[3].not_after, not_after - DAY);
+	}
+
+	#[test]
+	fn merge_state() {
+		let mut lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		let _ = lst.set_ikm_state(1, IkmState::DecryptOnly);
+		let _ = lst_bis.set_ikm_state(1, IkmState::Destroyed);
+		let _ = lst_bis.set_ikm_state(2, IkmState::DecryptOnly);
+
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(lst[0].state, IkmState::Destroyed);
+		assert!(lst[0].content.iter().all(|b| *b == 0));
+		assert_eq!(lst[1].state, IkmState::DecryptOnly);
+		assert_eq!(lst[2].state, IkmState::Active);
+		assert!(lst.diff(&lst_bis).is_empty());
+	}
+
+	#[test]
+	fn merge_state_reactivated() {
+		let mut lst = get_ikm_list();
+		let _ = lst.set_ikm_state(1, IkmState::DecryptOnly);
+		let _ = lst.set_ikm_state(2, IkmState::DecryptOnly);
+		let mut lst_bis = copy(&lst);
+		let _ = lst.reactivate_ikm(1, "retired too early");
+		let _ = lst_bis.reactivate_ikm(2, "retired too early");
+
+		let mut lst_ter = copy(&lst_bis);
+		let res = lst.merge(&lst_bis);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(lst[0].state, IkmState::Active);
+		assert_eq!(lst[1].state, IkmState::Active);
+		let res = lst_ter.merge(&lst);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(lst_ter[0].state, IkmState::Active);
+		assert_eq!(lst_ter[1].state, IkmState::Active);
+
+		let _ = lst.set_ikm_state(1, IkmState::DecryptOnly);
+		let res = lst_ter.merge(&lst);
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(lst_ter[0].state, IkmState::DecryptOnly);
+	}
+
+	#[test]
+	fn merge_state_conflict() {
+		let mut lst = get_ikm_list();
+		let _ = lst.set_ikm_state(1, IkmState::DecryptOnly);
+		let mut lst_bis = copy(&lst);
+		let _ = lst.reactivate_ikm(1, "retired too early");
+		let _ = lst_bis.reactivate_ikm(1, "retired too early, again");
+		let _ = lst_bis.set_ikm_state(1, IkmState::DecryptOnly);
+		let res = lst.merge(&lst_bis);
+		assert_eq!(res, Err(Error::IkmMergeConflict(1)));
+		assert_eq!(lst[0].state, IkmState::Active);
+	}
+
+	#[test]
+	fn merge_destroyed_id_collision() {
+		let mut lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		lst_bis.ikm_lst[0].content = vec![0; 32];
+		let _ = lst_bis.set_ikm_state(1, IkmState::Destroyed);
+		let res = lst.merge(&lst_bis);
+		assert_eq!(res, Err(Error::IkmMergeIdCollision(1)));
+		let _ = lst.set_ikm_state(1, IkmState::Destroyed);
+		let res = lst.merge(&copy(&lst_bis));
+		assert_eq!(res, Err(Error::IkmMergeIdCollision(1)));
+	}
+
+	#[test]
+	fn merge_id_collision() {
+		let mut lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		let _ = lst.add_ikm();
+		let _ = lst_bis.add_ikm();
+		let _ = lst_bis.revoke_ikm(1);
+		let res = lst.merge(&lst_bis);
+		assert_eq!(res, Err(Error::IkmMergeIdCollision(5)));
+		assert!(!lst.ikm_lst[0].is_revoked);
+	}
+
+	#[test]
+	fn merge_conflict() {
+		let mut lst = get_ikm_list();
+		let mut lst_bis = copy(&lst);
+		lst_bis.ikm_lst[0].not_after = Timestamp::UNIX_EPOCH;
+		let res = lst.merge(&lst_bis);
+		assert_eq!(res, Err(Error::IkmMergeConflict(1)));
+	}
+
+	#[test]
+	fn update() {
+		let mut lst = get_ikm_list();
+		assert_eq!(lst.get_generation(), 1);
+		let mut edit_1 = copy(&lst);
+		let mut edit_2 = copy(&lst);
+		let _ = edit_1.add_ikm();
+		let _ = edit_1.add_ikm();
+		let _ = edit_2.revoke_ikm(1);
+
+		let res = lst.update(copy(&edit_1));
+		assert!(res.is_ok(), "res: {res:?}");
+		assert_eq!(res.unwrap().get_added(), &[5, 6]);
+		assert_eq!(lst.get_generation(), 2);
+		assert_eq!(lst.len(), 6);
+
+		let res = lst.update(edit_2);
+		assert_eq!(res, Err(Error::IkmListStaleGeneration(3, 2)));
+		assert_eq!(lst.len(), 6);
+
+		let res = lst.update(copy(&lst));
+		assert_eq!(res, Err(Error::IkmListStaleGeneration(3, 2)));
+	}
+}
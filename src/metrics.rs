@@ -0,0 +1,282 @@
+use crate::audit::{AuditEvent, AuditOperation};
+use crate::ikm::IkmId;
+use crate::scheme::Scheme;
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+/// Dimensions used to break down the usage counters.
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub struct MetricsKey {
+	ikm_id: Option<IkmId>,
+	scheme: Option<Scheme>,
+	key_context: String,
+}
+
+impl MetricsKey {
+	/// Returns the identifier of the IKM. This identifier is not available for operations that
+	/// failed before the IKM could be determined, for example because of a malformed input.
+	pub fn get_ikm_id(&self) -> Option<IkmId> {
+		self.ikm_id
+	}
+
+	/// Returns the scheme of the IKM. The scheme is not available if the IKM is not part of the
+	/// IKM list.
+	pub fn get_scheme(&self) -> Option<Scheme> {
+		self.scheme
+	}
+
+	/// Returns the canonicalized representation of the key context.
+	pub fn get_key_context(&self) -> &str {
+		&self.key_context
+	}
+}
+
+/// Usage counters.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub struct MetricsCounters {
+	encryptions: u64,
+	decryptions: u64,
+	encryption_failures: u64,
+	decryption_failures: u64,
+}
+
+impl MetricsCounters {
+	/// Returns the number of successful encryptions.
+	pub fn get_encryptions(&self) -> u64 {
+		self.encryptions
+	}
+
+	/// Returns the number of successful decryptions.
+	pub fn get_decryptions(&self) -> u64 {
+		self.decryptions
+	}
+
+	/// Returns the number of failed encryptions.
+	pub fn get_encryption_failures(&self) -> u64 {
+		self.encryption_failures
+	}
+
+	/// Returns the number of failed decryptions.
+	pub fn get_decryption_failures(&self) -> u64 {
+		self.decryption_failures
+	}
+}
+
+#[derive(Debug, Default)]
+pub(crate) struct UsageMetrics {
+	counters: Mutex<HashMap<MetricsKey, MetricsCounters>>,
+}
+
+impl UsageMetrics {
+	pub(crate) fn record(&self, event: &AuditEvent<'_>, scheme: Option<Scheme>) {
+		let key = MetricsKey {
+			ikm_id: event.get_ikm_id(),
+			scheme,
+			key_context: event.get_key_context(),
+		};
+		let mut counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+		let counter = counters.entry(key).or_default();
+		let value = match (event.get_operation(), event.is_success()) {
+			(AuditOperation::Encrypt, true) => &mut counter.encryptions,
+			(AuditOperation::Decrypt, true) => &mut counter.decryptions,
+			(AuditOperation::Encrypt, false) => &mut counter.encryption_failures,
+			(AuditOperation::Decrypt, false) => &mut counter.decryption_failures,
+		};
+		*value = value.saturating_add(1);
+	}
+
+	pub(crate) fn snapshot(&self) -> MetricsSnapshot {
+		let counters = self.counters.lock().unwrap_or_else(|e| e.into_inner());
+		let mut entries: Vec<_> = counters.iter().map(|(k, v)| (k.clone(), *v)).collect();
+		entries
+			.sort_by(|(a, _), (b, _)| (a.ikm_id, &a.key_context).cmp(&(b.ikm_id, &b.key_context)));
+		MetricsSnapshot { entries }
+	}
+}
+
+/// Point-in-time copy of the usage counters of a [Coffio][crate::Coffio] instance.
+///
+/// # Examples
+///
+/// ```
+/// use coffio::{Coffio, InputKeyMaterialList, KeyContext};
+///
+/// let mut ikm_list = InputKeyMaterialList::new();
+/// let _ = ikm_list.add_ikm()?;
+/// let key_ctx: KeyContext = ["db name", "table name", "column name"].into();
+/// let mut coffio = Coffio::new(&ikm_list);
+/// coffio.enable_metrics();
+///
+/// let encrypted_data = coffio.encrypt(&key_ctx, &[].into(), b"Hello, World!")?;
+/// let _ = coffio.decrypt(&key_ctx, &[].into(), &encrypted_data)?;
+///
+/// let snapshot = coffio.get_metrics_snapshot().unwrap();
+/// for (key, counters) in snapshot.get_entries() {
+///     println!(
+///         "IKM {:?}: {} decryptions",
+///         key.get_ikm_id(),
+///         counters.get_decryptions()
+///     );
+/// }
+/// # Ok::<(), coffio::Error>(())
+/// ```
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct MetricsSnapshot {
+	entries: Vec<(MetricsKey, MetricsCounters)>,
+}
+
+impl MetricsSnapshot {
+	/// Returns the counters, sorted by IKM identifier and key context.
+	pub fn get_entries(&self) -> &[(MetricsKey, MetricsCounters)] {
+		&self.entries
+	}
+
+	/// Returns the sum of the counters of the specified IKM.
+	pub fn get_ikm_counters(&self, ikm_id: IkmId) -> MetricsCounters {
+		self.entries
+			.iter()
+			.filter(|(k, _)| k.ikm_id == Some(ikm_id))
+			.fold(MetricsCounters::default(), |acc, (_, c)| MetricsCounters {
+				encryptions: acc.encryptions.saturating_add(c.encryptions),
+				decryptions: acc.decryptions.saturating_add(c.decryptions),
+				encryption_failures: acc
+					.encryption_failures
+					.saturating_add(c.encryption_failures),
+				decryption_failures: acc
+					.decryption_failures
+					.saturating_add(c.decryption_failures),
+			})
+	}
+
+	/// Export the counters using the Prometheus text exposition format.
+	#[cfg(feature = "prometheus")]
+	pub fn to_prometheus(&self) -> String {
+		let metrics: [(&str, &str, CounterGetter); 4] = [
+			(
+				"coffio_encryptions_total",
+				"Number of successful encryptions.",
+				MetricsCounters::get_encryptions,
+			),
+			(
+				"coffio_decryptions_total",
+				"Number of successful decryptions.",
+				MetricsCounters::get_decryptions,
+			),
+			(
+				"coffio_encryption_failures_total",
+				"Number of failed encryptions.",
+				MetricsCounters::get_encryption_failures,
+			),
+			(
+				"coffio_decryption_failures_total",
+				"Number of failed decryptions.",
+				MetricsCounters::get_decryption_failures,
+			),
+		];
+		let mut res = String::new();
+		for (name, help, get_value) in metrics {
+			res += &format!("# HELP {name} {help}\n# TYPE {name} counter\n");
+			for (key, counters) in &self.entries {
+				let ikm_id = key.ikm_id.map(|id| id.to_string()).unwrap_or_default();
+				let scheme = key.scheme.map(|s| format!("{s:?}")).unwrap_or_default();
+				res += &format!(
+					"{name}{{ikm_id=\"{ikm_id}\",scheme=\"{scheme}\",key_context=\"{}\"}} {}\n",
+					escape_label_value(&key.key_context),
+					get_value(counters)
+				);
+			}
+		}
+		res
+	}
+}
+
+#[cfg(feature = "prometheus")]
+type CounterGetter = fn(&MetricsCounters) -> u64;
+
+#[cfg(feature = "prometheus")]
+fn escape_label_value(s: &str) -> String {
+	s.replace('\\', "\\\\")
+		.replace('"', "\\\"")
+		.replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use crate::context::KeyContext;
+	use crate::error::Error;
+
+	fn record(
+		metrics: &UsageMetrics,
+		operation: AuditOperation,
+		ikm_id: Option<IkmId>,
+		error: Option<&Error>,
+	) {
+		let key_ctx: KeyContext = ["db_name", "table_name", "column_name"].into();
+		let mut event = AuditEvent::new(operation, &key_ctx);
+		event.ikm_id = ikm_id;
+		event.error = error;
+		metrics.record(&event, ikm_id.map(|_| crate::DEFAULT_SCHEME));
+	}
+
+	fn get_metrics() -> UsageMetrics {
+		let metrics = UsageMetrics::default();
+		let err = Error::IkmNoneAvailable;
+		record(&metrics, AuditOperation::Encrypt, Some(2), None);
+		record(&metrics, AuditOperation::Encrypt, Some(2), None);
+		record(&metrics, AuditOperation::Decrypt, Some(2), None);
+		record(&metrics, AuditOperation::Decrypt, Some(1), None);
+		record(&metrics, AuditOperation::Decrypt, Some(1), Some(&err));
+		record(&metrics, AuditOperation::Decrypt, None, Some(&err));
+		metrics
+	}
+
+	#[test]
+	fn snapshot() {
+		let snapshot = get_metrics().snapshot();
+		let entries = snapshot.get_entries();
+		assert_eq!(entries.len(), 3);
+		assert_eq!(entries[0].0.get_ikm_id(), None);
+		assert_eq!(entries[0].0.get_scheme(), None);
+		assert_eq!(entries[0].1.get_decryption_failures(), 1);
+		assert_eq!(entries[1].0.get_ikm_id(), Some(1));
+		assert_eq!(entries[1].0.get_scheme(), Some(crate::DEFAULT_SCHEME));
+		assert_eq!(
+			entries[1].0.get_key_context(),
+			"ZGJfbmFtZQ:dGFibGVfbmFtZQ:Y29sdW1uX25hbWU"
+		);
+		assert_eq!(entries[1].1.get_decryptions(), 1);
+		assert_eq!(entries[1].1.get_decryption_failures(), 1);
+		assert_eq!(entries[2].0.get_ikm_id(), Some(2));
+		assert_eq!(entries[2].1.get_encryptions(), 2);
+		assert_eq!(entries[2].1.get_decryptions(), 1);
+		assert_eq!(entries[2].1.get_encryption_failures(), 0);
+
+		let counters = snapshot.get_ikm_counters(2);
+		assert_eq!(counters, entries[2].1);
+		assert_eq!(snapshot.get_ikm_counters(42), MetricsCounters::default());
+	}
+
+	#[test]
+	#[cfg(feature = "prometheus")]
+	fn to_prometheus() {
+		let prom = get_metrics().snapshot().to_prometheus();
+		let scheme = format!("{:?}", crate::DEFAULT_SCHEME);
+		assert!(prom.starts_with(
+			"# HELP coffio_encryptions_total Number of successful encryptions.\n# TYPE coffio_encryptions_total counter\n"
+		));
+		assert!(prom.contains(&format!(
+			"coffio_encryptions_total{{ikm_id=\"2\",scheme=\"{scheme}\",key_context=\"ZGJfbmFtZQ:dGFibGVfbmFtZQ:Y29sdW1uX25hbWU\"}} 2\n"
+		)));
+		assert!(prom.contains(
+			"coffio_decryption_failures_total{ikm_id=\"\",scheme=\"\",key_context=\"ZGJfbmFtZQ:dGFibGVfbmFtZQ:Y29sdW1uX25hbWU\"} 1\n"
+		));
+		assert_eq!(prom.lines().count(), 4 * (2 + 3));
+	}
+
+	#[test]
+	#[cfg(feature = "prometheus")]
+	fn escape_label() {
+		assert_eq!(escape_label_value("a\"b\\c\nd"), "a\\\"b\\\\c\\nd");
+	}
+}
@@ -1,10 +1,12 @@
+use crate::IkmState;
 use crate::InputKeyMaterial;
 use crate::KeyContext;
+use crate::clock::{Clock, Timestamp};
 use crate::error::{Error, Result};
-use std::time::{SystemTime, UNIX_EPOCH};
+use alloc::vec::Vec;
 
 /// Define the action that will be taken when attempting to decrypt data.
-#[derive(Clone, Copy)]
+#[derive(Clone, Copy, Debug, PartialEq)]
 pub enum DecryptionPolicyAction {
 	/// Allow decryption.
 	Allow,
@@ -14,6 +16,64 @@ pub enum DecryptionPolicyAction {
 	Warn,
 }
 
+/// Policy rule triggered when attempting to decrypt data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PolicyViolation {
+	/// The IKM has been used before its validity period.
+	EarlyEnc,
+	/// The IKM was expired when the encryption took place.
+	ExpiredEnc,
+	/// The IKM is now expired.
+	ExpiredNow,
+	/// The data has been encrypted using a time period located in the future.
+	FutureEnc,
+	/// The IKM is still pending activation.
+	Pending,
+	/// The IKM has been destroyed. Such a violation is always denied.
+	Destroyed,
+	/// The IKM is revoked.
+	Revoked,
+	/// The IKM is revoked and has been used before its revocation date.
+	RevokedEncBefore,
+	/// The IKM is revoked and has been used after its revocation date.
+	RevokedEncAfter,
+}
+
+impl From<PolicyViolation> for Error {
+	fn from(violation: PolicyViolation) -> Self {
+		match violation {
+			PolicyViolation::EarlyEnc => Error::PolicyDecryptionEarly,
+			PolicyViolation::ExpiredEnc => Error::PolicyDecryptionExpiredEnc,
+			PolicyViolation::ExpiredNow => Error::PolicyDecryptionExpiredNow,
+			PolicyViolation::FutureEnc => Error::PolicyDecryptionFuture,
+			PolicyViolation::Pending => Error::PolicyDecryptionPending,
+			PolicyViolation::Destroyed => Error::PolicyDecryptionDestroyed,
+			PolicyViolation::Revoked => Error::PolicyDecryptionRevoked,
+			PolicyViolation::RevokedEncBefore => Error::PolicyDecryptionRevokedEncBefore,
+			PolicyViolation::RevokedEncAfter => Error::PolicyDecryptionRevokedEncAfter,
+		}
+	}
+}
+
+/// Action taken on a policy rule triggered when attempting to decrypt data.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct PolicyDecision {
+	violation: PolicyViolation,
+	action: DecryptionPolicyAction,
+}
+
+impl PolicyDecision {
+	/// Returns the policy rule that has been triggered.
+	pub fn get_violation(&self) -> PolicyViolation {
+		self.violation
+	}
+
+	/// Returns the action that has been taken.
+	pub fn get_action(&self) -> DecryptionPolicyAction {
+		self.action
+	}
+}
+
 /// Set actions that will be taken when attempting to decrypt data that has previously been
 /// encrypted using a now expired or revoked IKM.
 #[derive(Clone, Copy)]
@@ -22,18 +82,57 @@ pub struct DecryptionPolicy {
 	expired_enc: DecryptionPolicyAction,
 	expired_now: DecryptionPolicyAction,
 	future_enc: DecryptionPolicyAction,
+	pending: DecryptionPolicyAction,
 	revoked: DecryptionPolicyAction,
+	revoked_enc_before: Option<DecryptionPolicyAction>,
+	revoked_enc_after: Option<DecryptionPolicyAction>,
 }
 
 impl DecryptionPolicy {
 	/// Enforce the policy on a given IKM.
+	#[cfg(feature = "std")]
 	pub fn check(
 		&self,
 		ikm: &InputKeyMaterial,
 		key_ctx: &KeyContext,
 		time_period: Option<u64>,
 	) -> Result<()> {
-		process_check(self, ikm, key_ctx, time_period, SystemTime::now())
+		process_check(self, ikm, key_ctx, time_period, Timestamp::now()?)
+	}
+
+	/// Enforce the policy on a given IKM, using the specified clock to get the current time.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{DecryptionPolicy, InputKeyMaterialList, KeyContext, SystemClock};
+	///
+	/// let mut ikm_list = InputKeyMaterialList::new();
+	/// let _ = ikm_list.add_ikm()?;
+	/// let key_ctx: KeyContext = ["db name", "table name", "column name"].into();
+	/// let policy = DecryptionPolicy::default();
+	/// policy.check_with_clock(&ikm_list[0], &key_ctx, None, &SystemClock)?;
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn check_with_clock(
+		&self,
+		ikm: &InputKeyMaterial,
+		key_ctx: &KeyContext,
+		time_period: Option<u64>,
+		clock: &dyn Clock,
+	) -> Result<()> {
+		process_check(self, ikm, key_ctx, time_period, clock.now()?)
+	}
+
+	pub(crate) fn check_with_decisions(
+		&self,
+		ikm: &InputKeyMaterial,
+		key_ctx: &KeyContext,
+		time_period: Option<u64>,
+		curr_time: Timestamp,
+		decisions: &mut Vec<PolicyDecision>,
+	) -> Result<()> {
+		process_check_with_decisions(self, ikm, key_ctx, time_period, curr_time, decisions)
 	}
 
 	/// Set the action for an IKM which has been used before its validity period.
@@ -73,12 +172,49 @@ impl DecryptionPolicy {
 		self
 	}
 
+	/// Set the action for an IKM which is still pending activation.
+	/// Default value is warn.
+	///
+	/// Data encrypted using a pending IKM means either that a node activated the IKM too early or
+	/// that the IKM list has been rolled back after the IKM's activation.
+	pub fn set_pending(&mut self, action: DecryptionPolicyAction) -> &mut Self {
+		self.pending = action;
+		self
+	}
+
 	/// Set the action for a revoked IKM.
 	/// Default value is warn.
+	///
+	/// This action is used when it is not possible to know whether the data has been encrypted
+	/// before or after the revocation, which happens when the IKM has no revocation date, when the
+	/// key is not periodic or when the encryption took place during the same period as the
+	/// revocation. It is also used for data encrypted before or after the revocation unless a
+	/// specific action has been set using [set_revoked_enc_before][Self::set_revoked_enc_before]
+	/// or [set_revoked_enc_after][Self::set_revoked_enc_after].
 	pub fn set_revoked(&mut self, action: DecryptionPolicyAction) -> &mut Self {
 		self.revoked = action;
 		self
 	}
+
+	/// Set the action for a revoked IKM which has been used before its revocation date.
+	/// Default value is the action set for a revoked IKM.
+	///
+	/// Warning: This policy will only work for periodic keys and the check is limited to periods
+	/// which are fully before the revocation date.
+	pub fn set_revoked_enc_before(&mut self, action: DecryptionPolicyAction) -> &mut Self {
+		self.revoked_enc_before = Some(action);
+		self
+	}
+
+	/// Set the action for a revoked IKM which has been used after its revocation date.
+	/// Default value is the action set for a revoked IKM.
+	///
+	/// Warning: This policy will only work for periodic keys and the check is limited to periods
+	/// which are fully after the revocation date.
+	pub fn set_revoked_enc_after(&mut self, action: DecryptionPolicyAction) -> &mut Self {
+		self.revoked_enc_after = Some(action);
+		self
+	}
 }
 
 impl Default for DecryptionPolicy {
@@ -88,66 +224,150 @@ impl Default for DecryptionPolicy {
 			expired_enc: DecryptionPolicyAction::Deny,
 			expired_now: DecryptionPolicyAction::Warn,
 			future_enc: DecryptionPolicyAction::Deny,
+			pending: DecryptionPolicyAction::Warn,
 			revoked: DecryptionPolicyAction::Warn,
+			revoked_enc_before: None,
+			revoked_enc_after: None,
 		}
 	}
 }
 
 macro_rules! policy_match {
-	($m: expr, $err: expr) => {
-		match $m {
+	($decisions: expr, $m: expr, $violation: expr) => {
+		let decision = PolicyDecision {
+			violation: $violation,
+			action: $m,
+		};
+		$decisions.push(decision);
+		match decision.action {
 			DecryptionPolicyAction::Allow => {}
 			DecryptionPolicyAction::Deny => {
-				return Err($err);
+				return Err(decision.violation.into());
 			}
 			DecryptionPolicyAction::Warn => {
-				log::warn!("{}", $err);
+				#[cfg(feature = "tracing")]
+				tracing::warn!(
+					violation = ?decision.violation,
+					"{}",
+					Error::from(decision.violation)
+				);
+				#[cfg(not(feature = "tracing"))]
+				log::warn!("{}", Error::from(decision.violation));
 			}
 		}
 	};
 }
 
+#[cfg(feature = "tracing")]
+pub(crate) fn get_policy_outcome(decisions: &[PolicyDecision]) -> &'static str {
+	let actions = decisions.iter().map(|d| d.action);
+	if actions.clone().any(|a| a == DecryptionPolicyAction::Deny) {
+		"deny"
+	} else if actions.clone().any(|a| a == DecryptionPolicyAction::Warn) {
+		"warn"
+	} else if decisions.is_empty() {
+		"pass"
+	} else {
+		"allow"
+	}
+}
+
 fn process_check(
 	policy: &DecryptionPolicy,
 	ikm: &InputKeyMaterial,
 	key_ctx: &KeyContext,
 	time_period: Option<u64>,
-	curr_time: SystemTime,
+	curr_time: Timestamp,
 ) -> Result<()> {
+	process_check_with_decisions(
+		policy,
+		ikm,
+		key_ctx,
+		time_period,
+		curr_time,
+		&mut Vec::new(),
+	)
+}
+
+fn process_check_with_decisions(
+	policy: &DecryptionPolicy,
+	ikm: &InputKeyMaterial,
+	key_ctx: &KeyContext,
+	time_period: Option<u64>,
+	curr_time: Timestamp,
+	decisions: &mut Vec<PolicyDecision>,
+) -> Result<()> {
+	// Check the IKM lifecycle state
+	match ikm.get_state() {
+		IkmState::Destroyed => {
+			policy_match!(
+				decisions,
+				DecryptionPolicyAction::Deny,
+				PolicyViolation::Destroyed
+			);
+		}
+		IkmState::Pending => {
+			policy_match!(decisions, policy.pending, PolicyViolation::Pending);
+		}
+		IkmState::Active | IkmState::DecryptOnly => {}
+	}
+
 	// Check for a revoked IKM
 	if ikm.is_revoked() {
-		policy_match!(policy.revoked, Error::PolicyDecryptionRevoked);
+		let revocation_tp = match ikm.get_revoked_at() {
+			Some(revoked_at) => key_ctx.get_time_period(revoked_at.as_secs()),
+			None => None,
+		};
+		match (time_period, revocation_tp) {
+			(Some(tp), Some(rev_tp)) if tp < rev_tp => {
+				policy_match!(
+					decisions,
+					policy.revoked_enc_before.unwrap_or(policy.revoked),
+					PolicyViolation::RevokedEncBefore
+				);
+			}
+			(Some(tp), Some(rev_tp)) if tp > rev_tp => {
+				policy_match!(
+					decisions,
+					policy.revoked_enc_after.unwrap_or(policy.revoked),
+					PolicyViolation::RevokedEncAfter
+				);
+			}
+			_ => {
+				policy_match!(decisions, policy.revoked, PolicyViolation::Revoked);
+			}
+		}
 	}
 
 	// Check for a now expired IKM
-	if curr_time > ikm.get_not_after() {
-		policy_match!(policy.expired_now, Error::PolicyDecryptionExpiredNow);
+	if curr_time >= ikm.get_not_after() {
+		policy_match!(decisions, policy.expired_now, PolicyViolation::ExpiredNow);
 	}
 
 	// Checks depending on the encryption time period.
 	if let Some(tp) = time_period {
 		// Check for an expired IKM at encryption
-		let max_ts = ikm.get_not_after().duration_since(UNIX_EPOCH)?.as_secs();
+		let max_ts = ikm.get_not_after().as_secs();
 		if let Some(max_tp) = key_ctx.get_time_period(max_ts)
 			&& tp > max_tp
 		{
-			policy_match!(policy.expired_enc, Error::PolicyDecryptionExpiredEnc);
+			policy_match!(decisions, policy.expired_enc, PolicyViolation::ExpiredEnc);
 		}
 
 		// Check for an encryption before the IKM validity
-		let min_ts = ikm.get_not_before().duration_since(UNIX_EPOCH)?.as_secs();
+		let min_ts = ikm.get_not_before().as_secs();
 		if let Some(min_tp) = key_ctx.get_time_period(min_ts)
 			&& tp < min_tp
 		{
-			policy_match!(policy.early_enc, Error::PolicyDecryptionEarly);
+			policy_match!(decisions, policy.early_enc, PolicyViolation::EarlyEnc);
 		}
 
 		// Check for an encryption in the future
-		let curr_ts = curr_time.duration_since(UNIX_EPOCH)?.as_secs();
+		let curr_ts = curr_time.as_secs();
 		if let Some(max_tp) = key_ctx.get_time_period(curr_ts)
 			&& tp > max_tp
 		{
-			policy_match!(policy.future_enc, Error::PolicyDecryptionFuture);
+			policy_match!(decisions, policy.future_enc, PolicyViolation::FutureEnc);
 		}
 	}
 	Ok(())
@@ -170,9 +390,13 @@ mod tests {
 			id: 42,
 			scheme,
 			content: Vec::new(),
-			not_before: UNIX_EPOCH + Duration::from_secs(1_680_321_720),
-			not_after: UNIX_EPOCH + Duration::from_secs(1_696_132_920),
+			not_before: Timestamp::UNIX_EPOCH + Duration::from_secs(1_680_321_720),
+			not_after: Timestamp::UNIX_EPOCH + Duration::from_secs(1_696_132_920),
 			is_revoked: false,
+			revoked_at: None,
+			revocation_reason: Default::default(),
+			state: Default::default(),
+			content_digest: None,
 		}
 	}
 
@@ -187,12 +411,12 @@ mod tests {
 		let policy = DecryptionPolicy::default();
 		let ikm = get_ikm();
 		let ctx = get_ctx();
-		let now = UNIX_EPOCH + Duration::from_secs(1_686_377_340);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_686_377_340);
 		let res = process_check(&policy, &ikm, &ctx, None, now);
 		assert!(res.is_ok(), "failed without time period: {res:?}");
 		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
 		assert!(res.is_ok(), "failed with time period: {res:?}");
-		let now = UNIX_EPOCH + Duration::from_secs(1_696_132_020);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_696_132_020);
 		let res = process_check(&policy, &ikm, &ctx, Some(218), now);
 		assert!(res.is_ok(), "failed with time period: {res:?}");
 	}
@@ -204,7 +428,7 @@ mod tests {
 		let mut ikm = get_ikm();
 		ikm.is_revoked = true;
 		let ctx = get_ctx();
-		let now = UNIX_EPOCH + Duration::from_secs(1_686_377_340);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_686_377_340);
 		let res = process_check(&policy, &ikm, &ctx, None, now);
 		assert_eq!(
 			res,
@@ -219,13 +443,136 @@ mod tests {
 		);
 	}
 
+	#[test]
+	fn ikm_state() {
+		let mut policy = DecryptionPolicy::default();
+		let mut ikm = get_ikm();
+		let ctx = get_ctx();
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_686_377_340);
+		ikm.state = IkmState::DecryptOnly;
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert!(res.is_ok(), "failed with decrypt-only IKM: {res:?}");
+		ikm.state = IkmState::Pending;
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert!(res.is_ok(), "failed with pending IKM: {res:?}");
+		policy.set_pending(DecryptionPolicyAction::Deny);
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert_eq!(res, Err(Error::PolicyDecryptionPending));
+		ikm.state = IkmState::Destroyed;
+		policy.set_pending(DecryptionPolicyAction::Allow);
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert_eq!(res, Err(Error::PolicyDecryptionDestroyed));
+	}
+
+	#[test]
+	fn ikm_revoked_enc() {
+		let mut policy = DecryptionPolicy::default();
+		policy.set_revoked(DecryptionPolicyAction::Deny);
+		policy.set_revoked_enc_before(DecryptionPolicyAction::Allow);
+		let mut ikm = get_ikm();
+		ikm.is_revoked = true;
+		ikm.revoked_at = Some(Timestamp::UNIX_EPOCH + Duration::from_secs(1_687_392_100));
+		let ctx = get_ctx();
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_696_132_020);
+		let res = process_check(&policy, &ikm, &ctx, None, now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionRevoked),
+			"failed without time period: {res:?}"
+		);
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert!(res.is_ok(), "failed with time period: {res:?}");
+		let res = process_check(&policy, &ikm, &ctx, Some(217), now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionRevoked),
+			"failed with time period: {res:?}"
+		);
+		let res = process_check(&policy, &ikm, &ctx, Some(218), now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionRevokedEncAfter),
+			"failed with time period: {res:?}"
+		);
+
+		policy.set_revoked_enc_before(DecryptionPolicyAction::Deny);
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionRevokedEncBefore),
+			"failed with time period: {res:?}"
+		);
+	}
+
+	#[test]
+	fn ikm_revoked_enc_default() {
+		let mut policy = DecryptionPolicy::default();
+		policy.set_revoked(DecryptionPolicyAction::Deny);
+		let mut ikm = get_ikm();
+		ikm.is_revoked = true;
+		ikm.revoked_at = Some(Timestamp::UNIX_EPOCH + Duration::from_secs(1_687_392_100));
+		let ctx = get_ctx();
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_696_132_020);
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionRevokedEncBefore),
+			"failed with time period: {res:?}"
+		);
+		let res = process_check(&policy, &ikm, &ctx, Some(218), now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionRevokedEncAfter),
+			"failed with time period: {res:?}"
+		);
+
+		policy.set_revoked(DecryptionPolicyAction::Allow);
+		let res = process_check(&policy, &ikm, &ctx, Some(216), now);
+		assert!(res.is_ok(), "failed with time period: {res:?}");
+		let res = process_check(&policy, &ikm, &ctx, Some(218), now);
+		assert!(res.is_ok(), "failed with time period: {res:?}");
+	}
+
+	#[test]
+	fn decisions() {
+		let mut policy = DecryptionPolicy::default();
+		policy.set_expired_now(DecryptionPolicyAction::Allow);
+		let mut ikm = get_ikm();
+		ikm.is_revoked = true;
+		let ctx = get_ctx();
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_757_525_359);
+		let mut decisions = Vec::new();
+		let res = process_check_with_decisions(&policy, &ikm, &ctx, None, now, &mut decisions);
+		assert!(res.is_ok(), "failed without time period: {res:?}");
+		assert_eq!(
+			decisions,
+			vec![
+				PolicyDecision {
+					violation: PolicyViolation::Revoked,
+					action: DecryptionPolicyAction::Warn,
+				},
+				PolicyDecision {
+					violation: PolicyViolation::ExpiredNow,
+					action: DecryptionPolicyAction::Allow,
+				},
+			]
+		);
+
+		let mut decisions = Vec::new();
+		let res = process_check_with_decisions(&policy, &ikm, &ctx, Some(219), now, &mut decisions);
+		assert_eq!(res, Err(Error::PolicyDecryptionExpiredEnc));
+		assert_eq!(decisions.len(), 3);
+		assert_eq!(decisions[2].get_violation(), PolicyViolation::ExpiredEnc);
+		assert_eq!(decisions[2].get_action(), DecryptionPolicyAction::Deny);
+	}
+
 	#[test]
 	fn ikm_expired_now() {
 		let mut policy = DecryptionPolicy::default();
 		policy.set_expired_now(DecryptionPolicyAction::Deny);
 		let ikm = get_ikm();
 		let ctx = get_ctx();
-		let now = UNIX_EPOCH + Duration::from_secs(1_757_525_359);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_757_525_359);
 		let res = process_check(&policy, &ikm, &ctx, None, now);
 		assert_eq!(
 			res,
@@ -238,6 +585,18 @@ mod tests {
 			Err(Error::PolicyDecryptionExpiredNow),
 			"failed with time period: {res:?}"
 		);
+
+		// The validity period does not include its end
+		let now = ikm.not_after;
+		let res = process_check(&policy, &ikm, &ctx, None, now);
+		assert_eq!(
+			res,
+			Err(Error::PolicyDecryptionExpiredNow),
+			"failed at not_after"
+		);
+		let now = ikm.not_after - Duration::from_secs(1);
+		let res = process_check(&policy, &ikm, &ctx, None, now);
+		assert!(res.is_ok(), "failed before not_after: {res:?}");
 	}
 
 	#[test]
@@ -245,7 +604,7 @@ mod tests {
 		let policy = DecryptionPolicy::default();
 		let ikm = get_ikm();
 		let ctx = get_ctx();
-		let now = UNIX_EPOCH + Duration::from_secs(1_757_525_359);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_757_525_359);
 		let res = process_check(&policy, &ikm, &ctx, None, now);
 		assert!(res.is_ok(), "failed without time period: {res:?}");
 		let res = process_check(&policy, &ikm, &ctx, Some(218), now);
@@ -262,7 +621,7 @@ mod tests {
 		let policy = DecryptionPolicy::default();
 		let ikm = get_ikm();
 		let ctx = get_ctx();
-		let now = UNIX_EPOCH + Duration::from_secs(1_686_377_340);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_686_377_340);
 		let res = process_check(&policy, &ikm, &ctx, None, now);
 		assert!(res.is_ok(), "failed without time period: {res:?}");
 		let res = process_check(&policy, &ikm, &ctx, Some(215), now);
@@ -279,7 +638,7 @@ mod tests {
 		let policy = DecryptionPolicy::default();
 		let ikm = get_ikm();
 		let ctx = get_ctx();
-		let now = UNIX_EPOCH + Duration::from_secs(1_680_321_821);
+		let now = Timestamp::UNIX_EPOCH + Duration::from_secs(1_680_321_821);
 		let res = process_check(&policy, &ikm, &ctx, None, now);
 		assert!(res.is_ok(), "failed without time period: {res:?}");
 		let res = process_check(&policy, &ikm, &ctx, Some(217), now);
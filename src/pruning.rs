@@ -0,0 +1,256 @@
+use crate::ikm::IkmId;
+#[cfg(feature = "std")]
+use crate::{
+	clock::Timestamp,
+	ikm::{InputKeyMaterial, InputKeyMaterialList},
+};
+use alloc::vec::Vec;
+#[cfg(feature = "std")]
+use core::time::Duration;
+
+/// Reason why an IKM has been selected for pruning.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum PruneReason {
+	/// The IKM has expired for longer than the retention period.
+	Expired,
+	/// The IKM has been revoked for longer than the retention period.
+	Revoked,
+}
+
+/// Result of an IKM pruning operation.
+///
+/// When returned by [get_prunable_ikms][InputKeyMaterialList::get_prunable_ikms], the report
+/// describes what would be removed from the list. When returned by
+/// [prune_ikms][InputKeyMaterialList::prune_ikms], it describes what has been removed.
+#[derive(Debug, Default, PartialEq)]
+pub struct PruneReport {
+	pruned: Vec<(IkmId, PruneReason)>,
+	kept_in_use: Vec<IkmId>,
+}
+
+impl PruneReport {
+	/// Returns the identifiers of the pruned IKMs along with the reason they have been selected.
+	pub fn get_pruned(&self) -> &[(IkmId, PruneReason)] {
+		&self.pruned
+	}
+
+	/// Returns the identifiers of the IKMs that would have been pruned but have been kept because
+	/// the usage report states they are still used by stored data.
+	pub fn get_kept_in_use(&self) -> &[IkmId] {
+		&self.kept_in_use
+	}
+
+	/// Check whether or not the pruning operation removes anything.
+	pub fn is_empty(&self) -> bool {
+		self.pruned.is_empty()
+	}
+}
+
+#[cfg(feature = "std")]
+impl InputKeyMaterial {
+	fn get_prune_reason(&self, retention: Duration, now: Timestamp) -> Option<PruneReason> {
+		// A revoked IKM cannot be used to encrypt data after its revocation date. Lists exported
+		// before the revocation date was recorded do not contain it, in which case the retention
+		// period starts at the end of the validity period, which is the latest date the IKM may
+		// have been used.
+		let last_use = match self.revoked_at {
+			Some(revoked_at) if self.is_revoked => revoked_at.min(self.not_after),
+			_ => self.not_after,
+		};
+		let retention_end = last_use.checked_add(retention)?;
+		if retention_end > now {
+			return None;
+		}
+		if self.is_revoked {
+			Some(PruneReason::Revoked)
+		} else {
+			Some(PruneReason::Expired)
+		}
+	}
+}
+
+#[cfg(feature = "std")]
+impl InputKeyMaterialList {
+	/// Dry run of [prune_ikms][Self::prune_ikms]: returns the report of the IKMs that would be
+	/// removed at the specified time, usually the current time, without modifying the list.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{InputKeyMaterialList, DEFAULT_SCHEME, Timestamp};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let year = Duration::from_secs(31_556_925);
+	/// let now = Timestamp::now()?;
+	/// let not_before = now - 3 * year;
+	/// let old_ikm = ikml.add_custom_ikm(DEFAULT_SCHEME, not_before, not_before + year)?;
+	/// let _ = ikml.add_ikm()?;
+	///
+	/// let report = ikml.get_prunable_ikms(year, None, now);
+	/// assert_eq!(report.get_pruned().len(), 1);
+	/// assert_eq!(report.get_pruned()[0].0, old_ikm);
+	/// assert_eq!(ikml.len(), 2);
+	///
+	/// let report = ikml.get_prunable_ikms(year, Some(&[old_ikm]), now);
+	/// assert!(report.is_empty());
+	/// assert_eq!(report.get_kept_in_use(), &[old_ikm]);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn get_prunable_ikms(
+		&self,
+		retention: Duration,
+		in_use: Option<&[IkmId]>,
+		now: Timestamp,
+	) -> PruneReport {
+		self.process_prune_report(retention, in_use, now)
+	}
+
+	/// Remove the IKMs which have either expired or been revoked for longer than the retention
+	/// period at the specified time, usually the current time.
+	///
+	/// If a usage report is provided, which is the list of the IKM identifiers that are still
+	/// referenced by stored data, the IKMs it contains are never removed. Use
+	/// [get_prunable_ikms][Self::get_prunable_ikms] to check what would be removed beforehand.
+	///
+	/// <div class="warning">
+	/// Without a usage report, there is no way to know whether or not the removed IKMs were still
+	/// required to decrypt some data. Make sure the retention period is longer than the time it
+	/// takes to re-encrypt or delete your data.
+	/// </div>
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{InputKeyMaterialList, DEFAULT_SCHEME, Timestamp};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let year = Duration::from_secs(31_556_925);
+	/// let now = Timestamp::now()?;
+	/// let not_before = now - 3 * year;
+	/// let _ = ikml.add_custom_ikm(DEFAULT_SCHEME, not_before, not_before + year)?;
+	/// let _ = ikml.add_ikm()?;
+	///
+	/// let report = ikml.prune_ikms(year, None, now);
+	/// assert_eq!(report.get_pruned().len(), 1);
+	/// assert_eq!(ikml.len(), 1);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn prune_ikms(
+		&mut self,
+		retention: Duration,
+		in_use: Option<&[IkmId]>,
+		now: Timestamp,
+	) -> PruneReport {
+		let report = self.process_prune_report(retention, in_use, now);
+		if !report.is_empty() {
+			self.ikm_lst
+				.retain(|ikm| !report.pruned.iter().any(|(id, _)| *id == ikm.id));
+			self.is_modified = true;
+		}
+		report
+	}
+
+	fn process_prune_report(
+		&self,
+		retention: Duration,
+		in_use: Option<&[IkmId]>,
+		now: Timestamp,
+	) -> PruneReport {
+		let mut report = PruneReport::default();
+		for ikm in &self.ikm_lst {
+			if let Some(reason) = ikm.get_prune_reason(retention, now) {
+				if in_use.is_some_and(|ids| ids.contains(&ikm.id)) {
+					report.kept_in_use.push(ikm.id);
+				} else {
+					report.pruned.push((ikm.id, reason));
+				}
+			}
+		}
+		report
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	const DAY: Duration = Duration::from_secs(86_400);
+
+	fn get_ikm_list(now: Timestamp) -> InputKeyMaterialList {
+		let scheme = crate::DEFAULT_SCHEME;
+		let mut lst = InputKeyMaterialList::new();
+		// 1: expired for 100 days
+		let _ = lst.add_custom_ikm(scheme, now - 200 * DAY, now - 100 * DAY);
+		// 2: expired for 10 days
+		let _ = lst.add_custom_ikm(scheme, now - 110 * DAY, now - 10 * DAY);
+		// 3: revoked and expired for 50 days
+		let _ = lst.add_custom_ikm(scheme, now - 150 * DAY, now - 50 * DAY);
+		let _ = lst.revoke_ikm(3);
+		// 4: revoked but still valid
+		let _ = lst.add_custom_ikm(scheme, now - 10 * DAY, now + 100 * DAY);
+		let _ = lst.revoke_ikm(4);
+		// 5: valid
+		let _ = lst.add_custom_ikm(scheme, now - 10 * DAY, now + 100 * DAY);
+		lst
+	}
+
+	#[test]
+	fn prune_report() {
+		let now = Timestamp::now().unwrap();
+		let lst = get_ikm_list(now);
+		let report = lst.process_prune_report(30 * DAY, None, now);
+		assert_eq!(
+			report.get_pruned(),
+			&[(1, PruneReason::Expired), (3, PruneReason::Revoked)]
+		);
+		assert!(report.get_kept_in_use().is_empty());
+
+		let report = lst.process_prune_report(DAY, None, now);
+		assert_eq!(report.get_pruned().len(), 3);
+
+		let report = lst.process_prune_report(365 * DAY, None, now);
+		assert!(report.is_empty());
+	}
+
+	#[test]
+	fn prune_report_in_use() {
+		let now = Timestamp::now().unwrap();
+		let lst = get_ikm_list(now);
+		let report = lst.process_prune_report(30 * DAY, Some(&[3, 4, 5]), now);
+		assert_eq!(report.get_pruned(), &[(1, PruneReason::Expired)]);
+		assert_eq!(report.get_kept_in_use(), &[3]);
+	}
+
+	#[test]
+	fn prune_report_revoked_at() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let reason = crate::RevocationReason::KeyCompromise;
+		let _ = lst.revoke_ikm_at(4, now - 40 * DAY, reason);
+		let report = lst.process_prune_report(30 * DAY, None, now);
+		assert_eq!(
+			report.get_pruned(),
+			&[
+				(1, PruneReason::Expired),
+				(3, PruneReason::Revoked),
+				(4, PruneReason::Revoked)
+			]
+		);
+	}
+
+	#[test]
+	fn prune_ikms() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let dry_run = lst.get_prunable_ikms(30 * DAY, Some(&[1]), now);
+		assert_eq!(lst.len(), 5);
+		let report = lst.prune_ikms(30 * DAY, Some(&[1]), now);
+		assert_eq!(report, dry_run);
+		assert_eq!(report.get_pruned(), &[(3, PruneReason::Revoked)]);
+		let ids: Vec<IkmId> = lst.iter().map(|ikm| ikm.id).collect();
+		assert_eq!(ids, vec![1, 2, 4, 5]);
+	}
+}
@@ -0,0 +1,140 @@
+use crate::error::Result;
+
+/// Fill the buffer with random bytes.
+///
+/// Unless the `insecure-rng-for-testing` feature is enabled and a random source has been set for
+/// the current thread, the bytes are retrieved from the operating system, or from the custom
+/// getrandom backend provided by the caller on targets without one.
+pub(crate) fn fill(buf: &mut [u8]) -> Result<()> {
+	#[cfg(feature = "insecure-rng-for-testing")]
+	if let Some(res) = insecure::fill(buf) {
+		return res;
+	}
+	getrandom::fill(buf)?;
+	Ok(())
+}
+
+#[cfg(feature = "insecure-rng-for-testing")]
+pub(crate) mod insecure {
+	use crate::error::Result;
+	use rand_chacha::ChaCha20Rng;
+	use rand_chacha::rand_core::{RngCore, SeedableRng};
+	use std::cell::RefCell;
+
+	thread_local! {
+		static RANDOM_SOURCE: RefCell<Option<Box<dyn RandomSource>>> = const { RefCell::new(None) };
+	}
+
+	/// Source of random bytes replacing the operating system's one.
+	///
+	/// **Warning**: this is only meant to produce reproducible test vectors. Using anything but
+	/// the operating system's random source to generate keys and nonces is insecure.
+	pub trait RandomSource {
+		/// Fill the buffer with random bytes.
+		fn fill(&mut self, buf: &mut [u8]) -> Result<()>;
+	}
+
+	/// Deterministic [RandomSource] based on ChaCha20 and initialized with a seed.
+	///
+	/// **Warning**: this is only meant to produce reproducible test vectors. Anyone knowing the
+	/// seed is able to regenerate every key and nonce.
+	pub struct SeededRandomSource {
+		rng: ChaCha20Rng,
+	}
+
+	impl SeededRandomSource {
+		/// Create a new random source using the specified seed.
+		pub fn new(seed: u64) -> Self {
+			Self {
+				rng: ChaCha20Rng::seed_from_u64(seed),
+			}
+		}
+	}
+
+	impl RandomSource for SeededRandomSource {
+		fn fill(&mut self, buf: &mut [u8]) -> Result<()> {
+			self.rng.fill_bytes(buf);
+			Ok(())
+		}
+	}
+
+	struct ResetGuard(Option<Box<dyn RandomSource>>);
+
+	impl Drop for ResetGuard {
+		fn drop(&mut self) {
+			let previous = self.0.take();
+			RANDOM_SOURCE.with(|source| *source.borrow_mut() = previous);
+		}
+	}
+
+	/// Run the specified function using the random source for every random value generated by
+	/// Coffio on the current thread. The previous random source is restored afterwards, even if the
+	/// function panics.
+	///
+	/// **Warning**: this is only meant to produce reproducible test vectors and is therefore only
+	/// available with the `insecure-rng-for-testing` feature, which must never be enabled in
+	/// production.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// # #[cfg(feature = "insecure-rng-for-testing")]
+	/// # {
+	/// use coffio::{InputKeyMaterialList, SeededRandomSource, with_random_source};
+	///
+	/// let gen_list = || {
+	///     let mut ikml = InputKeyMaterialList::new();
+	///     let _ = ikml.add_ikm();
+	///     ikml
+	/// };
+	/// let ikml_1 = with_random_source(SeededRandomSource::new(42), gen_list);
+	/// let ikml_2 = with_random_source(SeededRandomSource::new(42), gen_list);
+	/// assert_eq!(ikml_1[0].get_key_check_value(), ikml_2[0].get_key_check_value());
+	/// # }
+	/// ```
+	pub fn with_random_source<T>(
+		random_source: impl RandomSource + 'static,
+		f: impl FnOnce() -> T,
+	) -> T {
+		let previous =
+			RANDOM_SOURCE.with(|source| source.borrow_mut().replace(Box::new(random_source)));
+		let _guard = ResetGuard(previous);
+		f()
+	}
+
+	pub(super) fn fill(buf: &mut [u8]) -> Option<Result<()>> {
+		RANDOM_SOURCE.with(|source| source.borrow_mut().as_mut().map(|rs| rs.fill(buf)))
+	}
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn fill_random() {
+		let mut a = [0u8; 32];
+		let mut b = [0u8; 32];
+		assert_eq!(fill(&mut a), Ok(()));
+		assert_eq!(fill(&mut b), Ok(()));
+		assert_ne!(a, b);
+	}
+
+	#[test]
+	#[cfg(feature = "insecure-rng-for-testing")]
+	fn seeded_random_source() {
+		use insecure::{SeededRandomSource, with_random_source};
+
+		let gen_bytes = || {
+			let mut a = [0u8; 16];
+			let _ = fill(&mut a);
+			a
+		};
+		let a = with_random_source(SeededRandomSource::new(42), gen_bytes);
+		let b = with_random_source(SeededRandomSource::new(42), gen_bytes);
+		let c = with_random_source(SeededRandomSource::new(43), gen_bytes);
+		assert_eq!(a, b);
+		assert_ne!(a, c);
+		assert_ne!(gen_bytes(), a);
+	}
+}
@@ -0,0 +1,218 @@
+use crate::clock::Timestamp;
+use crate::error::{Error, Result};
+#[cfg(feature = "ikm-management")]
+use crate::ikm::InputKeyMaterialList;
+use crate::ikm::{IkmId, InputKeyMaterial};
+#[cfg(feature = "ikm-management")]
+use alloc::vec::Vec;
+
+pub(crate) const REVOCATION_RECORD_SIZE: usize = 13;
+
+/// Reason why an IKM has been revoked.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+#[repr(u8)]
+pub enum RevocationReason {
+	/// No reason has been given.
+	#[default]
+	Unspecified = 0,
+	/// The IKM, or a key derived from it, has leaked or is suspected to have leaked.
+	KeyCompromise = 1,
+	/// The IKM has been replaced by another one.
+	Superseded = 2,
+	/// The IKM is not used anymore.
+	CessationOfOperation = 3,
+}
+
+impl TryFrom<u8> for RevocationReason {
+	type Error = Error;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			0 => Ok(RevocationReason::Unspecified),
+			1 => Ok(RevocationReason::KeyCompromise),
+			2 => Ok(RevocationReason::Superseded),
+			3 => Ok(RevocationReason::CessationOfOperation),
+			_ => Err(Error::ParsingEncodedDataInvalidRevocation),
+		}
+	}
+}
+
+impl InputKeyMaterial {
+	/// Returns the date from which the IKM is considered revoked.
+	///
+	/// IKM lists exported before revocation dates were recorded do not contain this information,
+	/// hence a revoked IKM may not have any revocation date.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_revoked_at(&self) -> Option<Timestamp> {
+		self.revoked_at
+	}
+
+	/// Returns the reason why the IKM has been revoked, if it has been revoked.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_revocation_reason(&self) -> Option<RevocationReason> {
+		self.is_revoked.then_some(self.revocation_reason)
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn revocation_as_bytes(&self) -> Result<Option<Vec<u8>>> {
+		let revoked_at = match self.revoked_at {
+			Some(revoked_at) if self.is_revoked => revoked_at,
+			_ => return Ok(None),
+		};
+		let mut res = Vec::with_capacity(REVOCATION_RECORD_SIZE);
+		res.extend_from_slice(&self.id.to_le_bytes());
+		res.extend_from_slice(&revoked_at.as_secs().to_le_bytes());
+		res.push(self.revocation_reason as u8);
+		Ok(Some(res))
+	}
+
+	pub(crate) fn apply_revocation_bytes(ikm_lst: &mut [Self], b: &[u8]) -> Result<()> {
+		if b.len() != REVOCATION_RECORD_SIZE {
+			return Err(Error::ParsingEncodedDataInvalidRevocation);
+		}
+		let id = IkmId::from_le_bytes(b[0..4].try_into().unwrap());
+		let ts = u64::from_le_bytes(b[4..12].try_into().unwrap());
+		let ikm = ikm_lst
+			.iter_mut()
+			.find(|ikm| ikm.id == id && ikm.is_revoked)
+			.ok_or(Error::ParsingEncodedDataInvalidRevocation)?;
+		ikm.revoked_at = Some(Timestamp::from_secs(ts));
+		ikm.revocation_reason = b[12].try_into()?;
+		Ok(())
+	}
+}
+
+#[cfg(feature = "ikm-management")]
+impl InputKeyMaterialList {
+	/// Revoke the specified IKM from the list, specifying the date from which it should be
+	/// considered revoked and the reason of the revocation.
+	///
+	/// The revocation date may be in the past, for example the date at which the IKM is suspected
+	/// to have leaked. The [DecryptionPolicy][crate::DecryptionPolicy] is then able to
+	/// distinguish data encrypted before this date from data encrypted after it.
+	///
+	/// If the IKM has already been revoked, its revocation date and reason are updated.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{InputKeyMaterialList, RevocationReason, Timestamp};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// let leak_date = Timestamp::now()? - Duration::from_secs(3_600);
+	/// ikml.revoke_ikm_at(ikm_id, leak_date, RevocationReason::KeyCompromise)?;
+	/// assert!(ikml[0].is_revoked());
+	/// assert_eq!(ikml[0].get_revoked_at(), Some(leak_date));
+	/// assert_eq!(ikml[0].get_revocation_reason(), Some(RevocationReason::KeyCompromise));
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn revoke_ikm_at(
+		&mut self,
+		id: IkmId,
+		revoked_at: Timestamp,
+		reason: RevocationReason,
+	) -> Result<IkmId> {
+		let ikm = self
+			.ikm_lst
+			.iter_mut()
+			.find(|ikm| ikm.id == id)
+			.ok_or(Error::IkmNotFound(id))?;
+		ikm.is_revoked = true;
+		ikm.revoked_at = Some(revoked_at);
+		ikm.revocation_reason = reason;
+		self.is_modified = true;
+		Ok(id)
+	}
+}
+
+#[cfg(all(test, feature = "ikm-management"))]
+mod tests {
+	use super::*;
+	use std::time::Duration;
+
+	#[test]
+	fn revoke_ikm_at() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let now = Timestamp::now().unwrap();
+
+		let res = lst.revoke_ikm(1);
+		assert_eq!(res, Ok(1));
+		assert!(lst[0].get_revoked_at().is_some_and(|t| t >= now));
+		assert_eq!(
+			lst[0].get_revocation_reason(),
+			Some(RevocationReason::Unspecified)
+		);
+
+		let leak_date = now - Duration::from_secs(86_400);
+		let res = lst.revoke_ikm_at(1, leak_date, RevocationReason::KeyCompromise);
+		assert_eq!(res, Ok(1));
+		assert_eq!(lst[0].get_revoked_at(), Some(leak_date));
+		assert_eq!(
+			lst[0].get_revocation_reason(),
+			Some(RevocationReason::KeyCompromise)
+		);
+
+		assert_eq!(lst[1].get_revoked_at(), None);
+		assert_eq!(lst[1].get_revocation_reason(), None);
+		let res = lst.revoke_ikm_at(42, now, RevocationReason::Superseded);
+		assert_eq!(res, Err(Error::IkmNotFound(42)));
+	}
+
+	#[test]
+	fn reinstate_ikm() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.revoke_ikm_at(1, Timestamp::now().unwrap(), RevocationReason::Superseded);
+		let _ = lst.reinstate_ikm(1, "revoked by mistake");
+		assert_eq!(lst[0].get_revoked_at(), None);
+		assert_eq!(lst[0].get_revocation_reason(), None);
+	}
+
+	#[test]
+	fn export_import_revocation() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let revoked_at = Timestamp::UNIX_EPOCH + Duration::from_secs(1_712_475_802);
+		let _ = lst.revoke_ikm_at(2, revoked_at, RevocationReason::KeyCompromise);
+
+		let lst_bis = InputKeyMaterialList::import(&lst.export().unwrap()).unwrap();
+		assert_eq!(lst_bis[0].get_revoked_at(), None);
+		assert!(lst_bis[1].is_revoked());
+		assert_eq!(lst_bis[1].get_revoked_at(), Some(revoked_at));
+		assert_eq!(
+			lst_bis[1].get_revocation_reason(),
+			Some(RevocationReason::KeyCompromise)
+		);
+		assert_eq!(lst_bis[2].get_revoked_at(), None);
+	}
+
+	#[test]
+	fn apply_revocation_bytes_invalid() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let _ = lst.revoke_ikm(2);
+		let tests: &[(&[u8], &str)] = &[
+			(&[], "empty"),
+			(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], "too short"),
+			(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], "too long"),
+			(&[1, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], "not revoked"),
+			(&[3, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0], "unknown IKM"),
+			(&[2, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 42], "invalid reason"),
+		];
+		for (b, error_str) in tests {
+			let res = InputKeyMaterial::apply_revocation_bytes(&mut lst.ikm_lst, b);
+			assert_eq!(
+				res,
+				Err(Error::ParsingEncodedDataInvalidRevocation),
+				"failed error detection: {error_str}"
+			);
+		}
+	}
+}
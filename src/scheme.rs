@@ -5,6 +5,8 @@ use crate::encrypted_data::EncryptedData;
 use crate::error::Result;
 #[cfg(feature = "encryption")]
 use crate::kdf::KdfFunction;
+#[cfg(feature = "encryption")]
+use alloc::{boxed::Box, vec::Vec};
 
 #[cfg(feature = "aes")]
 mod aes;
@@ -42,7 +44,7 @@ pub(crate) type SchemeSerializeType = u32;
 ///   [encrypt][crate::Coffio::encrypt] with a single key, which means you should either rotate
 ///   your IKM or use an appropriate key periodicity before reaching this number. Coffio will neither
 ///   enforce this limit nor count the number of invocations, it is your responsibility to do so.
-#[derive(Copy, Clone, Debug, PartialEq)]
+#[derive(Copy, Clone, Debug, PartialEq, Eq, Hash)]
 pub enum Scheme {
 	/// `default`
 	/// - Key derivation: BLAKE3 derive_key mode
@@ -2,6 +2,7 @@ use crate::encrypted_data::EncryptedData;
 use crate::error::{Error, Result};
 use aes_gcm::aead::{Aead, KeyInit, Payload};
 use aes_gcm::{Aes128Gcm, Key, Nonce};
+use alloc::vec::Vec;
 
 pub(crate) const AES128_KEY_SIZE: usize = 128;
 // 96 bits (12 bytes)
@@ -13,7 +14,7 @@ const NONCE_SIZE: usize = 12;
 
 pub(crate) fn aes128gcm_gen_nonce() -> Result<Vec<u8>> {
 	let mut nonce: [u8; NONCE_SIZE] = [0; NONCE_SIZE];
-	getrandom::fill(&mut nonce)?;
+	crate::rand::fill(&mut nonce)?;
 	Ok(nonce.to_vec())
 }
 
@@ -67,5 +68,8 @@ pub(crate) fn aes128gcm_decrypt(
 
 	// Decrypt the payload and return
 	let cipher = Aes128Gcm::new(key);
-	Ok(cipher.decrypt(nonce, payload)?)
+	// The underlying error is discarded so the caller cannot know which input was wrong
+	cipher
+		.decrypt(nonce, payload)
+		.map_err(|_| Error::DecryptionFailed)
 }
@@ -1,3 +1,5 @@
+use alloc::vec::Vec;
+
 pub(crate) fn blake3_derive(context: &str, ikm: &[u8]) -> Vec<u8> {
 	blake3::derive_key(context, ikm).to_vec()
 }
@@ -1,3 +1,4 @@
+use alloc::vec::Vec;
 use hkdf::Hkdf;
 use sha2::Sha256;
 
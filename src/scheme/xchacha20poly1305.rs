@@ -1,5 +1,6 @@
 use crate::encrypted_data::EncryptedData;
 use crate::error::{Error, Result};
+use alloc::vec::Vec;
 use chacha20poly1305::aead::{Aead, KeyInit, Payload};
 use chacha20poly1305::{Key, XChaCha20Poly1305, XNonce};
 
@@ -9,7 +10,7 @@ const NONCE_SIZE: usize = 24;
 
 pub(crate) fn xchacha20poly1305_gen_nonce() -> Result<Vec<u8>> {
 	let mut nonce: [u8; NONCE_SIZE] = [0; NONCE_SIZE];
-	getrandom::fill(&mut nonce)?;
+	crate::rand::fill(&mut nonce)?;
 	Ok(nonce.to_vec())
 }
 
@@ -63,5 +64,8 @@ pub(crate) fn xchacha20poly1305_decrypt(
 
 	// Decrypt the payload and return
 	let cipher = XChaCha20Poly1305::new(key);
-	Ok(cipher.decrypt(nonce, payload)?)
+	// The underlying error is discarded so the caller cannot know which input was wrong
+	cipher
+		.decrypt(nonce, payload)
+		.map_err(|_| Error::DecryptionFailed)
 }
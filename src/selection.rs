@@ -0,0 +1,208 @@
+use crate::clock::Timestamp;
+use crate::ikm::InputKeyMaterial;
+use crate::scheme::Scheme;
+use core::sync::atomic::{AtomicUsize, Ordering};
+use core::time::Duration;
+
+/// Strategy used by [Coffio][crate::Coffio] to select the IKM used to encrypt data.
+///
+/// The candidates are the IKMs which may be used at the encryption time, which means they are
+/// active, not revoked and within their validity period. They are given in the same order as in
+/// the [InputKeyMaterialList][crate::InputKeyMaterialList], hence the most recently added IKM is
+/// the last one. Returning `None` results in an
+/// [IkmNoneAvailable][crate::Error::IkmNoneAvailable] error.
+///
+/// # Examples
+///
+/// ```
+/// use coffio::{IkmSelectionStrategy, InputKeyMaterial, Timestamp};
+///
+/// /// Use the oldest available IKM.
+/// struct OldestIkm;
+///
+/// impl IkmSelectionStrategy for OldestIkm {
+///     fn select<'a>(
+///         &self,
+///         candidates: &[&'a InputKeyMaterial],
+///         _encryption_time: Timestamp,
+///     ) -> Option<&'a InputKeyMaterial> {
+///         candidates.first().copied()
+///     }
+/// }
+/// ```
+pub trait IkmSelectionStrategy: Send + Sync {
+	/// Select the IKM used to encrypt data among the candidates.
+	fn select<'a>(
+		&self,
+		candidates: &[&'a InputKeyMaterial],
+		encryption_time: Timestamp,
+	) -> Option<&'a InputKeyMaterial>;
+}
+
+/// Select the most recently added IKM. This is the default strategy.
+#[derive(Clone, Copy, Debug, Default)]
+pub struct LatestIkm;
+
+impl IkmSelectionStrategy for LatestIkm {
+	fn select<'a>(
+		&self,
+		candidates: &[&'a InputKeyMaterial],
+		_encryption_time: Timestamp,
+	) -> Option<&'a InputKeyMaterial> {
+		candidates.last().copied()
+	}
+}
+
+/// Select the most recently added IKM using the specified scheme. If there is none, the most
+/// recently added IKM is selected.
+#[derive(Clone, Copy, Debug)]
+pub struct PreferScheme(pub Scheme);
+
+impl IkmSelectionStrategy for PreferScheme {
+	fn select<'a>(
+		&self,
+		candidates: &[&'a InputKeyMaterial],
+		_encryption_time: Timestamp,
+	) -> Option<&'a InputKeyMaterial> {
+		candidates
+			.iter()
+			.rev()
+			.find(|ikm| ikm.scheme == self.0)
+			.or(candidates.last())
+			.copied()
+	}
+}
+
+/// Select the most recently added IKM which does not expire within the specified duration. If
+/// there is none, the most recently added IKM is selected.
+///
+/// This is useful when the encrypted data is expected to be re-encrypted once the IKM expires:
+/// data encrypted right before the expiration would otherwise be re-encrypted shortly after.
+#[derive(Clone, Copy, Debug)]
+pub struct SkipExpiringWithin(pub Duration);
+
+impl IkmSelectionStrategy for SkipExpiringWithin {
+	fn select<'a>(
+		&self,
+		candidates: &[&'a InputKeyMaterial],
+		encryption_time: Timestamp,
+	) -> Option<&'a InputKeyMaterial> {
+		let limit = encryption_time.checked_add(self.0);
+		candidates
+			.iter()
+			.rev()
+			.find(|ikm| limit.is_some_and(|limit| ikm.not_after > limit))
+			.or(candidates.last())
+			.copied()
+	}
+}
+
+/// Select each of the candidates in turn, which spreads the encrypted data across all the
+/// available IKMs and therefore limits the amount of data encrypted using each one of them.
+#[derive(Debug, Default)]
+pub struct RoundRobin {
+	counter: AtomicUsize,
+}
+
+impl RoundRobin {
+	/// Create a new round-robin strategy.
+	pub fn new() -> Self {
+		Self::default()
+	}
+}
+
+impl IkmSelectionStrategy for RoundRobin {
+	fn select<'a>(
+		&self,
+		candidates: &[&'a InputKeyMaterial],
+		_encryption_time: Timestamp,
+	) -> Option<&'a InputKeyMaterial> {
+		if candidates.is_empty() {
+			return None;
+		}
+		let i = self.counter.fetch_add(1, Ordering::Relaxed);
+		candidates.get(i % candidates.len()).copied()
+	}
+}
+
+#[cfg(all(test, feature = "ikm-management"))]
+mod tests {
+	use super::*;
+	use crate::InputKeyMaterialList;
+	use std::time::Duration;
+
+	const DAY: Duration = Duration::from_secs(86_400);
+
+	fn select_id(
+		strategy: &impl IkmSelectionStrategy,
+		lst: &InputKeyMaterialList,
+		now: Timestamp,
+	) -> Option<crate::IkmId> {
+		let candidates = lst.get_usable_ikms(now);
+		strategy.select(&candidates, now).map(|ikm| ikm.id)
+	}
+
+	fn get_ikm_list(now: Timestamp) -> InputKeyMaterialList {
+		let scheme = crate::DEFAULT_SCHEME;
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_custom_ikm(scheme, now - DAY, now + 100 * DAY);
+		let _ = lst.add_custom_ikm(scheme, now - DAY, now + 200 * DAY);
+		let _ = lst.add_custom_ikm(scheme, now - DAY, now + 10 * DAY);
+		let _ = lst.add_custom_ikm(scheme, now + DAY, now + 300 * DAY);
+		lst
+	}
+
+	#[test]
+	fn latest_ikm() {
+		let now = Timestamp::now().unwrap();
+		let lst = get_ikm_list(now);
+		assert_eq!(select_id(&LatestIkm, &lst, now), Some(3));
+		assert_eq!(
+			select_id(&LatestIkm, &InputKeyMaterialList::new(), now),
+			None
+		);
+	}
+
+	#[test]
+	#[cfg(all(feature = "aes", feature = "chacha"))]
+	fn prefer_scheme() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = get_ikm_list(now);
+		let strategy = PreferScheme(Scheme::Aes128GcmWithSha256);
+		assert_eq!(select_id(&strategy, &lst, now), Some(3));
+		let _ = lst.add_custom_ikm(Scheme::Aes128GcmWithSha256, now - DAY, now + DAY);
+		let _ = lst.add_custom_ikm(Scheme::XChaCha20Poly1305WithBlake3, now - DAY, now + DAY);
+		assert_eq!(select_id(&strategy, &lst, now), Some(5));
+	}
+
+	#[test]
+	fn skip_expiring_within() {
+		let now = Timestamp::now().unwrap();
+		let lst = get_ikm_list(now);
+		assert_eq!(select_id(&SkipExpiringWithin(DAY), &lst, now), Some(3));
+		assert_eq!(select_id(&SkipExpiringWithin(30 * DAY), &lst, now), Some(2));
+		assert_eq!(
+			select_id(&SkipExpiringWithin(150 * DAY), &lst, now),
+			Some(2)
+		);
+		assert_eq!(
+			select_id(&SkipExpiringWithin(365 * DAY), &lst, now),
+			Some(3)
+		);
+	}
+
+	#[test]
+	fn round_robin() {
+		let now = Timestamp::now().unwrap();
+		let lst = get_ikm_list(now);
+		let strategy = RoundRobin::new();
+		let ids: Vec<_> = (0..5)
+			.filter_map(|_| select_id(&strategy, &lst, now))
+			.collect();
+		assert_eq!(ids, vec![1, 2, 3, 1, 2]);
+		assert_eq!(
+			select_id(&strategy, &InputKeyMaterialList::new(), now),
+			None
+		);
+	}
+}
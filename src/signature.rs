@@ -0,0 +1,223 @@
+use crate::error::{Error, Result};
+use crate::ikm::InputKeyMaterialList;
+use alloc::string::String;
+use ed25519_dalek::{SECRET_KEY_LENGTH, Signer, SigningKey, Verifier, VerifyingKey};
+
+const SIGNING_KEY_PREFIX: &str = "ikml-sk-v1:";
+const VERIFYING_KEY_PREFIX: &str = "ikml-vk-v1:";
+
+/// Secret key used to sign an exported [InputKeyMaterialList].
+///
+/// Anyone having write access to an exported IKM list can alter it, for example by revoking an IKM
+/// or by extending its validity period. Signing the IKM list allows to detect such modifications:
+/// the list is signed using Ed25519 by the operators who manage the IKMs and the applications only
+/// need the [IkmListVerifyingKey] to check the signature.
+///
+/// <div class="warning">
+/// The signing key must be kept separately from the IKM list. Anyone holding both can alter the
+/// list and sign it again.
+/// </div>
+///
+/// # Examples
+///
+/// ```
+/// use coffio::{IkmListSigningKey, IkmListVerifyingKey};
+///
+/// let signing_key = IkmListSigningKey::generate()?;
+/// let exported_signing_key = signing_key.export();
+/// let exported_verifying_key = signing_key.get_verifying_key().export();
+///
+/// let signing_key = IkmListSigningKey::import(&exported_signing_key)?;
+/// let verifying_key = IkmListVerifyingKey::import(&exported_verifying_key)?;
+/// assert_eq!(signing_key.get_verifying_key(), verifying_key);
+/// # Ok::<(), coffio::Error>(())
+/// ```
+pub struct IkmListSigningKey {
+	key: SigningKey,
+}
+
+impl IkmListSigningKey {
+	/// Generate a new random signing key.
+	pub fn generate() -> Result<Self> {
+		let mut secret = [0u8; SECRET_KEY_LENGTH];
+		crate::rand::fill(&mut secret)?;
+		Ok(Self {
+			key: SigningKey::from_bytes(&secret),
+		})
+	}
+
+	/// Returns the verifying key associated with this signing key.
+	pub fn get_verifying_key(&self) -> IkmListVerifyingKey {
+		IkmListVerifyingKey {
+			key: self.key.verifying_key(),
+		}
+	}
+
+	/// Export the signing key to a displayable string.
+	pub fn export(&self) -> String {
+		crate::storage::encode_key(SIGNING_KEY_PREFIX, self.key.as_bytes())
+	}
+
+	/// Import a signing key.
+	pub fn import(s: &str) -> Result<Self> {
+		let secret = crate::storage::decode_key(SIGNING_KEY_PREFIX, s)?;
+		Ok(Self {
+			key: SigningKey::from_bytes(&secret),
+		})
+	}
+}
+
+/// Public key used to verify the signature of an exported [InputKeyMaterialList].
+///
+/// See [IkmListSigningKey] for more details.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct IkmListVerifyingKey {
+	key: VerifyingKey,
+}
+
+impl IkmListVerifyingKey {
+	/// Export the verifying key to a displayable string.
+	pub fn export(&self) -> String {
+		crate::storage::encode_key(VERIFYING_KEY_PREFIX, self.key.as_bytes())
+	}
+
+	/// Import a verifying key.
+	pub fn import(s: &str) -> Result<Self> {
+		let public = crate::storage::decode_key(VERIFYING_KEY_PREFIX, s)?;
+		let key = VerifyingKey::from_bytes(&public)
+			.map_err(|_| Error::ParsingEncodedDataInvalidSignatureKey)?;
+		Ok(Self { key })
+	}
+}
+
+impl InputKeyMaterialList {
+	/// Export the IKM list to a displayable string which includes a signature of the list.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{IkmListSigningKey, InputKeyMaterialList};
+	///
+	/// let signing_key = IkmListSigningKey::generate()?;
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let _ = ikml.add_ikm()?;
+	/// let exported_ikml = ikml.export_signed(&signing_key)?;
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "ikm-management")]
+	pub fn export_signed(&self, key: &IkmListSigningKey) -> Result<String> {
+		let ikml = crate::storage::encode_ikm_list(self)?;
+		crate::storage::encode_signed_ikm_list(&ikml, |msg| key.key.sign(msg).to_bytes())
+	}
+
+	/// Import an IKM list which has been exported using
+	/// [export_signed][InputKeyMaterialList::export_signed].
+	///
+	/// The list is imported only if its signature is valid. Unsigned lists are rejected.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{IkmListSigningKey, InputKeyMaterialList};
+	///
+	/// let signing_key = IkmListSigningKey::generate()?;
+	/// let verifying_key = signing_key.get_verifying_key();
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let _ = ikml.add_ikm()?;
+	/// let exported_ikml = ikml.export_signed(&signing_key)?;
+	///
+	/// let ikml = InputKeyMaterialList::import_signed(&exported_ikml, &verifying_key)?;
+	/// assert_eq!(ikml.len(), 1);
+	/// assert!(InputKeyMaterialList::import_signed(&ikml.export()?, &verifying_key).is_err());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn import_signed(s: &str, key: &IkmListVerifyingKey) -> Result<Self> {
+		let ikml = crate::storage::decode_signed_ikm_list(s, |msg, signature| {
+			let signature = ed25519_dalek::Signature::from_bytes(signature);
+			key.key.verify(msg, &signature).is_ok()
+		})?;
+		crate::storage::decode_ikm_list(ikml)
+	}
+}
+
+#[cfg(all(test, feature = "ikm-management"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn export_import_keys() {
+		let signing_key = IkmListSigningKey::generate().unwrap();
+		let s = signing_key.export();
+		assert!(s.starts_with(SIGNING_KEY_PREFIX));
+		let signing_key_bis = IkmListSigningKey::import(&s).unwrap();
+		assert_eq!(signing_key.key, signing_key_bis.key);
+
+		let verifying_key = signing_key.get_verifying_key();
+		let s = verifying_key.export();
+		assert!(s.starts_with(VERIFYING_KEY_PREFIX));
+		let verifying_key_bis = IkmListVerifyingKey::import(&s).unwrap();
+		assert_eq!(verifying_key, verifying_key_bis);
+	}
+
+	#[test]
+	fn import_invalid_keys() {
+		let tests = &[
+			("", "empty string"),
+			("ikml-sk-v1:", "empty key"),
+			("ikml-sk-v1:AAAA", "invalid key length"),
+			(
+				"ikml-vk-v1:O2onvM62pC1io6jQKm8Nc2UyFXcd4kOmOsBIoYtZ2ik",
+				"invalid prefix",
+			),
+		];
+		for (s, error_str) in tests {
+			let res = IkmListSigningKey::import(s);
+			assert!(res.is_err(), "failed error detection: {error_str}");
+		}
+	}
+
+	#[test]
+	fn export_import_signed() {
+		let signing_key = IkmListSigningKey::generate().unwrap();
+		let verifying_key = signing_key.get_verifying_key();
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+
+		let s = lst.export_signed(&signing_key).unwrap();
+		assert!(s.starts_with("ikml-sig-v1:"));
+		let res = InputKeyMaterialList::import_signed(&s, &verifying_key);
+		assert!(res.is_ok(), "res: {res:?}");
+		let lst_bis = res.unwrap();
+		assert!(lst.diff(&lst_bis).is_empty());
+
+		let other_key = IkmListSigningKey::generate().unwrap().get_verifying_key();
+		let res = InputKeyMaterialList::import_signed(&s, &other_key);
+		assert_eq!(res.err(), Some(Error::IkmListInvalidSignature));
+	}
+
+	#[test]
+	fn import_signed_tampered() {
+		let signing_key = IkmListSigningKey::generate().unwrap();
+		let verifying_key = signing_key.get_verifying_key();
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let s = lst.export_signed(&signing_key).unwrap();
+
+		// Revoke an IKM in the signed list
+		let _ = lst.revoke_ikm(2);
+		let (signature, _) = s.rsplit_once("ikml-v2:").unwrap();
+		let tampered = format!("{signature}{}", lst.export().unwrap());
+		let res = InputKeyMaterialList::import_signed(&tampered, &verifying_key);
+		assert_eq!(res.err(), Some(Error::IkmListInvalidSignature));
+
+		// Unsigned list
+		let res = InputKeyMaterialList::import_signed(&lst.export().unwrap(), &verifying_key);
+		assert_eq!(res.err(), Some(Error::IkmListInvalidSignature));
+
+		// Signed lists cannot be imported without checking the signature
+		let res = InputKeyMaterialList::import(&s);
+		assert!(res.is_err());
+	}
+}
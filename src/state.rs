@@ -0,0 +1,460 @@
+#[cfg(feature = "ikm-management")]
+use crate::Scheme;
+#[cfg(feature = "ikm-management")]
+use crate::clock::Timestamp;
+use crate::error::{Error, Result};
+#[cfg(feature = "ikm-management")]
+use crate::ikm::InputKeyMaterialList;
+use crate::ikm::{IkmId, InputKeyMaterial};
+#[cfg(all(feature = "ikm-management", feature = "std"))]
+use crate::journal::IkmOperation;
+#[cfg(feature = "ikm-management")]
+use alloc::vec::Vec;
+#[cfg(all(feature = "ikm-management", feature = "std"))]
+use core::time::Duration;
+#[cfg(feature = "ikm-management")]
+use zeroize::Zeroize;
+
+pub(crate) const STATE_RECORD_SIZE: usize = 5;
+const CONTENT_DIGEST_SIZE: usize = 32;
+
+/// Lifecycle state of an [InputKeyMaterial].
+///
+/// The state is independent from the validity period and the revocation status: an IKM is used to
+/// encrypt data only if it is active, within its validity period and not revoked.
+///
+/// The only allowed transitions are:
+/// - pending → active
+/// - active → decrypt-only
+/// - decrypt-only → active, using [reactivate_ikm][InputKeyMaterialList::reactivate_ikm]
+/// - any state except destroyed → destroyed
+#[derive(Clone, Copy, Debug, Default, PartialEq, PartialOrd)]
+#[repr(u8)]
+pub enum IkmState {
+	/// The IKM is distributed but must not be used yet. This allows to make sure every node holds
+	/// the IKM before it starts being used to encrypt data.
+	Pending = 1,
+	/// The IKM may be used to encrypt and decrypt data.
+	#[default]
+	Active = 2,
+	/// The IKM is retired: it may only be used to decrypt data.
+	DecryptOnly = 3,
+	/// The IKM's content has been erased, hence it cannot be used anymore.
+	Destroyed = 4,
+}
+
+impl IkmState {
+	fn can_transition_to(self, state: IkmState) -> bool {
+		matches!(
+			(self, state),
+			(IkmState::Pending, IkmState::Active)
+				| (IkmState::Active, IkmState::DecryptOnly)
+				| (IkmState::Pending, IkmState::Destroyed)
+				| (IkmState::Active, IkmState::Destroyed)
+				| (IkmState::DecryptOnly, IkmState::Destroyed)
+		)
+	}
+}
+
+impl TryFrom<u8> for IkmState {
+	type Error = Error;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			1 => Ok(IkmState::Pending),
+			2 => Ok(IkmState::Active),
+			3 => Ok(IkmState::DecryptOnly),
+			4 => Ok(IkmState::Destroyed),
+			_ => Err(Error::ParsingEncodedDataInvalidIkmState),
+		}
+	}
+}
+
+impl InputKeyMaterial {
+	/// Returns the IKM's lifecycle state.
+	#[cfg(feature = "ikm-management")]
+	pub fn get_state(&self) -> IkmState {
+		self.state
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn state_as_bytes(&self) -> Option<Vec<u8>> {
+		if self.state == IkmState::default() {
+			return None;
+		}
+		let mut res = Vec::with_capacity(STATE_RECORD_SIZE + CONTENT_DIGEST_SIZE);
+		res.extend_from_slice(&self.id.to_le_bytes());
+		res.push(self.state as u8);
+		// The content of a destroyed IKM has been erased, its digest is kept so that copies of
+		// the list can still be compared.
+		if let Some(digest) = self.content_digest {
+			res.extend_from_slice(&digest);
+		}
+		Some(res)
+	}
+
+	pub(crate) fn apply_state_bytes(ikm_lst: &mut [Self], b: &[u8]) -> Result<()> {
+		if b.len() < STATE_RECORD_SIZE {
+			return Err(Error::ParsingEncodedDataInvalidIkmState);
+		}
+		let state: IkmState = b[4].try_into()?;
+		let content_digest = match state {
+			IkmState::Destroyed => Some(
+				b[STATE_RECORD_SIZE..]
+					.try_into()
+					.map_err(|_| Error::ParsingEncodedDataInvalidIkmState)?,
+			),
+			_ if b.len() != STATE_RECORD_SIZE => {
+				return Err(Error::ParsingEncodedDataInvalidIkmState);
+			}
+			_ => None,
+		};
+		let id = IkmId::from_le_bytes(b[0..4].try_into().unwrap());
+		let ikm = ikm_lst
+			.iter_mut()
+			.find(|ikm| ikm.id == id)
+			.ok_or(Error::ParsingEncodedDataInvalidIkmState)?;
+		ikm.state = state;
+		ikm.content_digest = content_digest;
+		Ok(())
+	}
+
+	#[cfg(feature = "ikm-management")]
+	pub(crate) fn set_state(&mut self, state: IkmState) {
+		if state == IkmState::Destroyed && self.content_digest.is_none() {
+			self.content_digest = Some(self.get_content_digest());
+			self.content.as_mut_slice().zeroize();
+		}
+		self.state = state;
+	}
+}
+
+#[cfg(feature = "ikm-management")]
+impl InputKeyMaterialList {
+	/// Change the lifecycle state of the specified IKM.
+	///
+	/// Destroying an IKM erases its content. This cannot be undone and every data encrypted using
+	/// this IKM will be lost. A decrypt-only IKM cannot be activated again using this function, use
+	/// [reactivate_ikm][Self::reactivate_ikm] instead.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{IkmState, InputKeyMaterialList};
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// ikml.set_ikm_state(ikm_id, IkmState::DecryptOnly)?;
+	/// assert_eq!(ikml[0].get_state(), IkmState::DecryptOnly);
+	/// assert!(ikml.set_ikm_state(ikm_id, IkmState::Pending).is_err());
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn set_ikm_state(&mut self, id: IkmId, state: IkmState) -> Result<IkmId> {
+		let ikm = self
+			.ikm_lst
+			.iter_mut()
+			.find(|ikm| ikm.id == id)
+			.ok_or(Error::IkmNotFound(id))?;
+		if !ikm.state.can_transition_to(state) {
+			return Err(Error::IkmInvalidStateTransition(id, ikm.state, state));
+		}
+		ikm.set_state(state);
+		self.is_modified = true;
+		Ok(id)
+	}
+
+	/// Reactivate a decrypt-only IKM so that it may be used to encrypt data again.
+	///
+	/// This is meant to recover from an early retirement. The reason, which cannot be empty, is
+	/// recorded in the list's [journal][Self::get_journal] so that this IKM is not retired again
+	/// when [merging][Self::merge] a copy of the list made before its reactivation.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{IkmState, InputKeyMaterialList};
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_ikm()?;
+	/// ikml.set_ikm_state(ikm_id, IkmState::DecryptOnly)?;
+	/// assert!(ikml.set_ikm_state(ikm_id, IkmState::Active).is_err());
+	/// ikml.reactivate_ikm(ikm_id, "retired too early")?;
+	/// assert_eq!(ikml[0].get_state(), IkmState::Active);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn reactivate_ikm(&mut self, id: IkmId, reason: &str) -> Result<IkmId> {
+		self.process_reactivate_ikm(id, reason, Timestamp::now()?)
+	}
+
+	#[cfg(feature = "std")]
+	fn process_reactivate_ikm(&mut self, id: IkmId, reason: &str, now: Timestamp) -> Result<IkmId> {
+		let pos = self.get_ikm_position(id)?;
+		crate::journal::check_reason(reason)?;
+		let ikm = &mut self.ikm_lst[pos];
+		if ikm.state != IkmState::DecryptOnly {
+			return Err(Error::IkmInvalidStateTransition(
+				id,
+				ikm.state,
+				IkmState::Active,
+			));
+		}
+		ikm.set_state(IkmState::Active);
+		self.add_journal_entry(id, IkmOperation::Reactivated, reason, now);
+		Ok(id)
+	}
+
+	/// Add a new pending IKM to the list, using the same settings as
+	/// [add_ikm][Self::add_ikm].
+	///
+	/// The new IKM is not used to encrypt data until it is activated using
+	/// [set_ikm_state][Self::set_ikm_state]. This allows to distribute the IKM to every node before
+	/// any of them starts using it.
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{IkmState, InputKeyMaterialList};
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let ikm_id = ikml.add_pending_ikm()?;
+	/// assert_eq!(ikml[0].get_state(), IkmState::Pending);
+	///
+	/// // Once every node has been updated:
+	/// ikml.set_ikm_state(ikm_id, IkmState::Active)?;
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn add_pending_ikm(&mut self) -> Result<IkmId> {
+		let not_before = Timestamp::now()?;
+		let not_after = not_before + Duration::from_secs(crate::DEFAULT_IKM_DURATION);
+		self.add_custom_pending_ikm(crate::DEFAULT_SCHEME, not_before, not_after)
+	}
+
+	/// Add a new pending IKM with a specified scheme, `not_before` and `not_after` fields. See
+	/// [add_custom_ikm][Self::add_custom_ikm] and [add_pending_ikm][Self::add_pending_ikm].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{DEFAULT_SCHEME, IkmState, InputKeyMaterialList, Timestamp};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let not_before = Timestamp::now()? + Duration::from_secs(86_400);
+	/// let not_after = not_before + Duration::from_secs(315_569_252);
+	/// let _ = ikml.add_custom_pending_ikm(DEFAULT_SCHEME, not_before, not_after)?;
+	/// assert_eq!(ikml[0].get_state(), IkmState::Pending);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn add_custom_pending_ikm(
+		&mut self,
+		scheme: Scheme,
+		not_before: Timestamp,
+		not_after: Timestamp,
+	) -> Result<IkmId> {
+		self.add_custom_ikm_with_state(scheme, not_before, not_after, IkmState::Pending)
+	}
+
+	/// Add a new pending IKM whose content has been generated outside of coffio. See
+	/// [add_ikm_from_bytes][Self::add_ikm_from_bytes] and [add_pending_ikm][Self::add_pending_ikm].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{DEFAULT_SCHEME, IkmState, InputKeyMaterialList, Timestamp};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let not_before = Timestamp::now()?;
+	/// let not_after = not_before + Duration::from_secs(315_569_252);
+	/// let legacy_secret = [0x2a; 32];
+	/// let ikm_id =
+	///     ikml.add_pending_ikm_from_bytes(DEFAULT_SCHEME, &legacy_secret, not_before, not_after, None)?;
+	/// assert_eq!(ikml[0].get_state(), IkmState::Pending);
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	pub fn add_pending_ikm_from_bytes(
+		&mut self,
+		scheme: Scheme,
+		content: &[u8],
+		not_before: Timestamp,
+		not_after: Timestamp,
+		id: Option<IkmId>,
+	) -> Result<IkmId> {
+		let state = IkmState::Pending;
+		self.add_ikm_from_bytes_with_state(scheme, content, not_before, not_after, id, state)
+	}
+
+	/// Pre-provision pending IKMs so that the list follows a rotation schedule. See
+	/// [schedule_ikm_rotation][Self::schedule_ikm_rotation] and
+	/// [add_pending_ikm][Self::add_pending_ikm].
+	///
+	/// # Examples
+	///
+	/// ```
+	/// use coffio::{DEFAULT_SCHEME, IkmState, InputKeyMaterialList};
+	/// use std::time::Duration;
+	///
+	/// let mut ikml = InputKeyMaterialList::new();
+	/// let day = Duration::from_secs(86_400);
+	/// let new_ikms = ikml.schedule_pending_ikm_rotation(DEFAULT_SCHEME, 30 * day, 60 * day)?;
+	/// assert_eq!(new_ikms.len(), 3);
+	/// assert!(ikml.iter().all(|ikm| ikm.get_state() == IkmState::Pending));
+	/// # Ok::<(), coffio::Error>(())
+	/// ```
+	#[cfg(feature = "std")]
+	pub fn schedule_pending_ikm_rotation(
+		&mut self,
+		scheme: Scheme,
+		interval: Duration,
+		horizon: Duration,
+	) -> Result<Vec<IkmId>> {
+		let state = IkmState::Pending;
+		self.process_schedule_ikm_rotation(scheme, interval, horizon, state, Timestamp::now()?)
+	}
+}
+
+#[cfg(all(test, feature = "ikm-management"))]
+mod tests {
+	use super::*;
+
+	#[test]
+	fn transitions() {
+		let states = [
+			IkmState::Pending,
+			IkmState::Active,
+			IkmState::DecryptOnly,
+			IkmState::Destroyed,
+		];
+		let allowed = [
+			(IkmState::Pending, IkmState::Active),
+			(IkmState::Pending, IkmState::Destroyed),
+			(IkmState::Active, IkmState::DecryptOnly),
+			(IkmState::Active, IkmState::Destroyed),
+			(IkmState::DecryptOnly, IkmState::Destroyed),
+		];
+		for from in states {
+			for to in states {
+				assert_eq!(
+					from.can_transition_to(to),
+					allowed.contains(&(from, to)),
+					"{from:?} -> {to:?}"
+				);
+			}
+		}
+	}
+
+	#[test]
+	fn set_ikm_state() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_pending_ikm();
+		assert_eq!(lst[0].get_state(), IkmState::Pending);
+		assert_eq!(lst.set_ikm_state(1, IkmState::Active), Ok(1));
+		assert_eq!(lst.set_ikm_state(1, IkmState::DecryptOnly), Ok(1));
+		assert_eq!(
+			lst.set_ikm_state(1, IkmState::Pending),
+			Err(Error::IkmInvalidStateTransition(
+				1,
+				IkmState::DecryptOnly,
+				IkmState::Pending
+			))
+		);
+		let kcv = lst[0].get_key_check_value();
+		assert_eq!(lst.set_ikm_state(1, IkmState::Destroyed), Ok(1));
+		assert_eq!(lst[0].get_state(), IkmState::Destroyed);
+		assert!(lst[0].content.iter().all(|b| *b == 0));
+		assert_eq!(lst[0].get_key_check_value(), kcv);
+		assert_eq!(
+			lst.set_ikm_state(42, IkmState::Active),
+			Err(Error::IkmNotFound(42))
+		);
+	}
+
+	#[test]
+	fn add_pending_ikms() {
+		let scheme = crate::DEFAULT_SCHEME;
+		let day = Duration::from_secs(86_400);
+		let now = Timestamp::from_secs(1_712_475_802);
+		let mut lst = InputKeyMaterialList::new();
+		assert_eq!(lst.add_custom_pending_ikm(scheme, now, now + day), Ok(1));
+		let content = [0x2a; 32];
+		let res = lst.add_pending_ikm_from_bytes(scheme, &content, now, now + day, Some(42));
+		assert_eq!(res, Ok(42));
+		let res = lst.process_schedule_ikm_rotation(scheme, day, day, IkmState::Pending, now);
+		assert_eq!(res, Ok(vec![43]));
+		assert!(lst.iter().all(|ikm| ikm.get_state() == IkmState::Pending));
+		assert!(lst.iter().all(|ikm| !ikm.is_usable_at(now)));
+
+		assert_eq!(lst.set_ikm_state(43, IkmState::Active), Ok(43));
+		assert!(lst[2].is_usable_at(now + day));
+	}
+
+	#[test]
+	fn export_import_state() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let _ = lst.add_ikm();
+		let _ = lst.add_pending_ikm();
+		let _ = lst.set_ikm_state(1, IkmState::DecryptOnly);
+		let kcv = lst[2].get_key_check_value();
+		let _ = lst.set_ikm_state(3, IkmState::Destroyed);
+
+		let lst_bis = InputKeyMaterialList::import(&lst.export().unwrap()).unwrap();
+		assert_eq!(lst_bis[0].get_state(), IkmState::DecryptOnly);
+		assert_eq!(lst_bis[1].get_state(), IkmState::Active);
+		assert_eq!(lst_bis[2].get_state(), IkmState::Destroyed);
+		assert_eq!(lst_bis[2].get_key_check_value(), kcv);
+		assert_eq!(lst_bis[3].get_state(), IkmState::Pending);
+	}
+
+	#[test]
+	fn reactivate_ikm() {
+		let now = Timestamp::now().unwrap();
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let res = lst.process_reactivate_ikm(1, "test", now);
+		assert_eq!(
+			res,
+			Err(Error::IkmInvalidStateTransition(
+				1,
+				IkmState::Active,
+				IkmState::Active
+			))
+		);
+		let _ = lst.set_ikm_state(1, IkmState::DecryptOnly);
+		let res = lst.process_reactivate_ikm(1, " ", now);
+		assert_eq!(res, Err(Error::IkmMissingReason));
+		assert_eq!(lst.process_reactivate_ikm(1, "test", now), Ok(1));
+		assert_eq!(lst[0].get_state(), IkmState::Active);
+		let journal = lst.get_journal();
+		assert_eq!(journal.len(), 1);
+		assert_eq!(journal[0].get_operation(), IkmOperation::Reactivated);
+		assert_eq!(journal[0].get_date(), now);
+	}
+
+	#[test]
+	fn apply_state_bytes_invalid() {
+		let mut lst = InputKeyMaterialList::new();
+		let _ = lst.add_ikm();
+		let tests: &[(&[u8], &str)] = &[
+			(&[], "empty"),
+			(&[1, 0, 0, 0], "too short"),
+			(&[1, 0, 0, 0, 1, 0], "too long"),
+			(&[1, 0, 0, 0, 4], "missing digest"),
+			(&[1, 0, 0, 0, 4, 0], "invalid digest"),
+			(&[2, 0, 0, 0, 1], "unknown IKM"),
+			(&[1, 0, 0, 0, 0], "invalid state"),
+			(&[1, 0, 0, 0, 5], "invalid state"),
+		];
+		for (b, error_str) in tests {
+			let res = InputKeyMaterial::apply_state_bytes(&mut lst.ikm_lst, b);
+			assert_eq!(
+				res,
+				Err(Error::ParsingEncodedDataInvalidIkmState),
+				"failed error detection: {error_str}"
+			);
+		}
+	}
+}
@@ -5,24 +5,37 @@ use crate::error::{Error, Result};
 use crate::ikm::IKM_BASE_STRUCT_SIZE;
 #[cfg(feature = "encryption")]
 use crate::ikm::IkmId;
-use crate::ikm::{CounterId, InputKeyMaterial, InputKeyMaterialList};
+use crate::ikm::{CounterId, Generation, InputKeyMaterial, InputKeyMaterialList};
+use crate::journal::IkmJournalEntry;
+#[cfg(feature = "ikm-signature")]
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
 use base64ct::{Base64UrlUnpadded, Encoding};
-use std::fmt;
+use core::fmt;
 
 const STORAGE_SEPARATOR: &str = ":";
+const IKML_V2_HEADER_SIZE: usize = 12;
+#[cfg(feature = "ikm-signature")]
+const SIGNED_IKML_VERSION: &str = "ikml-sig-v1:";
+#[cfg(feature = "ikm-signature")]
+const SIGNATURE_SIZE: usize = ed25519_dalek::SIGNATURE_LENGTH;
 #[cfg(feature = "encryption")]
 const NB_PARTS: usize = 3;
 
 #[derive(Clone, Copy, Debug, Default)]
 enum EncodedIkmlStorageVersion {
-	#[default]
 	V1,
+	#[default]
+	V2,
 }
 
 impl EncodedIkmlStorageVersion {
 	fn strip_prefix(data: &str) -> Result<(Self, &str)> {
-		if let Some(d) = data.strip_prefix(&EncodedIkmlStorageVersion::V1.to_string()) {
-			return Ok((EncodedIkmlStorageVersion::V1, d));
+		for version in [EncodedIkmlStorageVersion::V2, EncodedIkmlStorageVersion::V1] {
+			if let Some(d) = data.strip_prefix(&version.to_string()) {
+				return Ok((version, d));
+			}
 		}
 		Err(Error::ParsingEncodedDataInvalidIkmlVersion)
 	}
@@ -32,6 +45,33 @@ impl fmt::Display for EncodedIkmlStorageVersion {
 	fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
 		match self {
 			Self::V1 => write!(f, "ikml-v1:"),
+			Self::V2 => write!(f, "ikml-v2:"),
+		}
+	}
+}
+
+// Since the V2 format, each part following the header starts with a byte defining the kind of
+// record it contains. Unknown kinds of records are rejected rather than skipped, since dropping
+// them would silently lose data when the list is exported again: adding a new kind of record
+// therefore requires a new version of the format.
+#[derive(Clone, Copy, Debug, PartialEq)]
+enum IkmlRecordType {
+	Ikm = 1,
+	JournalEntry = 2,
+	Revocation = 3,
+	State = 4,
+}
+
+impl TryFrom<u8> for IkmlRecordType {
+	type Error = Error;
+
+	fn try_from(value: u8) -> Result<Self, Self::Error> {
+		match value {
+			1 => Ok(IkmlRecordType::Ikm),
+			2 => Ok(IkmlRecordType::JournalEntry),
+			3 => Ok(IkmlRecordType::Revocation),
+			4 => Ok(IkmlRecordType::State),
+			_ => Err(Error::ParsingEncodedDataInvalidIkmlRecordType(value)),
 		}
 	}
 }
@@ -69,18 +109,43 @@ fn decode_data(s: &str) -> Result<Vec<u8>> {
 	Ok(Base64UrlUnpadded::decode_vec(s)?)
 }
 
+#[cfg(feature = "ikm-management")]
+fn encode_record(record_type: IkmlRecordType, data: &[u8]) -> String {
+	let mut record = Vec::with_capacity(data.len() + 1);
+	record.push(record_type as u8);
+	record.extend_from_slice(data);
+	encode_data(&record)
+}
+
 #[cfg(feature = "ikm-management")]
 pub(crate) fn encode_ikm_list(ikml: &InputKeyMaterialList) -> Result<String> {
 	let version = EncodedIkmlStorageVersion::default().to_string();
 	let data_size = (ikml.ikm_lst.iter().fold(0, |acc, ikm| {
-		version.len() + acc + IKM_BASE_STRUCT_SIZE + ikm.scheme.get_ikm_size()
-	})) + 4;
+		version.len() + acc + IKM_BASE_STRUCT_SIZE + ikm.scheme.get_ikm_size() + 1
+	})) + IKML_V2_HEADER_SIZE;
+	let mut header = Vec::with_capacity(IKML_V2_HEADER_SIZE);
+	header.extend_from_slice(&ikml.id_counter.to_le_bytes());
+	header.extend_from_slice(&ikml.get_generation().to_le_bytes());
 	let mut ret = String::with_capacity(data_size);
 	ret += &version;
-	ret += &encode_data(&ikml.id_counter.to_le_bytes());
+	ret += &encode_data(&header);
 	for ikm in &ikml.ikm_lst {
 		ret += STORAGE_SEPARATOR;
-		ret += &encode_data(&ikm.as_bytes()?);
+		ret += &encode_record(IkmlRecordType::Ikm, &ikm.as_bytes()?);
+	}
+	for ikm in &ikml.ikm_lst {
+		if let Some(revocation) = ikm.revocation_as_bytes()? {
+			ret += STORAGE_SEPARATOR;
+			ret += &encode_record(IkmlRecordType::Revocation, &revocation);
+		}
+		if let Some(state) = ikm.state_as_bytes() {
+			ret += STORAGE_SEPARATOR;
+			ret += &encode_record(IkmlRecordType::State, &state);
+		}
+	}
+	for entry in &ikml.journal {
+		ret += STORAGE_SEPARATOR;
+		ret += &encode_record(IkmlRecordType::JournalEntry, &entry.as_bytes()?);
 	}
 	Ok(ret)
 }
@@ -105,11 +170,18 @@ pub(crate) fn encode_cipher(
 }
 
 pub(crate) fn decode_ikm_list(data: &str) -> Result<InputKeyMaterialList> {
-	let (_version, data) = EncodedIkmlStorageVersion::strip_prefix(data)?;
+	let (version, data) = EncodedIkmlStorageVersion::strip_prefix(data)?;
 	let v: Vec<&str> = data.split(STORAGE_SEPARATOR).collect();
 	if v.is_empty() {
 		return Err(Error::ParsingEncodedDataInvalidIkmListLen(v.len()));
 	}
+	match version {
+		EncodedIkmlStorageVersion::V1 => decode_ikm_list_v1(&v),
+		EncodedIkmlStorageVersion::V2 => decode_ikm_list_v2(&v),
+	}
+}
+
+fn decode_ikm_list_v1(v: &[&str]) -> Result<InputKeyMaterialList> {
 	let id_data = decode_data(v[0])?;
 	if id_data.len() != 4 {
 		return Err(Error::ParsingEncodedDataInvalidIkmListId(id_data));
@@ -124,9 +196,95 @@ pub(crate) fn decode_ikm_list(data: &str) -> Result<InputKeyMaterialList> {
 	Ok(InputKeyMaterialList {
 		ikm_lst,
 		id_counter,
+		..Default::default()
+	})
+}
+
+fn decode_ikm_list_v2(v: &[&str]) -> Result<InputKeyMaterialList> {
+	let header = decode_data(v[0])?;
+	if header.len() != IKML_V2_HEADER_SIZE {
+		return Err(Error::ParsingEncodedDataInvalidIkmListId(header));
+	}
+	let id_counter = CounterId::from_le_bytes(header[0..4].try_into().unwrap());
+	let generation = Generation::from_le_bytes(header[4..12].try_into().unwrap());
+	let mut ikm_lst = Vec::with_capacity(v.len() - 1);
+	let mut journal = Vec::new();
+	for record_str in &v[1..] {
+		let record = decode_data(record_str)?;
+		let (record_type, record_data) = record
+			.split_first()
+			.ok_or(Error::ParsingEncodedDataInvalidIkmLen(0))?;
+		match IkmlRecordType::try_from(*record_type)? {
+			IkmlRecordType::Ikm => ikm_lst.push(InputKeyMaterial::from_bytes(record_data)?),
+			IkmlRecordType::State => {
+				InputKeyMaterial::apply_state_bytes(&mut ikm_lst, record_data)?
+			}
+			IkmlRecordType::Revocation => {
+				InputKeyMaterial::apply_revocation_bytes(&mut ikm_lst, record_data)?
+			}
+			IkmlRecordType::JournalEntry => journal.push(IkmJournalEntry::from_bytes(record_data)?),
+		}
+	}
+	Ok(InputKeyMaterialList {
+		ikm_lst,
+		id_counter,
+		generation,
+		journal,
+		is_modified: false,
 	})
 }
 
+#[cfg(feature = "ikm-signature")]
+pub(crate) fn encode_key(prefix: &str, key: &[u8]) -> String {
+	let mut ret = prefix.to_string();
+	ret += &encode_data(key);
+	ret
+}
+
+#[cfg(feature = "ikm-signature")]
+pub(crate) fn decode_key<const N: usize>(prefix: &str, data: &str) -> Result<[u8; N]> {
+	let data = data
+		.strip_prefix(prefix)
+		.ok_or(Error::ParsingEncodedDataInvalidSignatureKey)?;
+	decode_data(data)?
+		.try_into()
+		.map_err(|_| Error::ParsingEncodedDataInvalidSignatureKey)
+}
+
+#[cfg(all(feature = "ikm-signature", feature = "ikm-management"))]
+pub(crate) fn encode_signed_ikm_list(
+	ikml: &str,
+	sign: impl FnOnce(&[u8]) -> [u8; SIGNATURE_SIZE],
+) -> Result<String> {
+	let version = SIGNED_IKML_VERSION;
+	let signature = sign(format!("{version}{ikml}").as_bytes());
+	let mut ret = String::with_capacity(version.len() + SIGNATURE_SIZE * 2 + ikml.len());
+	ret += version;
+	ret += &encode_data(&signature);
+	ret += STORAGE_SEPARATOR;
+	ret += ikml;
+	Ok(ret)
+}
+
+#[cfg(feature = "ikm-signature")]
+pub(crate) fn decode_signed_ikm_list(
+	data: &str,
+	verify: impl FnOnce(&[u8], &[u8; SIGNATURE_SIZE]) -> bool,
+) -> Result<&str> {
+	let version = SIGNED_IKML_VERSION;
+	let (signature, ikml) = data
+		.strip_prefix(version)
+		.and_then(|d| d.split_once(STORAGE_SEPARATOR))
+		.ok_or(Error::IkmListInvalidSignature)?;
+	let signature: [u8; SIGNATURE_SIZE] = decode_data(signature)?
+		.try_into()
+		.map_err(|_| Error::IkmListInvalidSignature)?;
+	if !verify(format!("{version}{ikml}").as_bytes(), &signature) {
+		return Err(Error::IkmListInvalidSignature);
+	}
+	Ok(ikml)
+}
+
 #[cfg(feature = "encryption")]
 pub(crate) fn decode_cipher(data: &str) -> Result<(IkmId, EncryptedData, Option<u64>)> {
 	let (_version, data) = EncodedDataStorageVersion::strip_prefix(data)?;
@@ -171,6 +329,7 @@ pub(crate) fn decode_cipher(data: &str) -> Result<(IkmId, EncryptedData, Option<
 #[cfg(all(test, feature = "ikm-management"))]
 mod ikm_lst {
 	const TEST_STR: &str = "ikml-v1:BgAAAA:AQAAAAEAAACUAPcqngJ46_HMtJSdIw-WeUtImcCVxOA47n6UIN5K2TbmoVwAAAAANmuEXgAAAAAB:AgAAAAEAAADf7CR8vl_aWOUyfsO0ek0YQr_Yi7L_sJmF2nIt_XOaCzYNal4AAAAAtkBLYAAAAAAA:AwAAAAEAAAAMoNIW9gIGkzegUDEsU3N1Rf_Zz0OMuylUSiQjUzLXqzY0MmAAAAAANsk0iwEAAAAA:BAAAAAEAAABbwRrMz3x3DkfOEFg1BHfLLRHoNqg6d_xGWwdh48hH8rZm9mEAAAAANjy9YwAAAAAA:BQAAAAEAAAA2LwnTgDUF7qn7dy79VA24JSSgo6vllAtU5zmhrxNJu7YIz4sBAAAANoUMjgEAAAAB:BgAAAAEAAAAn0Vqe2f9YRXBt6xVYaeSLs0Gf0S0_5B-hk-a2b0rhlraCJbwAAAAAtlErjAEAAAAA";
+	const TEST_STR_V2: &str = "ikml-v2:BgAAAAEAAAAAAAAA:AQEAAAABAAAAlAD3Kp4CeOvxzLSUnSMPlnlLSJnAlcTgOO5-lCDeStk25qFcAAAAADZrhF4AAAAAAQ:AQIAAAABAAAA3-wkfL5f2ljlMn7DtHpNGEK_2Iuy_7CZhdpyLf1zmgs2DWpeAAAAALZAS2AAAAAAAA:AQMAAAABAAAADKDSFvYCBpM3oFAxLFNzdUX_2c9DjLspVEokI1My16s2NDJgAAAAADbJNIsBAAAAAA:AQQAAAABAAAAW8EazM98dw5HzhBYNQR3yy0R6DaoOnf8RlsHYePIR_K2ZvZhAAAAADY8vWMAAAAAAA:AQUAAAABAAAANi8J04A1Be6p-3cu_VQNuCUkoKOr5ZQLVOc5oa8TSbu2CM-LAQAAADaFDI4BAAAAAQ:AQYAAAABAAAAJ9Fantn_WEVwbesVWGnki7NBn9EtP-QfoZPmtm9K4Za2giW8AAAAALZRK4wBAAAAAA";
 	const TEST_CTN_0: &[u8] = &[
 		0x94, 0x00, 0xf7, 0x2a, 0x9e, 0x02, 0x78, 0xeb, 0xf1, 0xcc, 0xb4, 0x94, 0x9d, 0x23, 0x0f,
 		0x96, 0x79, 0x4b, 0x48, 0x99, 0xc0, 0x95, 0xc4, 0xe0, 0x38, 0xee, 0x7e, 0x94, 0x20, 0xde,
@@ -202,54 +361,40 @@ mod ikm_lst {
 		0xe1, 0x96,
 	];
 
-	macro_rules! as_ts {
-		($systime: expr) => {
-			$systime
-				.duration_since(std::time::SystemTime::UNIX_EPOCH)
-				.unwrap()
-				.as_secs()
-		};
-	}
-
 	#[test]
 	#[cfg(all(feature = "ikm-management", feature = "chacha"))]
 	fn encode() {
-		use std::time::{Duration, SystemTime};
-		let bytes_to_system_time = |ts: u64| {
-			SystemTime::UNIX_EPOCH
-				.checked_add(Duration::from_secs(ts))
-				.unwrap()
-		};
+		use crate::Timestamp;
 		let mut lst = crate::InputKeyMaterialList::new();
 		let _ = lst.add_ikm();
 		lst.ikm_lst[0].content = TEST_CTN_0.to_vec();
-		lst.ikm_lst[0].not_before = bytes_to_system_time(1554114102);
-		lst.ikm_lst[0].not_after = bytes_to_system_time(1585736502);
+		lst.ikm_lst[0].not_before = Timestamp::from_secs(1554114102);
+		lst.ikm_lst[0].not_after = Timestamp::from_secs(1585736502);
 		lst.ikm_lst[0].is_revoked = true;
 		let _ = lst.add_ikm();
 		lst.ikm_lst[1].content = TEST_CTN_1.to_vec();
-		lst.ikm_lst[1].not_before = bytes_to_system_time(1584008502);
-		lst.ikm_lst[1].not_after = bytes_to_system_time(1615544502);
+		lst.ikm_lst[1].not_before = Timestamp::from_secs(1584008502);
+		lst.ikm_lst[1].not_after = Timestamp::from_secs(1615544502);
 		let _ = lst.add_ikm();
 		lst.ikm_lst[2].content = TEST_CTN_2.to_vec();
-		lst.ikm_lst[2].not_before = bytes_to_system_time(1613902902);
-		lst.ikm_lst[2].not_after = bytes_to_system_time(6630459702);
+		lst.ikm_lst[2].not_before = Timestamp::from_secs(1613902902);
+		lst.ikm_lst[2].not_after = Timestamp::from_secs(6630459702);
 		let _ = lst.add_ikm();
 		lst.ikm_lst[3].content = TEST_CTN_3.to_vec();
-		lst.ikm_lst[3].not_before = bytes_to_system_time(1643538102);
-		lst.ikm_lst[3].not_after = bytes_to_system_time(1673346102);
+		lst.ikm_lst[3].not_before = Timestamp::from_secs(1643538102);
+		lst.ikm_lst[3].not_after = Timestamp::from_secs(1673346102);
 		let _ = lst.add_ikm();
 		lst.ikm_lst[4].content = TEST_CTN_4.to_vec();
-		lst.ikm_lst[4].not_before = bytes_to_system_time(6640568502);
-		lst.ikm_lst[4].not_after = bytes_to_system_time(6678152502);
+		lst.ikm_lst[4].not_before = Timestamp::from_secs(6640568502);
+		lst.ikm_lst[4].not_after = Timestamp::from_secs(6678152502);
 		lst.ikm_lst[4].is_revoked = true;
 		let _ = lst.add_ikm();
 		lst.ikm_lst[5].content = TEST_CTN_5.to_vec();
-		lst.ikm_lst[5].not_before = bytes_to_system_time(3156574902);
-		lst.ikm_lst[5].not_after = bytes_to_system_time(6646616502);
+		lst.ikm_lst[5].not_before = Timestamp::from_secs(3156574902);
+		lst.ikm_lst[5].not_after = Timestamp::from_secs(6646616502);
 
 		let s = super::encode_ikm_list(&lst).unwrap();
-		assert_eq!(s, TEST_STR);
+		assert_eq!(s, TEST_STR_V2);
 	}
 
 	#[test]
@@ -258,36 +403,48 @@ mod ikm_lst {
 		let res = super::decode_ikm_list(TEST_STR);
 		assert!(res.is_ok(), "res: {res:?}");
 		let lst = res.unwrap();
+		assert_eq!(lst.generation, 0);
+		check_decoded(lst);
+
+		let res = super::decode_ikm_list(TEST_STR_V2);
+		assert!(res.is_ok(), "res: {res:?}");
+		let lst = res.unwrap();
+		assert_eq!(lst.generation, 1);
+		check_decoded(lst);
+	}
+
+	#[cfg(feature = "chacha")]
+	fn check_decoded(lst: crate::InputKeyMaterialList) {
 		assert_eq!(lst.id_counter, 6);
 		assert_eq!(lst.ikm_lst[0].id, 1);
 		assert_eq!(lst.ikm_lst[0].content, TEST_CTN_0);
-		assert_eq!(as_ts!(lst.ikm_lst[0].not_before), 1554114102);
-		assert_eq!(as_ts!(lst.ikm_lst[0].not_after), 1585736502);
+		assert_eq!(lst.ikm_lst[0].not_before.as_secs(), 1554114102);
+		assert_eq!(lst.ikm_lst[0].not_after.as_secs(), 1585736502);
 		assert_eq!(lst.ikm_lst[0].is_revoked, true);
 		assert_eq!(lst.ikm_lst[1].id, 2);
 		assert_eq!(lst.ikm_lst[1].content, TEST_CTN_1);
-		assert_eq!(as_ts!(lst.ikm_lst[1].not_before), 1584008502);
-		assert_eq!(as_ts!(lst.ikm_lst[1].not_after), 1615544502);
+		assert_eq!(lst.ikm_lst[1].not_before.as_secs(), 1584008502);
+		assert_eq!(lst.ikm_lst[1].not_after.as_secs(), 1615544502);
 		assert_eq!(lst.ikm_lst[1].is_revoked, false);
 		assert_eq!(lst.ikm_lst[2].id, 3);
 		assert_eq!(lst.ikm_lst[2].content, TEST_CTN_2);
-		assert_eq!(as_ts!(lst.ikm_lst[2].not_before), 1613902902);
-		assert_eq!(as_ts!(lst.ikm_lst[2].not_after), 6630459702);
+		assert_eq!(lst.ikm_lst[2].not_before.as_secs(), 1613902902);
+		assert_eq!(lst.ikm_lst[2].not_after.as_secs(), 6630459702);
 		assert_eq!(lst.ikm_lst[2].is_revoked, false);
 		assert_eq!(lst.ikm_lst[3].id, 4);
 		assert_eq!(lst.ikm_lst[3].content, TEST_CTN_3);
-		assert_eq!(as_ts!(lst.ikm_lst[3].not_before), 1643538102);
-		assert_eq!(as_ts!(lst.ikm_lst[3].not_after), 1673346102);
+		assert_eq!(lst.ikm_lst[3].not_before.as_secs(), 1643538102);
+		assert_eq!(lst.ikm_lst[3].not_after.as_secs(), 1673346102);
 		assert_eq!(lst.ikm_lst[3].is_revoked, false);
 		assert_eq!(lst.ikm_lst[4].id, 5);
 		assert_eq!(lst.ikm_lst[4].content, TEST_CTN_4);
-		assert_eq!(as_ts!(lst.ikm_lst[4].not_before), 6640568502);
-		assert_eq!(as_ts!(lst.ikm_lst[4].not_after), 6678152502);
+		assert_eq!(lst.ikm_lst[4].not_before.as_secs(), 6640568502);
+		assert_eq!(lst.ikm_lst[4].not_after.as_secs(), 6678152502);
 		assert_eq!(lst.ikm_lst[4].is_revoked, true);
 		assert_eq!(lst.ikm_lst[5].id, 6);
 		assert_eq!(lst.ikm_lst[5].content, TEST_CTN_5);
-		assert_eq!(as_ts!(lst.ikm_lst[5].not_before), 3156574902);
-		assert_eq!(as_ts!(lst.ikm_lst[5].not_after), 6646616502);
+		assert_eq!(lst.ikm_lst[5].not_before.as_secs(), 3156574902);
+		assert_eq!(lst.ikm_lst[5].not_after.as_secs(), 6646616502);
 		assert_eq!(lst.ikm_lst[5].is_revoked, false);
 	}
 
@@ -302,8 +459,8 @@ mod ikm_lst {
 		let res = super::encode_ikm_list(&lst);
 		assert!(res.is_ok(), "res: {res:?}");
 		let s = res.unwrap();
-		assert!(s.starts_with("ikml-v1:AwAAAA:"));
-		assert_eq!(s.len(), 245);
+		assert!(s.starts_with("ikml-v2:AwAAAAEAAAAAAAAA:"));
+		assert_eq!(s.len(), 261);
 
 		let res = super::decode_ikm_list(&s);
 		assert!(res.is_ok(), "res: {res:?}");
@@ -314,12 +471,12 @@ mod ikm_lst {
 			assert_eq!(lst.ikm_lst[i].scheme, lst2.ikm_lst[i].scheme);
 			assert_eq!(lst.ikm_lst[i].content, lst2.ikm_lst[i].content);
 			assert_eq!(
-				as_ts!(lst.ikm_lst[i].not_before),
-				as_ts!(lst2.ikm_lst[i].not_before)
+				lst.ikm_lst[i].not_before.as_secs(),
+				lst2.ikm_lst[i].not_before.as_secs()
 			);
 			assert_eq!(
-				as_ts!(lst.ikm_lst[i].not_after),
-				as_ts!(lst2.ikm_lst[i].not_after)
+				lst.ikm_lst[i].not_after.as_secs(),
+				lst2.ikm_lst[i].not_after.as_secs()
 			);
 			assert_eq!(lst.ikm_lst[i].is_revoked, lst2.ikm_lst[i].is_revoked);
 		}
@@ -347,6 +504,16 @@ mod ikm_lst {
 				"invalid ikm",
 			),
 			("ikml-v1:BgAAAA:", "empty ikm"),
+			("ikml-v2:BgAAAA", "v1 header"),
+			("ikml-v2:BgAAAAEAAAAAAAAA:", "empty record"),
+			(
+				"ikml-v2:BgAAAAEAAAAAAAAA:AQAAAAEAAACUAPcqngJ46_HMtJSdIw-WeUtImcCVxOA47n6UIN5K2TbmoVwAAAAANmuEXgAAAAAB",
+				"v1 ikm",
+			),
+			(
+				"ikml-v2:BgAAAAEAAAAAAAAA:KgEAAAABAAAAlAD3Kp4CeOvxzLSUnSMPlnlLSJnAlcTgOO5-lCDeStk25qFcAAAAADZrhF4AAAAAAQ",
+				"unknown record type",
+			),
 		];
 		for (s, error_str) in tests {
 			let res = super::decode_ikm_list(s);
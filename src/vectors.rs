@@ -0,0 +1,283 @@
+//! Known-answer test vectors.
+//!
+//! The vectors are stored in `vectors/enc-v1.json` and can be regenerated using:
+//!
+//! ```text
+//! cargo test --lib vectors::tests::generate_vectors -- --ignored
+//! ```
+
+use crate::coffio::Coffio;
+use crate::context::{DataContext, KeyContext};
+use crate::ikm::{IkmId, InputKeyMaterialList};
+use crate::kdf::derive_key;
+use crate::{Scheme, Timestamp, storage};
+use serde_json::{Value, json};
+use std::num::NonZeroU64;
+use std::time::Duration;
+
+const VECTORS_PATH: &str = concat!(env!("CARGO_MANIFEST_DIR"), "/vectors/enc-v1.json");
+const SCHEMES: &[(Scheme, &str, usize)] = &[
+	(
+		Scheme::XChaCha20Poly1305WithBlake3,
+		"XChaCha20Poly1305WithBlake3",
+		24,
+	),
+	(Scheme::Aes128GcmWithSha256, "Aes128GcmWithSha256", 12),
+];
+
+struct TestCase {
+	description: &'static str,
+	ikm_id: IkmId,
+	key_context: &'static [&'static str],
+	periodicity: Option<u64>,
+	encryption_time: u64,
+	data_context: &'static [&'static str],
+	plaintext: Vec<u8>,
+}
+
+fn get_test_cases() -> Vec<TestCase> {
+	vec![
+		TestCase {
+			description: "static key context",
+			ikm_id: 1,
+			key_context: &["db_name", "table_name", "column_name"],
+			periodicity: None,
+			encryption_time: 1_700_000_000,
+			data_context: &["018db876-3d9d-79af-9460-55d17da991d8"],
+			plaintext: b"Lorem ipsum dolor sit amet.".to_vec(),
+		},
+		TestCase {
+			description: "periodic key context using the default periodicity",
+			ikm_id: 2,
+			key_context: &["db_name", "table_name", "column_name"],
+			periodicity: Some(crate::DEFAULT_KEY_CTX_PERIODICITY),
+			encryption_time: 1_700_000_000,
+			data_context: &["018db876-3d9d-79af-9460-55d17da991d8", "some username"],
+			plaintext: b"Lorem ipsum dolor sit amet.".to_vec(),
+		},
+		TestCase {
+			description: "periodic key context using a custom periodicity",
+			ikm_id: 3,
+			key_context: &["db_name", "table_name", "column_name"],
+			periodicity: Some(86_400),
+			encryption_time: 1_712_475_802,
+			data_context: &["b3b21eb1-70d7-4dc6-9a2a-439e17d8491d", "8dfa06bc", "42"],
+			plaintext: b"Hello, World!".to_vec(),
+		},
+		TestCase {
+			description: "empty contexts and empty plaintext",
+			ikm_id: 4,
+			key_context: &[],
+			periodicity: None,
+			encryption_time: 1_700_000_000,
+			data_context: &[],
+			plaintext: Vec::new(),
+		},
+		TestCase {
+			description: "context elements containing separators, empty and non-ASCII elements",
+			ikm_id: 5,
+			key_context: &["db:name", "", "clé"],
+			periodicity: None,
+			encryption_time: 1_700_000_000,
+			data_context: &["", "a:b:c", "ünïcödé 🦀"],
+			plaintext: "ünïcödé 🦀".as_bytes().to_vec(),
+		},
+		TestCase {
+			description: "maximal IKM id and first time period",
+			ikm_id: IkmId::MAX,
+			key_context: &["db_name", "table_name", "column_name"],
+			periodicity: Some(3_600),
+			encryption_time: 1_000,
+			data_context: &["018db876-3d9d-79af-9460-55d17da991d8"],
+			plaintext: b"Lorem ipsum dolor sit amet.".to_vec(),
+		},
+		TestCase {
+			description: "long plaintext",
+			ikm_id: 7,
+			key_context: &["db_name", "table_name", "column_name"],
+			periodicity: None,
+			encryption_time: 1_700_000_000,
+			data_context: &["018db876-3d9d-79af-9460-55d17da991d8"],
+			plaintext: (0..1_024).map(|i| (i % 251) as u8).collect(),
+		},
+	]
+}
+
+fn to_hex(b: &[u8]) -> String {
+	b.iter().map(|b| format!("{b:02x}")).collect()
+}
+
+fn from_hex(s: &str) -> Vec<u8> {
+	(0..s.len())
+		.step_by(2)
+		.map(|i| u8::from_str_radix(&s[i..i + 2], 16).unwrap())
+		.collect()
+}
+
+fn get_key_context(elems: &[&str], periodicity: Option<u64>) -> KeyContext {
+	let mut ctx: KeyContext = elems.into();
+	match periodicity {
+		Some(p) => ctx.set_periodicity(NonZeroU64::new(p).unwrap()),
+		None => ctx.set_static(),
+	}
+	ctx
+}
+
+fn get_ikm_list(scheme: Scheme, ikm_id: IkmId, content: &[u8]) -> InputKeyMaterialList {
+	let mut lst = InputKeyMaterialList::new();
+	let not_after = Timestamp::UNIX_EPOCH + Duration::from_secs(1 << 33);
+	lst.add_ikm_from_bytes(
+		scheme,
+		content,
+		Timestamp::UNIX_EPOCH,
+		not_after,
+		Some(ikm_id),
+	)
+	.unwrap();
+	lst
+}
+
+fn generate_vector(
+	(scheme, scheme_name, nonce_size): (Scheme, &str, usize),
+	index: usize,
+	tc: &TestCase,
+) -> Value {
+	let seed = index as u8;
+	let content: Vec<u8> = (0..scheme.get_ikm_size())
+		.map(|i| seed.wrapping_mul(37).wrapping_add(i as u8))
+		.collect();
+	let lst = get_ikm_list(scheme, tc.ikm_id, &content);
+	let ikm = &lst[0];
+	let nonce: Vec<u8> = (0..nonce_size)
+		.map(|i| {
+			seed.wrapping_mul(91)
+				.wrapping_add(0xa0)
+				.wrapping_add(i as u8)
+		})
+		.collect();
+	let key_ctx = get_key_context(tc.key_context, tc.periodicity);
+	let data_ctx: DataContext = tc.data_context.into();
+	let tp = key_ctx.get_time_period(tc.encryption_time);
+	let key = derive_key(ikm, &key_ctx, tp);
+	let aad = Coffio::generate_aad(ikm.id, &nonce, &key_ctx, &data_ctx, tp);
+	let encrypted_data = (scheme.get_encryption())(&key, &nonce, &tc.plaintext, &aad).unwrap();
+	json!({
+		"description": format!("{scheme_name}: {}", tc.description),
+		"scheme": scheme_name,
+		"scheme_id": scheme as u32,
+		"ikm_id": tc.ikm_id,
+		"ikm": to_hex(&content),
+		"key_context": tc.key_context,
+		"periodicity": tc.periodicity,
+		"encryption_time": tc.encryption_time,
+		"time_period": tp,
+		"data_context": tc.data_context,
+		"nonce": to_hex(&nonce),
+		"plaintext": to_hex(&tc.plaintext),
+		"aad": aad,
+		"derived_key": to_hex(&key),
+		"encrypted_data": storage::encode_cipher(ikm.id, &encrypted_data, tp),
+	})
+}
+
+fn generate_vectors() -> String {
+	let mut vectors = Vec::new();
+	for scheme in SCHEMES {
+		for tc in get_test_cases() {
+			vectors.push(generate_vector(*scheme, vectors.len(), &tc));
+		}
+	}
+	let mut doc = json!({
+		"description": "Coffio enc-v1 known-answer test vectors. Binary values are hex-encoded. The periodicity and the time period are null for static key contexts.",
+		"vectors": vectors,
+	});
+	// The keys order must not depend on whether or not serde_json's `preserve_order` feature is
+	// enabled, which is the case when building the command-line tool.
+	doc.sort_all_objects();
+	serde_json::to_string_pretty(&doc).unwrap() + "\n"
+}
+
+fn check_vector(v: &Value) {
+	let description = v["description"].as_str().unwrap();
+	let scheme = Scheme::try_from(v["scheme_id"].as_u64().unwrap() as u32).unwrap();
+	let ikm_id = v["ikm_id"].as_u64().unwrap() as IkmId;
+	let lst = get_ikm_list(scheme, ikm_id, &from_hex(v["ikm"].as_str().unwrap()));
+	let str_list = |v: &Value| -> Vec<String> {
+		v.as_array()
+			.unwrap()
+			.iter()
+			.map(|e| e.as_str().unwrap().to_string())
+			.collect()
+	};
+	let key_ctx_elems = str_list(&v["key_context"]);
+	let key_ctx_elems: Vec<&str> = key_ctx_elems.iter().map(String::as_str).collect();
+	let key_ctx = get_key_context(&key_ctx_elems, v["periodicity"].as_u64());
+	let data_ctx_elems = str_list(&v["data_context"]);
+	let data_ctx_elems: Vec<&str> = data_ctx_elems.iter().map(String::as_str).collect();
+	let data_ctx: DataContext = data_ctx_elems.as_slice().into();
+	let tp = v["time_period"].as_u64();
+	assert_eq!(
+		key_ctx.get_time_period(v["encryption_time"].as_u64().unwrap()),
+		tp,
+		"{description}: time period"
+	);
+	let nonce = from_hex(v["nonce"].as_str().unwrap());
+	let plaintext = from_hex(v["plaintext"].as_str().unwrap());
+
+	let key = derive_key(&lst[0], &key_ctx, tp);
+	assert_eq!(
+		to_hex(&key),
+		v["derived_key"].as_str().unwrap(),
+		"{description}: derived key"
+	);
+	let aad = Coffio::generate_aad(ikm_id, &nonce, &key_ctx, &data_ctx, tp);
+	assert_eq!(aad, v["aad"].as_str().unwrap(), "{description}: AAD");
+	let encrypted_data = (scheme.get_encryption())(&key, &nonce, &plaintext, &aad).unwrap();
+	let expected = v["encrypted_data"].as_str().unwrap();
+	assert_eq!(
+		storage::encode_cipher(ikm_id, &encrypted_data, tp),
+		expected,
+		"{description}: encrypted data"
+	);
+	let (dec_ikm_id, dec_encrypted_data, dec_tp) = storage::decode_cipher(expected).unwrap();
+	assert_eq!(dec_ikm_id, ikm_id, "{description}: decoded IKM id");
+	assert_eq!(dec_tp, tp, "{description}: decoded time period");
+	assert_eq!(
+		dec_encrypted_data.nonce, nonce,
+		"{description}: decoded nonce"
+	);
+	assert_eq!(
+		dec_encrypted_data.ciphertext, encrypted_data.ciphertext,
+		"{description}: decoded ciphertext"
+	);
+	let res = Coffio::new(&lst).decrypt(&key_ctx, &data_ctx, expected);
+	assert_eq!(res, Ok(plaintext), "{description}: decryption");
+}
+
+#[cfg(test)]
+mod tests {
+	use super::*;
+
+	#[test]
+	#[ignore]
+	fn generate_vectors() {
+		std::fs::write(VECTORS_PATH, super::generate_vectors()).unwrap();
+	}
+
+	#[test]
+	fn vectors_up_to_date() {
+		let vectors = std::fs::read_to_string(VECTORS_PATH).unwrap();
+		assert_eq!(vectors, super::generate_vectors());
+	}
+
+	#[test]
+	fn check_vectors() {
+		let vectors = std::fs::read_to_string(VECTORS_PATH).unwrap();
+		let doc: Value = serde_json::from_str(&vectors).unwrap();
+		let vectors = doc["vectors"].as_array().unwrap();
+		assert_eq!(vectors.len(), SCHEMES.len() * get_test_cases().len());
+		for v in vectors {
+			check_vector(v);
+		}
+	}
+}
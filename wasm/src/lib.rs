@@ -0,0 +1,286 @@
+#![warn(missing_docs)]
+
+//! WebAssembly bindings for [Coffio](https://docs.rs/coffio/), allowing client-side tools such as
+//! a browser-based admin console to inspect and manage IKM lists and to encrypt and decrypt data.
+//!
+//! Errors are thrown as JavaScript `Error` objects named `CoffioError` which have the `code`,
+//! `codeStr` and `kind` properties (see `coffio::Error::get_code`).
+
+use coffio::{
+	DEFAULT_KEY_CTX_PERIODICITY, DataContext, Error, IkmId, IkmListVerifyingKey, KeyContext,
+	Timestamp,
+};
+use js_sys::{Date, Reflect};
+use std::cell::RefCell;
+use std::num::NonZeroU64;
+use std::rc::Rc;
+use wasm_bindgen::prelude::*;
+
+fn to_js_error(error: Error) -> JsValue {
+	let js_error = js_sys::Error::new(&error.to_string());
+	js_error.set_name("CoffioError");
+	let _ = Reflect::set(&js_error, &"code".into(), &error.get_code().into());
+	let _ = Reflect::set(&js_error, &"codeStr".into(), &error.get_code_str().into());
+	let _ = Reflect::set(&js_error, &"kind".into(), &error.get_kind().as_str().into());
+	js_error.into()
+}
+
+fn to_js_date(time: Timestamp) -> Date {
+	let ms = time.as_secs() as f64 * 1000.0;
+	Date::new(&ms.into())
+}
+
+/// Input key material (IKM), without its secret content.
+#[wasm_bindgen]
+pub struct InputKeyMaterial {
+	id: IkmId,
+	scheme: String,
+	state: String,
+	not_before: Timestamp,
+	not_after: Timestamp,
+	revoked_at: Option<Timestamp>,
+	is_revoked: bool,
+	key_check_value: String,
+}
+
+impl From<&coffio::InputKeyMaterial> for InputKeyMaterial {
+	fn from(ikm: &coffio::InputKeyMaterial) -> Self {
+		Self {
+			id: ikm.get_id(),
+			scheme: format!("{:?}", ikm.get_scheme()),
+			state: format!("{:?}", ikm.get_state()),
+			not_before: ikm.get_not_before(),
+			not_after: ikm.get_not_after(),
+			revoked_at: ikm.get_revoked_at(),
+			is_revoked: ikm.is_revoked(),
+			key_check_value: ikm.get_key_check_value(),
+		}
+	}
+}
+
+#[wasm_bindgen]
+impl InputKeyMaterial {
+	/// Identifier of the IKM.
+	#[wasm_bindgen(getter)]
+	pub fn id(&self) -> IkmId {
+		self.id
+	}
+
+	/// Name of the scheme the IKM is bound to.
+	#[wasm_bindgen(getter)]
+	pub fn scheme(&self) -> String {
+		self.scheme.clone()
+	}
+
+	/// Lifecycle state of the IKM.
+	#[wasm_bindgen(getter)]
+	pub fn state(&self) -> String {
+		self.state.clone()
+	}
+
+	/// Date from which the IKM may be used.
+	#[wasm_bindgen(getter, js_name = notBefore)]
+	pub fn not_before(&self) -> Date {
+		to_js_date(self.not_before)
+	}
+
+	/// Date after which the IKM must not be used.
+	#[wasm_bindgen(getter, js_name = notAfter)]
+	pub fn not_after(&self) -> Date {
+		to_js_date(self.not_after)
+	}
+
+	/// Date at which the IKM has been revoked, if known.
+	#[wasm_bindgen(getter, js_name = revokedAt)]
+	pub fn revoked_at(&self) -> Option<Date> {
+		self.revoked_at.map(to_js_date)
+	}
+
+	/// Whether or not the IKM has been revoked.
+	#[wasm_bindgen(getter, js_name = isRevoked)]
+	pub fn is_revoked(&self) -> bool {
+		self.is_revoked
+	}
+
+	/// Non-secret key check value of the IKM.
+	#[wasm_bindgen(getter, js_name = keyCheckValue)]
+	pub fn key_check_value(&self) -> String {
+		self.key_check_value.clone()
+	}
+}
+
+/// List of input key materials (IKM).
+#[wasm_bindgen]
+#[derive(Default)]
+pub struct InputKeyMaterialList {
+	inner: Rc<RefCell<coffio::InputKeyMaterialList>>,
+}
+
+#[wasm_bindgen]
+impl InputKeyMaterialList {
+	/// Create a new empty IKM list.
+	#[wasm_bindgen(constructor)]
+	pub fn new() -> Self {
+		Self::default()
+	}
+
+	/// Import an IKM list from its exported representation.
+	///
+	/// If a verifying key is specified, the IKM list must have been signed using the associated
+	/// signing key. Otherwise, signatures are never verified.
+	pub fn import(s: &str, verifying_key: Option<String>) -> Result<InputKeyMaterialList, JsValue> {
+		let lst = match verifying_key {
+			Some(key) => {
+				let key = IkmListVerifyingKey::import(&key).map_err(to_js_error)?;
+				coffio::InputKeyMaterialList::import_signed(s, &key)
+			}
+			None => coffio::InputKeyMaterialList::import(s),
+		}
+		.map_err(to_js_error)?;
+		Ok(Self {
+			inner: Rc::new(RefCell::new(lst)),
+		})
+	}
+
+	/// Export the IKM list to a string.
+	pub fn export(&self) -> Result<String, JsValue> {
+		self.inner.borrow().export().map_err(to_js_error)
+	}
+
+	/// Add a new random IKM using the default scheme and return its identifier.
+	#[wasm_bindgen(js_name = addIkm)]
+	pub fn add_ikm(&self) -> Result<IkmId, JsValue> {
+		self.inner.borrow_mut().add_ikm().map_err(to_js_error)
+	}
+
+	/// Delete an IKM from the list.
+	#[wasm_bindgen(js_name = deleteIkm)]
+	pub fn delete_ikm(&self, id: IkmId) -> Result<IkmId, JsValue> {
+		self.inner.borrow_mut().delete_ikm(id).map_err(to_js_error)
+	}
+
+	/// Revoke an IKM.
+	#[wasm_bindgen(js_name = revokeIkm)]
+	pub fn revoke_ikm(&self, id: IkmId) -> Result<IkmId, JsValue> {
+		self.inner.borrow_mut().revoke_ikm(id).map_err(to_js_error)
+	}
+
+	/// Reinstate a revoked IKM. The reason is recorded in the IKM list's journal.
+	#[wasm_bindgen(js_name = reinstateIkm)]
+	pub fn reinstate_ikm(&self, id: IkmId, reason: &str) -> Result<IkmId, JsValue> {
+		self.inner
+			.borrow_mut()
+			.reinstate_ikm(id, reason)
+			.map_err(to_js_error)
+	}
+
+	/// Generation of the IKM list. Once modified, a list has the generation it had when it was
+	/// imported, incremented by one.
+	#[wasm_bindgen(getter)]
+	pub fn generation(&self) -> f64 {
+		self.inner.borrow().get_generation() as f64
+	}
+
+	/// Fingerprint of the IKM list, which does not contain any secret.
+	#[wasm_bindgen(getter)]
+	pub fn fingerprint(&self) -> String {
+		self.inner.borrow().get_fingerprint()
+	}
+
+	/// Number of IKMs in the list.
+	#[wasm_bindgen(getter)]
+	pub fn length(&self) -> usize {
+		self.inner.borrow().len()
+	}
+
+	/// IKMs of the list, without their secret content.
+	#[wasm_bindgen(getter)]
+	pub fn ikms(&self) -> Vec<InputKeyMaterial> {
+		self.inner.borrow().iter().map(Into::into).collect()
+	}
+}
+
+fn get_key_context(elems: &[String], periodicity: &JsValue) -> Result<KeyContext, JsValue> {
+	let elems: Vec<&str> = elems.iter().map(String::as_str).collect();
+	let mut ctx: KeyContext = elems.as_slice().into();
+	if periodicity.is_null() {
+		ctx.set_static();
+		return Ok(ctx);
+	}
+	let periodicity = if periodicity.is_undefined() {
+		NonZeroU64::new(DEFAULT_KEY_CTX_PERIODICITY)
+	} else {
+		periodicity
+			.as_f64()
+			.filter(|p| p.fract() == 0.0 && *p >= 1.0 && *p <= u64::MAX as f64)
+			.and_then(|p| NonZeroU64::new(p as u64))
+	};
+	let periodicity = periodicity.ok_or_else(|| {
+		js_sys::TypeError::new("the key periodicity must be a positive integer or null")
+	})?;
+	ctx.set_periodicity(periodicity);
+	Ok(ctx)
+}
+
+fn get_data_context(elems: &[String]) -> DataContext {
+	let elems: Vec<&str> = elems.iter().map(String::as_str).collect();
+	elems.as_slice().into()
+}
+
+/// Encrypt and decrypt data using the IKMs of an IKM list, using the default decryption policy.
+///
+/// Modifications of the IKM list are taken into account by subsequent operations.
+#[wasm_bindgen]
+pub struct Coffio {
+	ikm_list: Rc<RefCell<coffio::InputKeyMaterialList>>,
+}
+
+#[wasm_bindgen]
+impl Coffio {
+	/// Create a new instance using an IKM list.
+	#[wasm_bindgen(constructor)]
+	pub fn new(ikm_list: &InputKeyMaterialList) -> Self {
+		Self {
+			ikm_list: Rc::clone(&ikm_list.inner),
+		}
+	}
+
+	/// Encrypt data using a key context and a data context, both being arrays of strings.
+	///
+	/// If the key periodicity is `undefined`, the default periodicity is used. If it is `null`,
+	/// the key context is static. Otherwise, the key changes every `keyPeriodicity` seconds.
+	pub fn encrypt(
+		&self,
+		key_context: Vec<String>,
+		data_context: Vec<String>,
+		data: &[u8],
+		key_periodicity: JsValue,
+	) -> Result<String, JsValue> {
+		let key_ctx = get_key_context(&key_context, &key_periodicity)?;
+		let data_ctx = get_data_context(&data_context);
+		let ikm_list = self.ikm_list.borrow();
+		coffio::Coffio::new(&ikm_list)
+			.encrypt(&key_ctx, &data_ctx, data)
+			.map_err(to_js_error)
+	}
+
+	/// Decrypt data using a key context and a data context, both being arrays of strings.
+	///
+	/// The key periodicity follows the same rules as for [encrypt][Self::encrypt] and must be the
+	/// one used for the encryption, otherwise the decryption policy checks depending on the time
+	/// period are not reliable.
+	pub fn decrypt(
+		&self,
+		key_context: Vec<String>,
+		data_context: Vec<String>,
+		encrypted_data: &str,
+		key_periodicity: JsValue,
+	) -> Result<Vec<u8>, JsValue> {
+		let key_ctx = get_key_context(&key_context, &key_periodicity)?;
+		let data_ctx = get_data_context(&data_context);
+		let ikm_list = self.ikm_list.borrow();
+		coffio::Coffio::new(&ikm_list)
+			.decrypt(&key_ctx, &data_ctx, encrypted_data)
+			.map_err(to_js_error)
+	}
+}
@@ -0,0 +1,155 @@
+//! Run using `wasm-pack test --node wasm` from the root of the repository.
+
+#![cfg(target_arch = "wasm32")]
+
+use coffio_wasm::{Coffio, InputKeyMaterialList};
+use js_sys::Reflect;
+use wasm_bindgen::JsValue;
+use wasm_bindgen_test::wasm_bindgen_test;
+
+const TEST_RAW_IKML: &str =
+	"ikml-v1:AQAAAA:AQAAAAEAAAC_vYEw1ujVG5i-CtoPYSzik_6xaAq59odjPm5ij01-e6zz4mUAAAAALJGBiwAAAAAA";
+const TEST_SIGNED_RAW_IKML: &str = "ikml-sig-v1:QG0h7hQHigCTQGFeeImmdwqkJgeqNsj1ROW0C2AcXZ4Eyr-0WZdUF0btC6AlWJe-fx3UvHaNW6Z0IVapeSvzCg:ikml-v2:AQAAAAAAAAAAAAAA:AQEAAAABAAAAv72BMNbo1RuYvgraD2Es4pP-sWgKufaHYz5uYo9Nfnus8-JlAAAAACyRgYsAAAAAAA";
+const TEST_VERIFYING_KEY: &str = "ikml-vk-v1:ikqbo0__36X7seCBYHHkdd96kJSrx-R65v8NnYjyDMk";
+const TEST_OTHER_VERIFYING_KEY: &str = "ikml-vk-v1:uy1kjiGiQoAxJpFYzfSEB_8M7OCsk0HV7rl9kQaDyRs";
+const TEST_CIPHERTEXT: &str = "enc-v1:AQAAAA:qpVDbGvu0wl2tQgfF5jngCWCoCq5d9gj:eTkOSKz9YyvJE8PyT1lAFn4hyeK_0l6tWU4yyHA-7WRCJ9G-HWNpqoKBxg:NgAAAAAAAAA";
+const TEST_DATA: &[u8] = b"Lorem ipsum dolor sit amet.";
+
+fn get_key_ctx() -> Vec<String> {
+	vec![
+		"db_name".to_string(),
+		"table_name".to_string(),
+		"column_name".to_string(),
+	]
+}
+
+fn get_data_ctx() -> Vec<String> {
+	vec!["018db876-3d9d-79af-9460-55d17da991d8".to_string()]
+}
+
+fn get_property(value: &JsValue, name: &str) -> JsValue {
+	Reflect::get(value, &name.into()).unwrap()
+}
+
+#[wasm_bindgen_test]
+fn reference_ciphertext() {
+	let ikml = InputKeyMaterialList::import(TEST_RAW_IKML, None).unwrap();
+	let cb = Coffio::new(&ikml);
+	let res = cb.decrypt(
+		get_key_ctx(),
+		get_data_ctx(),
+		TEST_CIPHERTEXT,
+		JsValue::UNDEFINED,
+	);
+	assert_eq!(res.unwrap(), TEST_DATA);
+}
+
+#[wasm_bindgen_test]
+fn round_trip() {
+	let ikml = InputKeyMaterialList::new();
+	let cb = Coffio::new(&ikml);
+	ikml.add_ikm().unwrap();
+	for periodicity in [JsValue::UNDEFINED, JsValue::NULL, JsValue::from(3600)] {
+		let encrypted = cb
+			.encrypt(
+				get_key_ctx(),
+				get_data_ctx(),
+				TEST_DATA,
+				periodicity.clone(),
+			)
+			.unwrap();
+		let res = cb.decrypt(get_key_ctx(), get_data_ctx(), &encrypted, periodicity);
+		assert_eq!(res.unwrap(), TEST_DATA);
+	}
+	let res = cb.encrypt(get_key_ctx(), get_data_ctx(), TEST_DATA, JsValue::from(0));
+	assert!(res.is_err());
+}
+
+#[wasm_bindgen_test]
+fn errors() {
+	let ikml = InputKeyMaterialList::import(TEST_RAW_IKML, None).unwrap();
+	let cb = Coffio::new(&ikml);
+	let err = cb
+		.decrypt(get_key_ctx(), vec![], TEST_CIPHERTEXT, JsValue::UNDEFINED)
+		.unwrap_err();
+	assert_eq!(get_property(&err, "name"), "CoffioError");
+	assert_eq!(get_property(&err, "code"), 300);
+	assert_eq!(get_property(&err, "codeStr"), "decryption_failed");
+	assert_eq!(get_property(&err, "kind"), "integrity");
+
+	let err = InputKeyMaterialList::import("ikml-v1:invalid", None)
+		.err()
+		.unwrap();
+	assert_eq!(get_property(&err, "kind"), "parse");
+}
+
+#[wasm_bindgen_test]
+fn import_signed() {
+	let key = Some(TEST_VERIFYING_KEY.to_string());
+	let ikml = InputKeyMaterialList::import(TEST_SIGNED_RAW_IKML, key.clone()).unwrap();
+	assert_eq!(ikml.length(), 1);
+
+	let other_key = Some(TEST_OTHER_VERIFYING_KEY.to_string());
+	let err = InputKeyMaterialList::import(TEST_SIGNED_RAW_IKML, other_key)
+		.err()
+		.unwrap();
+	assert_eq!(get_property(&err, "codeStr"), "ikm_list_invalid_signature");
+	assert!(InputKeyMaterialList::import(TEST_RAW_IKML, key).is_err());
+	assert!(InputKeyMaterialList::import(TEST_SIGNED_RAW_IKML, None).is_err());
+}
+
+#[wasm_bindgen_test]
+fn decryption_policy_time_period() {
+	let ikml = InputKeyMaterialList::import(TEST_RAW_IKML, None).unwrap();
+	let cb = Coffio::new(&ikml);
+	let err = cb
+		.decrypt(
+			get_key_ctx(),
+			get_data_ctx(),
+			TEST_CIPHERTEXT,
+			JsValue::from(3600),
+		)
+		.unwrap_err();
+	assert_eq!(get_property(&err, "codeStr"), "policy_early_encryption");
+	assert_eq!(get_property(&err, "kind"), "policy");
+	let res = cb.decrypt(
+		get_key_ctx(),
+		get_data_ctx(),
+		TEST_CIPHERTEXT,
+		JsValue::NULL,
+	);
+	assert_eq!(res.unwrap(), TEST_DATA);
+	let res = cb.decrypt(
+		get_key_ctx(),
+		get_data_ctx(),
+		TEST_CIPHERTEXT,
+		JsValue::from(0),
+	);
+	assert!(res.is_err());
+}
+
+#[wasm_bindgen_test]
+fn ikm_list_management() {
+	let ikml = InputKeyMaterialList::new();
+	assert_eq!(ikml.add_ikm().unwrap(), 1);
+	assert_eq!(ikml.add_ikm().unwrap(), 2);
+	let ikml = InputKeyMaterialList::import(&ikml.export().unwrap(), None).unwrap();
+	let generation = ikml.generation();
+	let fingerprint = ikml.fingerprint();
+	assert_eq!(ikml.revoke_ikm(1).unwrap(), 1);
+	assert_eq!(ikml.generation(), generation + 1.0);
+	assert_ne!(ikml.fingerprint(), fingerprint);
+
+	let ikms = ikml.ikms();
+	assert_eq!(ikms.len(), 2);
+	assert_eq!(ikms[0].id(), 1);
+	assert!(ikms[0].is_revoked());
+	assert!(ikms[0].revoked_at().is_some());
+	assert!(!ikms[1].is_revoked());
+	assert_eq!(ikms[1].state(), "Active");
+	assert!(ikms[1].not_before().get_time() < ikms[1].not_after().get_time());
+
+	assert_eq!(ikml.reinstate_ikm(1, "revoked by mistake").unwrap(), 1);
+	assert_eq!(ikml.delete_ikm(2).unwrap(), 2);
+	assert_eq!(ikml.length(), 1);
+}